@@ -11,7 +11,7 @@ pub mod regalloc;
 pub mod riscv64;
 
 pub use block::MBlock;
-pub use context::{MContext, RawData};
+pub use context::{MContext, RawData, Reloc};
 pub use func::MFunc;
 pub use lower::{LowerConfig, LowerContext, LowerSpec};
 pub use regs::{PReg, RegKind, VReg};
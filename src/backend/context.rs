@@ -14,6 +14,26 @@ pub enum RawData {
     ///
     /// The field is the size of the zero-initialized data.
     Bss(usize),
+    /// Read-only bytes local to the object file, declared in the rodata
+    /// section, e.g., string literals.
+    ReadOnly(Vec<u8>),
+    /// Bytes of the data containing addresses of other symbols, declared in
+    /// the data section.
+    ///
+    /// The addresses are left as zeros in the bytes, and filled by the linker
+    /// according to the relocations, which are sorted by offset.
+    Relocated { bytes: Vec<u8>, relocs: Vec<Reloc> },
+}
+
+/// The address of a label in the data.
+#[derive(Debug, Clone)]
+pub struct Reloc {
+    /// The offset of the address in the bytes.
+    pub offset: usize,
+    /// The size of the address in bytes, either 4 or 8.
+    pub size: usize,
+    /// The label whose address is stored.
+    pub label: MLabel,
 }
 
 pub struct MContext<I>
@@ -63,6 +83,8 @@ where
         self.raw_data.push((label.into(), data));
     }
 
+    pub fn raw_data(&self) -> &[(MLabel, RawData)] { &self.raw_data }
+
     pub fn set_arch(&mut self, arch: impl Into<String>) { self.arch = arch.into(); }
 
     pub fn arch(&self) -> &str { &self.arch }
@@ -128,6 +150,36 @@ where
                     writeln!(f, "\t.zero {}", size)?;
                     writeln!(f)?;
                 }
+                RawData::ReadOnly(bytes) => {
+                    writeln!(f, "\t.section .rodata")?;
+                    writeln!(f, "{}:", label)?;
+                    for byte in bytes.iter() {
+                        writeln!(f, "\t.byte {}", byte)?;
+                    }
+                    writeln!(f)?;
+                }
+                RawData::Relocated { bytes, relocs } => {
+                    writeln!(f, "\t.data")?;
+                    writeln!(f, "\t.global {}", label)?;
+                    writeln!(f, "\t.align 3")?;
+                    writeln!(f, "{}:", label)?;
+                    let mut relocs = relocs.iter().peekable();
+                    let mut offset = 0;
+                    while offset < bytes.len() {
+                        match relocs.next_if(|reloc| reloc.offset == offset) {
+                            Some(reloc) => {
+                                let directive = if reloc.size == 8 { "dword" } else { "word" };
+                                writeln!(f, "\t.{} {}", directive, reloc.label)?;
+                                offset += reloc.size;
+                            }
+                            None => {
+                                writeln!(f, "\t.byte {}", bytes[offset])?;
+                                offset += 1;
+                            }
+                        }
+                    }
+                    writeln!(f)?;
+                }
             }
             writeln!(f)?;
         }
@@ -89,6 +89,13 @@ where
         })
     }
 
+    /// Create an external function, which is only referenced by its label.
+    pub fn new_external(mctx: &mut MContext<I>, label: impl Into<MLabel>) -> Self {
+        let func = Self::new(mctx, label);
+        func.deref_mut(mctx).is_external = true;
+        func
+    }
+
     pub fn is_external(self, mctx: &MContext<I>) -> bool { self.deref(mctx).is_external }
 
     pub fn add_storage_stack_size(self, mctx: &mut MContext<I>, size: u64) {
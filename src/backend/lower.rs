@@ -4,7 +4,7 @@ use std::hash::Hash;
 use super::func::MLabel;
 use super::inst::MInst;
 use super::regs::Reg;
-use super::{MBlock, MContext, MFunc, PReg};
+use super::{MBlock, MContext, MFunc, PReg, RawData, Reloc};
 use crate::infra::linked_list::{CursorStrategy, LinkedListContainer, LinkedListNode};
 use crate::ir;
 
@@ -248,6 +248,23 @@ pub trait LowerSpec: Sized {
 
     fn gen_ret(lower: &mut LowerContext<Self>);
 
+    /// Generate a call to `func`, passing the arguments by the calling
+    /// convention.
+    ///
+    /// The arguments from `num_fixed_args` on are variadic, which may be
+    /// passed differently from the fixed ones.
+    ///
+    /// # Returns
+    ///
+    /// The returned value, if `ret_ty` is not `None`.
+    fn gen_call(
+        lower: &mut LowerContext<Self>,
+        func: MFunc<Self::I>,
+        args: Vec<MValue>,
+        num_fixed_args: usize,
+        ret_ty: Option<ir::Ty>,
+    ) -> Option<MValue>;
+
     // TODO: Add more prototypes if needed
 
     fn gen_func_prologue(lower: &mut LowerContext<Self>, func: MFunc<Self::I>);
@@ -296,6 +313,12 @@ where
             let symbol = func.name(self.ctx);
             let label = MLabel::from(symbol.to_string());
 
+            if func.is_declaration(self.ctx) {
+                let mfunc = MFunc::new_external(&mut self.mctx, label);
+                self.funcs.insert(func, mfunc);
+                continue;
+            }
+
             let mfunc = MFunc::new(&mut self.mctx, label);
             self.funcs.insert(func, mfunc);
 
@@ -306,14 +329,36 @@ where
             }
         }
 
-        // TODO: You also need to translate global slots in IR into data in
-        // machine code
+        // translate global slots in IR into data in machine code
+        for global in self.ctx.globals() {
+            let label = global.name(self.ctx).to_string();
+            let mut bytes = Vec::new();
+            let mut relocs = Vec::new();
+            constant_bytes(self.ctx, global.value(self.ctx), &mut bytes, &mut relocs);
+
+            let data = if !relocs.is_empty() {
+                // Addresses are only known at link time
+                RawData::Relocated { bytes, relocs }
+            } else if global.is_constant(self.ctx) && global.is_private(self.ctx) {
+                RawData::ReadOnly(bytes)
+            } else if bytes.iter().all(|&byte| byte == 0) {
+                // Zero-initialized globals take no space in the object file
+                RawData::Bss(bytes.len())
+            } else {
+                RawData::Bytes(bytes)
+            };
+            self.mctx.add_raw_data(label, data);
+        }
     }
 
     pub fn after_regalloc(&mut self) {
         for func in self.ctx.funcs() {
             let mfunc = self.funcs[&func];
 
+            if mfunc.is_external(&self.mctx) {
+                continue;
+            }
+
             self.curr_func = Some(mfunc);
 
             self.curr_block = Some(mfunc.head(&self.mctx).unwrap());
@@ -332,6 +377,10 @@ where
         for func in self.ctx.funcs() {
             let mfunc = self.funcs[&func];
 
+            if mfunc.is_external(&self.mctx) {
+                continue;
+            }
+
             let storage_size = mfunc.storage_stack_size(&self.mctx) as i64;
             let outgoing_size = mfunc.outgoing_stack_size(&self.mctx) as i64;
 
@@ -430,7 +479,30 @@ where
                 S::gen_store(self, self.lowered[&val], mem_loc);
             }
             Ik::Br => {}
-            Ik::Call => {}
+            Ik::Call => {
+                // The first operand is the callee, and the rest are the arguments.
+                let mut operands = inst.operand_iter(self.ctx);
+                let callee = match operands.next().unwrap().kind(self.ctx) {
+                    ir::ValueKind::Constant {
+                        value: ir::ConstantValue::GlobalRef { name, .. },
+                    } => name.as_str(),
+                    _ => unreachable!("callee should be a function"),
+                };
+                let func = self
+                    .ctx
+                    .funcs()
+                    .find(|func| func.name(self.ctx) == callee)
+                    .unwrap();
+                let mfunc = self.funcs[&func];
+                let args = operands.map(|arg| self.lowered[&arg]).collect();
+                let num_fixed_args = func.params(self.ctx).len();
+
+                let result = inst.result(self.ctx);
+                let ret_ty = result.map(|result| result.ty(self.ctx));
+                if let Some(mval) = S::gen_call(self, mfunc, args, num_fixed_args, ret_ty) {
+                    self.lowered.insert(result.unwrap(), mval);
+                }
+            }
             Ik::Ret => {
                 if inst.operand_iter(self.ctx).next().is_some() {
                     let val = inst.operand(self.ctx, 0);
@@ -446,3 +518,101 @@ where
         }
     }
 }
+
+/// Append the little-endian bytes of a constant in memory to `bytes`.
+///
+/// The address of a global is left as zeros in `bytes`, and a relocation to
+/// it is appended to `relocs`.
+fn constant_bytes(
+    ctx: &ir::Context,
+    constant: &ir::ConstantValue,
+    bytes: &mut Vec<u8>,
+    relocs: &mut Vec<Reloc>,
+) {
+    use ir::ConstantValue as Cv;
+
+    match constant {
+        Cv::Undef { ty } | Cv::AggregateZero { ty } => {
+            let size = ty.bitwidth(ctx).div_ceil(8);
+            bytes.resize(bytes.len() + size, 0);
+        }
+        Cv::Int1 { value, .. } => bytes.push(*value as u8),
+        Cv::Int8 { value, .. } => bytes.push(*value as u8),
+        Cv::Int32 { value, .. } => bytes.extend_from_slice(&value.to_le_bytes()),
+        Cv::Float32 { value, .. } => bytes.extend_from_slice(&value.to_le_bytes()),
+        Cv::Array { elems, .. } => {
+            for elem in elems {
+                constant_bytes(ctx, elem, bytes, relocs);
+            }
+        }
+        Cv::GlobalRef { ty, name, .. } => {
+            let size = ty.bitwidth(ctx).div_ceil(8);
+            relocs.push(Reloc {
+                offset: bytes.len(),
+                size,
+                label: MLabel::from(name.clone()),
+            });
+            bytes.resize(bytes.len() + size, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::riscv64::lower::RvLowerSpec;
+
+    #[test]
+    fn test_lower_zero_globals() {
+        let ctx = ir::parse(
+            "\
+@a = global i32 0
+@b = global [2 x i32] zeroinitializer
+@c = global [2 x i32] [i32 0, i32 1]
+",
+        )
+        .unwrap();
+        let mut lower: LowerContext<RvLowerSpec> = LowerContext::new(&ctx, LowerConfig::default());
+        lower.lower();
+
+        let data: Vec<_> = lower
+            .mctx()
+            .raw_data()
+            .iter()
+            .map(|(label, data)| (label.to_string(), data))
+            .collect();
+        assert!(matches!(data[0], (ref label, RawData::Bss(4)) if label == "a"));
+        assert!(matches!(data[1], (ref label, RawData::Bss(8)) if label == "b"));
+        assert!(matches!(data[2], (ref label, RawData::Bytes(_)) if label == "c"));
+    }
+
+    #[test]
+    fn test_lower_global_refs() {
+        let mut ctx = ir::parse(
+            "\
+@g = global i32 1
+@p = global ptr @g
+@q = global [2 x ptr] [ptr @p, ptr @g]
+",
+        )
+        .unwrap();
+        ctx.set_target_info(ir::TargetInfo { ptr_size: 8 });
+        let mut lower: LowerContext<RvLowerSpec> = LowerContext::new(&ctx, LowerConfig::default());
+        lower.lower();
+
+        let data = lower.mctx().raw_data();
+        let RawData::Relocated { bytes, relocs } = &data[2].1 else {
+            panic!("expected relocations in `q`");
+        };
+        assert_eq!(bytes, &[0; 16]);
+        let relocs: Vec<_> = relocs
+            .iter()
+            .map(|reloc| (reloc.offset, reloc.size, reloc.label.to_string()))
+            .collect();
+        assert_eq!(relocs, [(0, 8, "p".to_string()), (8, 8, "g".to_string())]);
+
+        let asm = lower.mctx().display().to_string();
+        assert!(asm.contains("p:\n\t.dword g\n"));
+        assert!(asm.contains("q:\n\t.dword p\n\t.dword g\n"));
+    }
+}
@@ -5,6 +5,8 @@ use std::fmt;
 pub enum RegKind {
     /// The general purpose register.
     General,
+    /// The floating-point register.
+    Float,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -70,6 +72,7 @@ impl fmt::Display for VReg {
             "{}{}",
             match self.1 {
                 RegKind::General => "$r",
+                RegKind::Float => "$f",
             },
             self.0
         )
@@ -85,6 +85,17 @@ impl RvInst {
         mctx.alloc(data)
     }
 
+    pub fn build_fpu_move(mctx: &mut MContext<Self>, op: FpuMoveOp, rd: Reg, rs: Reg) -> Self {
+        let kind = RvInstKind::FpuMove { op, rd, rs };
+        let data = RvInstData {
+            kind,
+            next: None,
+            prev: None,
+            parent: None,
+        };
+        mctx.alloc(data)
+    }
+
     pub fn build_load(mctx: &mut MContext<Self>, op: LoadOp, rd: Reg, loc: MemLoc) -> Self {
         let kind = RvInstKind::Load { op, rd, loc };
         let data = RvInstData {
@@ -211,6 +222,7 @@ impl RvInst {
             | LoadOp::Lbu
             | LoadOp::Lhu
             | LoadOp::Lwu => mctx.new_vreg(RegKind::General).into(),
+            LoadOp::Flw | LoadOp::Fld => mctx.new_vreg(RegKind::Float).into(),
         };
         let inst = Self::build_load(mctx, op, rd, loc);
         (inst, rd)
@@ -247,6 +259,13 @@ pub enum RvInstKind {
         rs1: Reg,
         rs2: Reg,
     },
+    /// Move bits between registers, where at least one of them is a
+    /// floating-point register.
+    FpuMove {
+        op: FpuMoveOp,
+        rd: Reg,
+        rs: Reg,
+    },
     Load {
         op: LoadOp,
         rd: Reg,
@@ -333,6 +352,9 @@ impl<'a> fmt::Display for DisplayRvInst<'a> {
                     regs::display(*rs2)
                 )
             }
+            Ik::FpuMove { op, rd, rs } => {
+                write!(f, "{} {}, {}", op, regs::display(*rd), regs::display(*rs))
+            }
             Ik::Load { op, rd, loc } => {
                 let slot = match loc {
                     MemLoc::RegOffset { base, offset } => {
@@ -528,6 +550,35 @@ impl fmt::Display for AluOpRRI {
     }
 }
 
+#[derive(Copy, Clone)]
+pub enum FpuMoveOp {
+    /// Move a single-precision float between float registers.
+    FmvS,
+    /// Move a double-precision float between float registers.
+    FmvD,
+    /// Move the low 32 bits of a general register into a float register.
+    FmvWX,
+    /// Move the 64 bits of a general register into a float register.
+    FmvDX,
+    /// Move the bits of a single-precision float into a general register.
+    FmvXW,
+    /// Move the bits of a double-precision float into a general register.
+    FmvXD,
+}
+
+impl fmt::Display for FpuMoveOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FpuMoveOp::FmvS => write!(f, "fmv.s"),
+            FpuMoveOp::FmvD => write!(f, "fmv.d"),
+            FpuMoveOp::FmvWX => write!(f, "fmv.w.x"),
+            FpuMoveOp::FmvDX => write!(f, "fmv.d.x"),
+            FpuMoveOp::FmvXW => write!(f, "fmv.x.w"),
+            FpuMoveOp::FmvXD => write!(f, "fmv.x.d"),
+        }
+    }
+}
+
 #[derive(Copy, Clone)]
 pub enum LoadOp {
     Lb,
@@ -537,6 +588,8 @@ pub enum LoadOp {
     Lbu,
     Lhu,
     Lwu,
+    Flw,
+    Fld,
 }
 
 impl fmt::Display for LoadOp {
@@ -549,6 +602,8 @@ impl fmt::Display for LoadOp {
             LoadOp::Lbu => write!(f, "lbu"),
             LoadOp::Lhu => write!(f, "lhu"),
             LoadOp::Lwu => write!(f, "lwu"),
+            LoadOp::Flw => write!(f, "flw"),
+            LoadOp::Fld => write!(f, "fld"),
         }
     }
 }
@@ -559,6 +614,8 @@ pub enum StoreOp {
     Sh,
     Sw,
     Sd,
+    Fsw,
+    Fsd,
 }
 
 impl fmt::Display for StoreOp {
@@ -568,6 +625,8 @@ impl fmt::Display for StoreOp {
             StoreOp::Sh => write!(f, "sh"),
             StoreOp::Sw => write!(f, "sw"),
             StoreOp::Sd => write!(f, "sd"),
+            StoreOp::Fsw => write!(f, "fsw"),
+            StoreOp::Fsd => write!(f, "fsd"),
         }
     }
 }
@@ -609,6 +668,7 @@ impl MInst for RvInst {
             Ik::Li { .. } => vec![],
             Ik::AluRRI { rs, .. } => vec![*rs],
             Ik::AluRRR { rs1, rs2, .. } => vec![*rs1, *rs2],
+            Ik::FpuMove { rs, .. } => vec![*rs],
             Ik::Load { loc, .. } => match loc {
                 MemLoc::RegOffset { base, .. } => vec![*base],
                 MemLoc::Slot { .. } | MemLoc::Incoming { .. } => {
@@ -660,6 +720,7 @@ impl MInst for RvInst {
             Ik::Li { rd, .. } => vec![*rd],
             Ik::AluRRI { rd, .. } => vec![*rd],
             Ik::AluRRR { rd, .. } => vec![*rd],
+            Ik::FpuMove { rd, .. } => vec![*rd],
             Ik::Load { rd, .. } => vec![*rd],
             Ik::Store { .. } => vec![],
             Ik::Ret => vec![],
@@ -678,6 +739,7 @@ impl MInst for RvInst {
             Ik::Li { rd, .. } => vec![*rd],
             Ik::AluRRI { rd, .. } => vec![*rd],
             Ik::AluRRR { rd, .. } => vec![*rd],
+            Ik::FpuMove { rd, .. } => vec![*rd],
             Ik::Load { rd, .. } => vec![*rd],
             Ik::Store { .. } => vec![],
             Ik::Ret => vec![],
@@ -699,6 +761,7 @@ impl MInst for RvInst {
             Ik::Li { rd, .. } => vec![*rd],
             Ik::AluRRI { rd, rs, .. } => vec![*rd, *rs],
             Ik::AluRRR { rd, rs1, rs2, .. } => vec![*rd, *rs1, *rs2],
+            Ik::FpuMove { rd, rs, .. } => vec![*rd, *rs],
             Ik::Load { rd, loc, .. } => match loc {
                 MemLoc::RegOffset { base, .. } => vec![*rd, *base],
                 MemLoc::Slot { .. } | MemLoc::Incoming { .. } => vec![*rd],
@@ -751,6 +814,7 @@ impl MInst for RvInst {
             Ik::Li { .. }
             | Ik::AluRRI { .. }
             | Ik::AluRRR { .. }
+            | Ik::FpuMove { .. }
             | Ik::Ret
             | Ik::Call { .. }
             | Ik::J { .. }
@@ -808,6 +872,7 @@ impl MInst for RvInst {
             Ik::Li { .. }
             | Ik::AluRRI { .. }
             | Ik::AluRRR { .. }
+            | Ik::FpuMove { .. }
             | Ik::Ret
             | Ik::Call { .. }
             | Ik::J { .. }
@@ -844,6 +909,14 @@ impl MInst for RvInst {
                     *rs2 = to;
                 }
             }
+            Ik::FpuMove { rd, rs, .. } => {
+                if *rd == from {
+                    *rd = to;
+                }
+                if *rs == from {
+                    *rs = to;
+                }
+            }
             Ik::Load { rd, loc, .. } => {
                 if *rd == from {
                     *rd = to;
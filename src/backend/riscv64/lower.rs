@@ -1,6 +1,6 @@
 use super::imm::Imm12;
-use super::inst::{AluOpRRI, AluOpRRR, BrOp, LoadOp, RvInst, StoreOp};
-use super::regs::{self, CALLEE_SAVED_REGS, CALLER_SAVED_REGS};
+use super::inst::{AluOpRRI, AluOpRRR, BrOp, FpuMoveOp, LoadOp, RvInst, StoreOp};
+use super::regs::{self, CALLEE_SAVED_REGS, CALLER_SAVED_REGS, FLOAT_ARG_REGS, INT_ARG_REGS};
 use crate::backend::inst::MInst;
 use crate::backend::lower::{LowerContext, LowerSpec, MValue, MValueKind, MemLoc};
 use crate::backend::regs::Reg;
@@ -50,6 +50,38 @@ impl LowerSpec for RvLowerSpec {
             regs::s10(),
             regs::s11(),
             regs::ra(),
+            regs::ft0(),
+            regs::ft1(),
+            regs::ft2(),
+            regs::ft3(),
+            regs::ft4(),
+            regs::ft5(),
+            regs::ft6(),
+            regs::ft7(),
+            regs::ft8(),
+            regs::ft9(),
+            regs::ft10(),
+            regs::ft11(),
+            regs::fa0(),
+            regs::fa1(),
+            regs::fa2(),
+            regs::fa3(),
+            regs::fa4(),
+            regs::fa5(),
+            regs::fa6(),
+            regs::fa7(),
+            regs::fs0(),
+            regs::fs1(),
+            regs::fs2(),
+            regs::fs3(),
+            regs::fs4(),
+            regs::fs5(),
+            regs::fs6(),
+            regs::fs7(),
+            regs::fs8(),
+            regs::fs9(),
+            regs::fs10(),
+            regs::fs11(),
         ]
     }
 
@@ -62,8 +94,10 @@ impl LowerSpec for RvLowerSpec {
     fn caller_saved_regs() -> Vec<PReg> { CALLER_SAVED_REGS.to_vec() }
 
     fn return_reg(ctx: &ir::Context, ty: ir::Ty) -> PReg {
-        // TODO
-        regs::a0()
+        match reg_kind(ctx, ty) {
+            RegKind::General => regs::a0(),
+            RegKind::Float => regs::fa0(),
+        }
     }
 
     fn total_stack_size(lower: &mut LowerContext<Self>, mfunc: MFunc<Self::I>) -> u64 {
@@ -75,8 +109,53 @@ impl LowerSpec for RvLowerSpec {
     }
 
     fn gen_move(lower: &mut LowerContext<Self>, dst: Reg, src: MValue) {
-        // TODO
-        todo!()
+        let is_f64 = src.ty().is_f64(lower.ctx);
+        // Moves of raw bits between general and float registers
+        let (to_float, to_general) = if is_f64 {
+            (FpuMoveOp::FmvDX, FpuMoveOp::FmvXD)
+        } else {
+            (FpuMoveOp::FmvWX, FpuMoveOp::FmvXW)
+        };
+
+        let inst = match src.kind() {
+            MValueKind::Reg(reg) => match (dst.kind(), reg.kind()) {
+                (RegKind::General, RegKind::General) => {
+                    // mv dst, reg
+                    let zero = Imm12::try_from_i64(0).unwrap();
+                    RvInst::build_alu_rri(&mut lower.mctx, AluOpRRI::Addi, dst, reg, zero)
+                }
+                (RegKind::Float, RegKind::Float) => {
+                    let op = if is_f64 { FpuMoveOp::FmvD } else { FpuMoveOp::FmvS };
+                    RvInst::build_fpu_move(&mut lower.mctx, op, dst, reg)
+                }
+                (RegKind::Float, RegKind::General) => {
+                    RvInst::build_fpu_move(&mut lower.mctx, to_float, dst, reg)
+                }
+                (RegKind::General, RegKind::Float) => {
+                    RvInst::build_fpu_move(&mut lower.mctx, to_general, dst, reg)
+                }
+            },
+            // Float immediates are kept as their bits
+            MValueKind::Imm(_, imm) => match dst.kind() {
+                RegKind::General => RvInst::build_li(&mut lower.mctx, dst, imm as u64),
+                RegKind::Float => {
+                    let (li, t) = RvInst::li(&mut lower.mctx, imm as u64);
+                    lower
+                        .curr_block
+                        .unwrap()
+                        .push_back(&mut lower.mctx, li)
+                        .unwrap();
+                    RvInst::build_fpu_move(&mut lower.mctx, to_float, dst, t)
+                }
+            },
+            MValueKind::Mem(loc) => RvInst::build_load_addr(&mut lower.mctx, dst, loc),
+            MValueKind::Undef => return,
+        };
+        lower
+            .curr_block
+            .unwrap()
+            .push_back(&mut lower.mctx, inst)
+            .unwrap();
     }
 
     fn gen_sp_adjust(lower: &mut LowerContext<Self>, offset: i64) {
@@ -157,6 +236,70 @@ impl LowerSpec for RvLowerSpec {
         curr_block.push_back(&mut lower.mctx, inst).unwrap();
     }
 
+    fn gen_call(
+        lower: &mut LowerContext<Self>,
+        func: MFunc<Self::I>,
+        args: Vec<MValue>,
+        num_fixed_args: usize,
+        ret_ty: Option<ir::Ty>,
+    ) -> Option<MValue> {
+        let curr_block = lower.curr_block.unwrap();
+        let mut arg_regs = Vec::new();
+
+        // Fixed float arguments go to fa0-fa7, and the others, including all
+        // variadic arguments, go to a0-a7. Float arguments also take a0-a7
+        // when fa0-fa7 are used up. The rest are passed by the outgoing area
+        // on the stack.
+        let mut int_regs = INT_ARG_REGS.iter();
+        let mut float_regs = FLOAT_ARG_REGS.iter();
+        let mut stack_offset = 0;
+
+        for (i, arg) in args.into_iter().enumerate() {
+            let kind = reg_kind(lower.ctx, arg.ty());
+            let reg = match kind {
+                RegKind::Float if i < num_fixed_args => {
+                    float_regs.next().or_else(|| int_regs.next())
+                }
+                RegKind::General | RegKind::Float => int_regs.next(),
+            };
+
+            if let Some(&reg) = reg {
+                Self::gen_move(lower, reg.into(), arg);
+                arg_regs.push(reg);
+            } else {
+                let src: Reg = lower.mctx.new_vreg(kind).into();
+                Self::gen_move(lower, src, arg);
+                let op = match kind {
+                    RegKind::General => StoreOp::Sd,
+                    RegKind::Float if arg.ty().is_f64(lower.ctx) => StoreOp::Fsd,
+                    RegKind::Float => StoreOp::Fsw,
+                };
+                let loc = MemLoc::RegOffset {
+                    base: regs::sp().into(),
+                    offset: stack_offset,
+                };
+                let store = RvInst::store(&mut lower.mctx, op, src, loc);
+                curr_block.push_back(&mut lower.mctx, store).unwrap();
+                stack_offset += 8;
+            }
+        }
+
+        lower
+            .curr_func
+            .unwrap()
+            .update_outgoing_stack_size(&mut lower.mctx, stack_offset as u64);
+
+        let call = RvInst::call(&mut lower.mctx, func, arg_regs);
+        curr_block.push_back(&mut lower.mctx, call).unwrap();
+
+        ret_ty.map(|ty| {
+            let ret_reg = Self::return_reg(lower.ctx, ty);
+            let dst: Reg = lower.mctx.new_vreg(reg_kind(lower.ctx, ty)).into();
+            Self::gen_move(lower, dst, MValue::new_reg(ty, ret_reg));
+            MValue::new_reg(ty, dst)
+        })
+    }
+
     fn gen_func_prologue(lower: &mut LowerContext<Self>, func: MFunc<Self::I>) {
         let mut clobber_ra = false;
 
@@ -221,44 +364,43 @@ impl LowerSpec for RvLowerSpec {
 
         let mut curr_offset = total_stack_size - 8;
         for reg in saved_regs {
-            // TODO: make this modular
-            if let RegKind::General = reg.kind() {
-                if let Some(imm) = Imm12::try_from_i64(curr_offset) {
-                    let store = RvInst::store(
-                        &mut lower.mctx,
-                        StoreOp::Sd,
-                        reg.into(),
-                        MemLoc::RegOffset {
-                            base: sp.into(),
-                            offset: imm.as_i16() as i64,
-                        },
-                    );
-                    inst_buf.push(store);
-                } else {
-                    let t0 = regs::t0();
-                    let li = RvInst::build_li(&mut lower.mctx, t0.into(), curr_offset as u64);
-                    let add = RvInst::build_alu_rrr(
-                        &mut lower.mctx,
-                        AluOpRRR::Add,
-                        t0.into(),
-                        sp.into(),
-                        t0.into(),
-                    );
-                    let store = RvInst::store(
-                        &mut lower.mctx,
-                        StoreOp::Sd,
-                        reg.into(),
-                        MemLoc::RegOffset {
-                            base: t0.into(),
-                            offset: 0,
-                        },
-                    );
-                    inst_buf.push(li);
-                    inst_buf.push(add);
-                    inst_buf.push(store);
-                }
+            let op = match reg.kind() {
+                RegKind::General => StoreOp::Sd,
+                RegKind::Float => StoreOp::Fsd,
+            };
+            if let Some(imm) = Imm12::try_from_i64(curr_offset) {
+                let store = RvInst::store(
+                    &mut lower.mctx,
+                    op,
+                    reg.into(),
+                    MemLoc::RegOffset {
+                        base: sp.into(),
+                        offset: imm.as_i16() as i64,
+                    },
+                );
+                inst_buf.push(store);
             } else {
-                unimplemented!()
+                let t0 = regs::t0();
+                let li = RvInst::build_li(&mut lower.mctx, t0.into(), curr_offset as u64);
+                let add = RvInst::build_alu_rrr(
+                    &mut lower.mctx,
+                    AluOpRRR::Add,
+                    t0.into(),
+                    sp.into(),
+                    t0.into(),
+                );
+                let store = RvInst::store(
+                    &mut lower.mctx,
+                    op,
+                    reg.into(),
+                    MemLoc::RegOffset {
+                        base: t0.into(),
+                        offset: 0,
+                    },
+                );
+                inst_buf.push(li);
+                inst_buf.push(add);
+                inst_buf.push(store);
             }
             curr_offset -= 8;
         }
@@ -312,43 +454,43 @@ impl LowerSpec for RvLowerSpec {
         let mut curr_offset = total_stack_size - 8;
 
         for reg in saved_regs {
-            if let RegKind::General = reg.kind() {
-                if let Some(imm) = Imm12::try_from_i64(curr_offset) {
-                    let load = RvInst::build_load(
-                        &mut lower.mctx,
-                        LoadOp::Ld,
-                        reg.into(),
-                        MemLoc::RegOffset {
-                            base: sp.into(),
-                            offset: imm.as_i16() as i64,
-                        },
-                    );
-                    curr_block.push_back(&mut lower.mctx, load).unwrap();
-                } else {
-                    let t0 = regs::t0();
-                    let li = RvInst::build_li(&mut lower.mctx, t0.into(), curr_offset as u64);
-                    let add = RvInst::build_alu_rrr(
-                        &mut lower.mctx,
-                        AluOpRRR::Add,
-                        t0.into(),
-                        sp.into(),
-                        t0.into(),
-                    );
-                    let load = RvInst::build_load(
-                        &mut lower.mctx,
-                        LoadOp::Ld,
-                        reg.into(),
-                        MemLoc::RegOffset {
-                            base: t0.into(),
-                            offset: 0,
-                        },
-                    );
-                    curr_block.push_back(&mut lower.mctx, li).unwrap();
-                    curr_block.push_back(&mut lower.mctx, add).unwrap();
-                    curr_block.push_back(&mut lower.mctx, load).unwrap();
-                }
+            let op = match reg.kind() {
+                RegKind::General => LoadOp::Ld,
+                RegKind::Float => LoadOp::Fld,
+            };
+            if let Some(imm) = Imm12::try_from_i64(curr_offset) {
+                let load = RvInst::build_load(
+                    &mut lower.mctx,
+                    op,
+                    reg.into(),
+                    MemLoc::RegOffset {
+                        base: sp.into(),
+                        offset: imm.as_i16() as i64,
+                    },
+                );
+                curr_block.push_back(&mut lower.mctx, load).unwrap();
             } else {
-                unimplemented!()
+                let t0 = regs::t0();
+                let li = RvInst::build_li(&mut lower.mctx, t0.into(), curr_offset as u64);
+                let add = RvInst::build_alu_rrr(
+                    &mut lower.mctx,
+                    AluOpRRR::Add,
+                    t0.into(),
+                    sp.into(),
+                    t0.into(),
+                );
+                let load = RvInst::build_load(
+                    &mut lower.mctx,
+                    op,
+                    reg.into(),
+                    MemLoc::RegOffset {
+                        base: t0.into(),
+                        offset: 0,
+                    },
+                );
+                curr_block.push_back(&mut lower.mctx, li).unwrap();
+                curr_block.push_back(&mut lower.mctx, add).unwrap();
+                curr_block.push_back(&mut lower.mctx, load).unwrap();
             }
             curr_offset -= 8;
         }
@@ -377,6 +519,7 @@ impl LowerSpec for RvLowerSpec {
     fn gen_spill_load(lower: &mut LowerContext<Self>, reg: Reg, slot: MemLoc, inst: Self::I) {
         let load = match reg.kind() {
             RegKind::General => RvInst::build_load(&mut lower.mctx, LoadOp::Ld, reg, slot),
+            RegKind::Float => RvInst::build_load(&mut lower.mctx, LoadOp::Fld, reg, slot),
         };
         inst.insert_before(&mut lower.mctx, load).unwrap();
     }
@@ -384,9 +527,154 @@ impl LowerSpec for RvLowerSpec {
     fn gen_spill_store(lower: &mut LowerContext<Self>, reg: Reg, slot: MemLoc, inst: Self::I) {
         let store = match reg.kind() {
             RegKind::General => RvInst::store(&mut lower.mctx, StoreOp::Sd, reg, slot),
+            RegKind::Float => RvInst::store(&mut lower.mctx, StoreOp::Fsd, reg, slot),
         };
         inst.insert_after(&mut lower.mctx, store).unwrap();
     }
 
     fn display_reg(reg: Reg) -> String { regs::display(reg) }
 }
+
+/// Get the kind of registers holding values of type `ty`.
+fn reg_kind(ctx: &ir::Context, ty: ir::Ty) -> RegKind {
+    if ty.is_float(ctx) || ty.is_f64(ctx) {
+        RegKind::Float
+    } else {
+        RegKind::General
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::inst::DisplayMInst;
+    use crate::backend::{LowerConfig, MContext};
+
+    /// Lower a call to `f` in a fresh function, with the arguments created by
+    /// `args`, and return the lowered instructions.
+    fn lower_call(
+        ctx: &ir::Context,
+        args: impl FnOnce(&mut MContext<RvInst>) -> Vec<MValue>,
+        num_fixed_args: Option<usize>,
+        ret_ty: Option<ir::Ty>,
+    ) -> Vec<String> {
+        let mut lower: LowerContext<RvLowerSpec> = LowerContext::new(ctx, LowerConfig::default());
+        let mfunc = MFunc::new(&mut lower.mctx, "main");
+        let mblock = MBlock::new(&mut lower.mctx, ".entry");
+        mfunc.push_back(&mut lower.mctx, mblock).unwrap();
+        lower.curr_func = Some(mfunc);
+        lower.curr_block = Some(mblock);
+
+        let callee = MFunc::new_external(&mut lower.mctx, "f");
+        let args = args(&mut lower.mctx);
+        let num_fixed_args = num_fixed_args.unwrap_or(args.len());
+        let ret = RvLowerSpec::gen_call(&mut lower, callee, args, num_fixed_args, ret_ty);
+        assert_eq!(ret.is_some(), ret_ty.is_some());
+
+        mblock
+            .iter(&lower.mctx)
+            .map(|inst| inst.display(&lower.mctx).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_gen_call_int_args() {
+        let mut ctx = ir::Context::default();
+        let i32 = ir::Ty::i32(&mut ctx);
+
+        let args = |mctx: &mut MContext<RvInst>| {
+            (0..10)
+                .map(|i| MValue::new_imm(i32, mctx.new_vreg(RegKind::General), i))
+                .collect()
+        };
+        let insts = lower_call(&ctx, args, None, Some(i32));
+
+        // 8 arguments in a0-a7, and the other 2 in the outgoing area
+        for (i, reg) in ["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"]
+            .iter()
+            .enumerate()
+        {
+            assert_eq!(insts[i], format!("li {}, {}", reg, i));
+        }
+        assert!(insts[8].starts_with("li ") && insts[8].ends_with(", 8"));
+        assert!(insts[9].starts_with("sd ") && insts[9].ends_with(", 0(sp)"));
+        assert!(insts[10].starts_with("li ") && insts[10].ends_with(", 9"));
+        assert!(insts[11].starts_with("sd ") && insts[11].ends_with(", 8(sp)"));
+        assert_eq!(insts[12], "call f");
+        // the result is moved out of a0
+        assert!(insts[13].starts_with("addi ") && insts[13].ends_with(", a0, 0"));
+        assert_eq!(insts.len(), 14);
+    }
+
+    #[test]
+    fn test_gen_call_float_args() {
+        let mut ctx = ir::Context::default();
+        let i32 = ir::Ty::i32(&mut ctx);
+        let f32 = ir::Ty::f32(&mut ctx);
+
+        // f(i32 1, f32 %x, f32 1.5, i32 %y) -> f32
+        let args = |mctx: &mut MContext<RvInst>| {
+            vec![
+                MValue::new_imm(i32, mctx.new_vreg(RegKind::General), 1),
+                MValue::new_reg(f32, mctx.new_vreg(RegKind::Float)),
+                MValue::new_imm(f32, mctx.new_vreg(RegKind::Float), 1.5f32.to_bits() as i64),
+                MValue::new_reg(i32, mctx.new_vreg(RegKind::General)),
+            ]
+        };
+        let insts = lower_call(&ctx, args, None, Some(f32));
+
+        assert_eq!(insts[0], "li a0, 1");
+        assert!(insts[1].starts_with("fmv.s fa0, $f"));
+        assert!(insts[2].starts_with("li ") && insts[2].ends_with(", 0x000000003fc00000"));
+        assert!(insts[3].starts_with("fmv.w.x fa1, $r"));
+        assert!(insts[4].starts_with("addi a1, $r") && insts[4].ends_with(", 0"));
+        assert_eq!(insts[5], "call f");
+        // the result is moved out of fa0
+        assert!(insts[6].starts_with("fmv.s $f") && insts[6].ends_with(", fa0"));
+        assert_eq!(insts.len(), 7);
+    }
+
+    #[test]
+    fn test_gen_call_float_args_overflow() {
+        let mut ctx = ir::Context::default();
+        let f32 = ir::Ty::f32(&mut ctx);
+
+        // 17 float arguments: 8 in fa0-fa7, 8 in a0-a7 and 1 on the stack
+        let args = |mctx: &mut MContext<RvInst>| {
+            (0..17)
+                .map(|_| MValue::new_reg(f32, mctx.new_vreg(RegKind::Float)))
+                .collect()
+        };
+        let insts = lower_call(&ctx, args, None, None);
+
+        for i in 0..8 {
+            assert!(insts[i].starts_with(&format!("fmv.s fa{}, $f", i)));
+            assert!(insts[i + 8].starts_with(&format!("fmv.x.w a{}, $f", i)));
+        }
+        assert!(insts[16].starts_with("fmv.s $f"));
+        assert!(insts[17].starts_with("fsw $f") && insts[17].ends_with(", 0(sp)"));
+        assert_eq!(insts[18], "call f");
+        assert_eq!(insts.len(), 19);
+    }
+
+    #[test]
+    fn test_gen_call_variadic_float() {
+        let mut ctx = ir::Context::default();
+        let ptr = ir::Ty::ptr(&mut ctx);
+        let f64 = ir::Ty::f64(&mut ctx);
+
+        // putf(ptr %fmt, ...) with a double, which is passed in a1
+        let args = |mctx: &mut MContext<RvInst>| {
+            vec![
+                MValue::new_reg(ptr, mctx.new_vreg(RegKind::General)),
+                MValue::new_reg(f64, mctx.new_vreg(RegKind::Float)),
+            ]
+        };
+        let insts = lower_call(&ctx, args, Some(1), None);
+
+        assert!(insts[0].starts_with("addi a0, $r"));
+        assert!(insts[1].starts_with("fmv.x.d a1, $f"));
+        assert_eq!(insts[2], "call f");
+        assert_eq!(insts.len(), 3);
+    }
+}
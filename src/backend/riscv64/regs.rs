@@ -69,6 +69,70 @@ pub const fn t5() -> PReg { PReg::new(30, RegKind::General) }
 
 pub const fn t6() -> PReg { PReg::new(31, RegKind::General) }
 
+pub const fn ft0() -> PReg { PReg::new(0, RegKind::Float) }
+
+pub const fn ft1() -> PReg { PReg::new(1, RegKind::Float) }
+
+pub const fn ft2() -> PReg { PReg::new(2, RegKind::Float) }
+
+pub const fn ft3() -> PReg { PReg::new(3, RegKind::Float) }
+
+pub const fn ft4() -> PReg { PReg::new(4, RegKind::Float) }
+
+pub const fn ft5() -> PReg { PReg::new(5, RegKind::Float) }
+
+pub const fn ft6() -> PReg { PReg::new(6, RegKind::Float) }
+
+pub const fn ft7() -> PReg { PReg::new(7, RegKind::Float) }
+
+pub const fn fs0() -> PReg { PReg::new(8, RegKind::Float) }
+
+pub const fn fs1() -> PReg { PReg::new(9, RegKind::Float) }
+
+pub const fn fa0() -> PReg { PReg::new(10, RegKind::Float) }
+
+pub const fn fa1() -> PReg { PReg::new(11, RegKind::Float) }
+
+pub const fn fa2() -> PReg { PReg::new(12, RegKind::Float) }
+
+pub const fn fa3() -> PReg { PReg::new(13, RegKind::Float) }
+
+pub const fn fa4() -> PReg { PReg::new(14, RegKind::Float) }
+
+pub const fn fa5() -> PReg { PReg::new(15, RegKind::Float) }
+
+pub const fn fa6() -> PReg { PReg::new(16, RegKind::Float) }
+
+pub const fn fa7() -> PReg { PReg::new(17, RegKind::Float) }
+
+pub const fn fs2() -> PReg { PReg::new(18, RegKind::Float) }
+
+pub const fn fs3() -> PReg { PReg::new(19, RegKind::Float) }
+
+pub const fn fs4() -> PReg { PReg::new(20, RegKind::Float) }
+
+pub const fn fs5() -> PReg { PReg::new(21, RegKind::Float) }
+
+pub const fn fs6() -> PReg { PReg::new(22, RegKind::Float) }
+
+pub const fn fs7() -> PReg { PReg::new(23, RegKind::Float) }
+
+pub const fn fs8() -> PReg { PReg::new(24, RegKind::Float) }
+
+pub const fn fs9() -> PReg { PReg::new(25, RegKind::Float) }
+
+pub const fn fs10() -> PReg { PReg::new(26, RegKind::Float) }
+
+pub const fn fs11() -> PReg { PReg::new(27, RegKind::Float) }
+
+pub const fn ft8() -> PReg { PReg::new(28, RegKind::Float) }
+
+pub const fn ft9() -> PReg { PReg::new(29, RegKind::Float) }
+
+pub const fn ft10() -> PReg { PReg::new(30, RegKind::Float) }
+
+pub const fn ft11() -> PReg { PReg::new(31, RegKind::Float) }
+
 pub const fn display_preg(reg: PReg) -> &'static str {
     match reg.kind() {
         RegKind::General => match reg.num() {
@@ -106,6 +170,41 @@ pub const fn display_preg(reg: PReg) -> &'static str {
             31 => "t6",
             _ => "<invalid>",
         },
+        RegKind::Float => match reg.num() {
+            0 => "ft0",
+            1 => "ft1",
+            2 => "ft2",
+            3 => "ft3",
+            4 => "ft4",
+            5 => "ft5",
+            6 => "ft6",
+            7 => "ft7",
+            8 => "fs0",
+            9 => "fs1",
+            10 => "fa0",
+            11 => "fa1",
+            12 => "fa2",
+            13 => "fa3",
+            14 => "fa4",
+            15 => "fa5",
+            16 => "fa6",
+            17 => "fa7",
+            18 => "fs2",
+            19 => "fs3",
+            20 => "fs4",
+            21 => "fs5",
+            22 => "fs6",
+            23 => "fs7",
+            24 => "fs8",
+            25 => "fs9",
+            26 => "fs10",
+            27 => "fs11",
+            28 => "ft8",
+            29 => "ft9",
+            30 => "ft10",
+            31 => "ft11",
+            _ => "<invalid>",
+        },
     }
 }
 
@@ -128,7 +227,9 @@ pub fn display(reg: Reg) -> String {
 
 pub const INT_ARG_REGS: [PReg; 8] = [a0(), a1(), a2(), a3(), a4(), a5(), a6(), a7()];
 
-pub const CALLER_SAVED_REGS: [PReg; 16] = [
+pub const FLOAT_ARG_REGS: [PReg; 8] = [fa0(), fa1(), fa2(), fa3(), fa4(), fa5(), fa6(), fa7()];
+
+pub const CALLER_SAVED_REGS: [PReg; 36] = [
     ra(),
     t0(),
     t1(),
@@ -145,9 +246,29 @@ pub const CALLER_SAVED_REGS: [PReg; 16] = [
     a5(),
     a6(),
     a7(),
+    ft0(),
+    ft1(),
+    ft2(),
+    ft3(),
+    ft4(),
+    ft5(),
+    ft6(),
+    ft7(),
+    fa0(),
+    fa1(),
+    fa2(),
+    fa3(),
+    fa4(),
+    fa5(),
+    fa6(),
+    fa7(),
+    ft8(),
+    ft9(),
+    ft10(),
+    ft11(),
 ];
 
-pub const CALLEE_SAVED_REGS: [PReg; 13] = [
+pub const CALLEE_SAVED_REGS: [PReg; 25] = [
     sp(),
     s0(),
     s1(),
@@ -161,10 +282,23 @@ pub const CALLEE_SAVED_REGS: [PReg; 13] = [
     s9(),
     s10(),
     s11(),
+    fs0(),
+    fs1(),
+    fs2(),
+    fs3(),
+    fs4(),
+    fs5(),
+    fs6(),
+    fs7(),
+    fs8(),
+    fs9(),
+    fs10(),
+    fs11(),
 ];
 
-pub const RETURN_REGS: [PReg; 1] = [
+pub const RETURN_REGS: [PReg; 2] = [
     a0(),
     // since we only support 64-bit return value, a1 is not used
     // a1(),
+    fa0(),
 ];
@@ -1,10 +1,12 @@
 mod ast;
+mod diagnostic;
 mod irgen;
 mod parse;
 mod preprocess;
 mod types;
 
 pub use ast::*;
+pub use diagnostic::*;
 pub use irgen::*;
 pub use parse::*;
 pub use preprocess::*;
@@ -2,8 +2,10 @@
 
 use std::collections::HashMap;
 
+use super::diagnostic::{Diagnostic, Diagnostics, ErrorCode};
 use super::irgen::IrGenResult;
 use super::types::{Type, TypeKind as Tk};
+use crate::infra::span::Span;
 
 /// Represents a constant value that can be evaluated at compile time.
 #[derive(Debug, Clone)]
@@ -11,6 +13,9 @@ pub enum ComptimeVal {
     Bool(bool),
     Int(i32),
     Float(f32),
+    /// A constant array of the given type. The normalized initializer list may
+    /// be shorter than the array, and the missing trailing elements are zeros.
+    Array(Type, Vec<ComptimeVal>),
 }
 
 impl ComptimeVal {
@@ -20,6 +25,7 @@ impl ComptimeVal {
             Self::Bool(b) => *b as i32,
             Self::Int(i) => *i,
             Self::Float(f) => *f as i32,
+            Self::Array(..) => panic!("constant array is not an int"),
         }
     }
 
@@ -28,6 +34,7 @@ impl ComptimeVal {
             Self::Bool(b) => *b as i32 as f32,
             Self::Int(i) => *i as f32,
             Self::Float(f) => *f,
+            Self::Array(..) => panic!("constant array is not a float"),
         }
     }
 
@@ -49,15 +56,48 @@ impl ComptimeVal {
             Self::Bool(_) => Type::bool(),
             Self::Int(_) => Type::int(),
             Self::Float(_) => Type::float(),
+            Self::Array(ty, _) => ty.clone(),
         }
     }
 
+    /// Get the zero value of the given type.
+    pub fn zero(ty: &Type) -> Self {
+        match ty.kind() {
+            Tk::Bool => Self::Bool(false),
+            Tk::Int => Self::Int(0),
+            Tk::Float => Self::Float(0.0),
+            Tk::Array(..) => Self::Array(ty.clone(), Vec::new()),
+            Tk::Void | Tk::Ptr(..) | Tk::Str | Tk::Func(..) => {
+                panic!("type `{}` has no zero value", ty)
+            }
+        }
+    }
+
+    /// Get the element of a constant array at `index`, or `None` if the index
+    /// is out of bounds.
+    pub fn index(&self, index: i32) -> Option<Self> {
+        let Self::Array(ty, elems) = self else {
+            panic!("only constant arrays can be indexed");
+        };
+        let Tk::Array(elem_ty, len) = ty.kind() else {
+            unreachable!()
+        };
+        let index = usize::try_from(index).ok().filter(|index| index < len)?;
+        Some(
+            elems
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| Self::zero(elem_ty)),
+        )
+    }
+
     /// Check if the comptime value is zero.
     pub fn is_zero(&self) -> bool {
         match self {
             Self::Bool(b) => !*b,
             Self::Int(i) => *i == 0,
             Self::Float(f) => *f == 0.0,
+            Self::Array(_, elems) => elems.iter().all(Self::is_zero),
         }
     }
 
@@ -67,12 +107,14 @@ impl ComptimeVal {
             Self::Bool(a) => *a,
             Self::Int(a) => *a != 0,
             Self::Float(a) => *a != 0.0,
+            Self::Array(..) => panic!("constant array is not a condition"),
         };
 
         let rhs = match other {
             Self::Bool(b) => *b,
             Self::Int(b) => *b != 0,
             Self::Float(b) => *b != 0.0,
+            Self::Array(..) => panic!("constant array is not a condition"),
         };
 
         Self::Bool(lhs || rhs)
@@ -84,12 +126,14 @@ impl ComptimeVal {
             Self::Bool(a) => *a,
             Self::Int(a) => *a != 0,
             Self::Float(a) => *a != 0.0,
+            Self::Array(..) => panic!("constant array is not a condition"),
         };
 
         let rhs = match other {
             Self::Bool(b) => *b,
             Self::Int(b) => *b != 0,
             Self::Float(b) => *b != 0.0,
+            Self::Array(..) => panic!("constant array is not a condition"),
         };
 
         Self::Bool(lhs && rhs)
@@ -121,6 +165,9 @@ impl PartialEq for ComptimeVal {
             // Coercion situations, int -> float
             (Cv::Int(a), Cv::Float(b)) => (*a as f32 - *b).abs() < f32::EPSILON,
             (Cv::Float(a), Cv::Int(b)) => (*a - *b as f32).abs() < f32::EPSILON,
+
+            (Cv::Array(a_ty, a), Cv::Array(b_ty, b)) => a_ty == b_ty && a == b,
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => false,
         }
     }
 }
@@ -146,6 +193,9 @@ impl PartialOrd for ComptimeVal {
             // Coercion situations, int -> float
             (Cv::Int(a), Cv::Float(b)) => (*a as f32).partial_cmp(b),
             (Cv::Float(a), Cv::Int(b)) => a.partial_cmp(&(*b as f32)),
+
+            // Constant arrays are not ordered
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => None,
         }
     }
 }
@@ -157,8 +207,9 @@ impl std::ops::Neg for ComptimeVal {
         use ComptimeVal as Cv;
         match self {
             Cv::Bool(a) => Cv::Int(-(a as i32)),
-            Cv::Int(a) => Cv::Int(-a),
+            Cv::Int(a) => Cv::Int(a.wrapping_neg()),
             Cv::Float(a) => Cv::Float(-a),
+            Cv::Array(..) => panic!("cannot negate a constant array"),
         }
     }
 }
@@ -172,6 +223,7 @@ impl std::ops::Not for ComptimeVal {
             Cv::Bool(a) => Cv::Bool(!a),
             Cv::Int(a) => Cv::Bool(a != 0),
             Cv::Float(a) => Cv::Bool(a != 0.0),
+            Cv::Array(..) => panic!("cannot negate a constant array"),
         }
     }
 }
@@ -182,13 +234,13 @@ impl std::ops::Add for ComptimeVal {
     fn add(self, other: Self) -> Self {
         use ComptimeVal as Cv;
         match (self, other) {
-            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int(a as i32 + b as i32),
-            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a + b),
+            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int((a as i32).wrapping_add(b as i32)),
+            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a.wrapping_add(b)),
             (Cv::Float(a), Cv::Float(b)) => Cv::Float(a + b),
 
             // coercion situations, bool -> int
-            (Cv::Bool(a), Cv::Int(b)) => Cv::Int(a as i32 + b),
-            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a + b as i32),
+            (Cv::Bool(a), Cv::Int(b)) => Cv::Int((a as i32).wrapping_add(b)),
+            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a.wrapping_add(b as i32)),
 
             // coercion situations, bool -> float
             (Cv::Bool(a), Cv::Float(b)) => Cv::Float(a as i32 as f32 + b),
@@ -197,6 +249,10 @@ impl std::ops::Add for ComptimeVal {
             // coercion situations, int -> float
             (Cv::Int(a), Cv::Float(b)) => Cv::Float(a as f32 + b),
             (Cv::Float(a), Cv::Int(b)) => Cv::Float(a + b as f32),
+
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => {
+                panic!("arithmetic on constant arrays")
+            }
         }
     }
 }
@@ -208,13 +264,13 @@ impl std::ops::Sub for ComptimeVal {
         use ComptimeVal as Cv;
 
         match (self, other) {
-            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int(a as i32 - b as i32),
-            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a - b),
+            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int((a as i32).wrapping_sub(b as i32)),
+            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a.wrapping_sub(b)),
             (Cv::Float(a), Cv::Float(b)) => Cv::Float(a - b),
 
             // coercion situations, bool -> int
-            (Cv::Bool(a), Cv::Int(b)) => Cv::Int(a as i32 - b),
-            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a - b as i32),
+            (Cv::Bool(a), Cv::Int(b)) => Cv::Int((a as i32).wrapping_sub(b)),
+            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a.wrapping_sub(b as i32)),
 
             // coercion situations, bool -> float
             (Cv::Bool(a), Cv::Float(b)) => Cv::Float(a as i32 as f32 - b),
@@ -223,6 +279,10 @@ impl std::ops::Sub for ComptimeVal {
             // coercion situations, int -> float
             (Cv::Int(a), Cv::Float(b)) => Cv::Float(a as f32 - b),
             (Cv::Float(a), Cv::Int(b)) => Cv::Float(a - b as f32),
+
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => {
+                panic!("arithmetic on constant arrays")
+            }
         }
     }
 }
@@ -233,13 +293,13 @@ impl std::ops::Mul for ComptimeVal {
     fn mul(self, other: Self) -> Self {
         use ComptimeVal as Cv;
         match (self, other) {
-            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int(a as i32 * b as i32),
-            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a * b),
+            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int((a as i32).wrapping_mul(b as i32)),
+            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a.wrapping_mul(b)),
             (Cv::Float(a), Cv::Float(b)) => Cv::Float(a * b),
 
             // coercion situations, bool -> int
-            (Cv::Bool(a), Cv::Int(b)) => Cv::Int(a as i32 * b),
-            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a * b as i32),
+            (Cv::Bool(a), Cv::Int(b)) => Cv::Int((a as i32).wrapping_mul(b)),
+            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a.wrapping_mul(b as i32)),
 
             // coercion situations, bool -> float
             (Cv::Bool(a), Cv::Float(b)) => Cv::Float(a as i32 as f32 * b),
@@ -248,56 +308,69 @@ impl std::ops::Mul for ComptimeVal {
             // coercion situations, int -> float
             (Cv::Int(a), Cv::Float(b)) => Cv::Float(a as f32 * b),
             (Cv::Float(a), Cv::Int(b)) => Cv::Float(a * b as f32),
+
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => {
+                panic!("arithmetic on constant arrays")
+            }
         }
     }
 }
 
-impl std::ops::Div for ComptimeVal {
-    type Output = Self;
-
-    fn div(self, other: Self) -> Self {
+impl ComptimeVal {
+    /// Compute the quotient of two comptime values.
+    ///
+    /// Returns `None` if the result is undefined, i.e., an integer division by
+    /// zero or `i32::MIN / -1`, which should be left to the runtime.
+    pub fn checked_div(self, other: Self) -> Option<Self> {
         use ComptimeVal as Cv;
         match (self, other) {
-            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int(a as i32 / b as i32),
-            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a / b),
-            (Cv::Float(a), Cv::Float(b)) => Cv::Float(a / b),
+            (Cv::Bool(a), Cv::Bool(b)) => (a as i32).checked_div(b as i32).map(Cv::Int),
+            (Cv::Int(a), Cv::Int(b)) => a.checked_div(b).map(Cv::Int),
+            (Cv::Float(a), Cv::Float(b)) => Some(Cv::Float(a / b)),
 
             // coercion situations, bool -> int
-            (Cv::Bool(a), Cv::Int(b)) => Cv::Int(a as i32 / b),
-            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a / b as i32),
+            (Cv::Bool(a), Cv::Int(b)) => (a as i32).checked_div(b).map(Cv::Int),
+            (Cv::Int(a), Cv::Bool(b)) => a.checked_div(b as i32).map(Cv::Int),
 
             // coercion situations, bool -> float
-            (Cv::Bool(a), Cv::Float(b)) => Cv::Float(a as i32 as f32 / b),
-            (Cv::Float(a), Cv::Bool(b)) => Cv::Float(a / b as i32 as f32),
+            (Cv::Bool(a), Cv::Float(b)) => Some(Cv::Float(a as i32 as f32 / b)),
+            (Cv::Float(a), Cv::Bool(b)) => Some(Cv::Float(a / b as i32 as f32)),
 
             // coercion situations, int -> float
-            (Cv::Int(a), Cv::Float(b)) => Cv::Float(a as f32 / b),
-            (Cv::Float(a), Cv::Int(b)) => Cv::Float(a / b as f32),
+            (Cv::Int(a), Cv::Float(b)) => Some(Cv::Float(a as f32 / b)),
+            (Cv::Float(a), Cv::Int(b)) => Some(Cv::Float(a / b as f32)),
+
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => {
+                panic!("arithmetic on constant arrays")
+            }
         }
     }
-}
 
-impl std::ops::Rem for ComptimeVal {
-    type Output = Self;
-
-    fn rem(self, other: Self) -> Self {
+    /// Compute the remainder of two comptime values.
+    ///
+    /// Returns `None` if the result is undefined, same as [`Self::checked_div`].
+    pub fn checked_rem(self, other: Self) -> Option<Self> {
         use ComptimeVal as Cv;
         match (self, other) {
-            (Cv::Bool(a), Cv::Bool(b)) => Cv::Int(a as i32 % b as i32),
-            (Cv::Int(a), Cv::Int(b)) => Cv::Int(a % b),
-            (Cv::Float(a), Cv::Float(b)) => Cv::Float(a % b),
+            (Cv::Bool(a), Cv::Bool(b)) => (a as i32).checked_rem(b as i32).map(Cv::Int),
+            (Cv::Int(a), Cv::Int(b)) => a.checked_rem(b).map(Cv::Int),
+            (Cv::Float(a), Cv::Float(b)) => Some(Cv::Float(a % b)),
 
             // bool -> int
-            (Cv::Bool(a), Cv::Int(b)) => Cv::Int(a as i32 % b),
-            (Cv::Int(a), Cv::Bool(b)) => Cv::Int(a % b as i32),
+            (Cv::Bool(a), Cv::Int(b)) => (a as i32).checked_rem(b).map(Cv::Int),
+            (Cv::Int(a), Cv::Bool(b)) => a.checked_rem(b as i32).map(Cv::Int),
 
             // bool -> float
-            (Cv::Bool(a), Cv::Float(b)) => Cv::Float(a as i32 as f32 % b),
-            (Cv::Float(a), Cv::Bool(b)) => Cv::Float(a % b as i32 as f32),
+            (Cv::Bool(a), Cv::Float(b)) => Some(Cv::Float(a as i32 as f32 % b)),
+            (Cv::Float(a), Cv::Bool(b)) => Some(Cv::Float(a % b as i32 as f32)),
 
             // int -> float
-            (Cv::Int(a), Cv::Float(b)) => Cv::Float(a as f32 % b),
-            (Cv::Float(a), Cv::Int(b)) => Cv::Float(a % b as f32),
+            (Cv::Int(a), Cv::Float(b)) => Some(Cv::Float(a as f32 % b)),
+            (Cv::Float(a), Cv::Int(b)) => Some(Cv::Float(a % b as f32)),
+
+            (Cv::Array(..), _) | (_, Cv::Array(..)) => {
+                panic!("arithmetic on constant arrays")
+            }
         }
     }
 }
@@ -339,9 +412,13 @@ pub struct FuncCall {
 /// Left value refers to a specific memory location, typically allowing it to be
 /// assigned a value.
 /// Its usually on the left side of an assignment.
+/// e.g. `a`, `a[1][i]`
 #[derive(Debug, PartialEq, Eq)]
 pub struct LVal {
     pub ident: String,
+    /// Indices into the array, empty for scalar variables.
+    pub indices: Vec<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -358,6 +435,20 @@ pub enum ExprKind {
     LVal(LVal),
     /// Type coercion. This is used to convert one type to another.
     Coercion(Box<Expr>),
+    /// Initializer list of arrays.
+    /// e.g. `{1, 2, {3}}`
+    ///
+    /// After type checking, the list is normalized according to the type of
+    /// the array: each list has no more elements than the length of its
+    /// dimension, and each element is either a nested list (for sub-arrays) or
+    /// a scalar expression. The omitted trailing elements are zero.
+    InitList(Vec<Expr>),
+    /// String literal, as the bytes with the escape sequences decoded and
+    /// without the terminating NUL.
+    ///
+    /// Strings are only allowed as arguments of function calls, and can only
+    /// be passed as the format of `putf`.
+    Str(Vec<u8>),
 }
 
 /// Expression.
@@ -368,6 +459,10 @@ pub struct Expr {
     /// Type of the expression.
     /// Its generated during type checking.
     pub ty: Option<Type>,
+    /// Location of the expression in the source.
+    /// Expressions inserted by type checking (e.g., coercions and folded
+    /// constants) inherit the span of the expression they replace.
+    pub span: Span,
 }
 
 impl PartialEq for Expr {
@@ -384,13 +479,17 @@ impl Expr {
         Self {
             kind: ExprKind::Const(val),
             ty: Some(ty),
+            span: Span::default(),
         }
     }
 
+    /// Create a binary expression spanning from `lhs` to `rhs`.
     pub fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Self {
+        let span = lhs.span.merge(rhs.span);
         Self {
             kind: ExprKind::Binary(op, Box::new(lhs), Box::new(rhs)),
             ty: None,
+            span,
         }
     }
 
@@ -398,6 +497,7 @@ impl Expr {
         Self {
             kind: ExprKind::Unary(op, Box::new(expr)),
             ty: None,
+            span: Span::default(),
         }
     }
 
@@ -405,6 +505,7 @@ impl Expr {
         Self {
             kind: ExprKind::FuncCall(FuncCall { ident, args }),
             ty: None,
+            span: Span::default(),
         }
     }
 
@@ -412,6 +513,23 @@ impl Expr {
         Self {
             kind: ExprKind::LVal(lval),
             ty: None,
+            span: Span::default(),
+        }
+    }
+
+    pub fn init_list(elems: Vec<Expr>) -> Self {
+        Self {
+            kind: ExprKind::InitList(elems),
+            ty: None,
+            span: Span::default(),
+        }
+    }
+
+    pub fn string(value: Vec<u8>) -> Self {
+        Self {
+            kind: ExprKind::Str(value),
+            ty: Some(Type::str()),
+            span: Span::default(),
         }
     }
 
@@ -422,11 +540,19 @@ impl Expr {
             }
         }
 
+        let span = expr.span;
         Self {
             kind: ExprKind::Coercion(Box::new(expr)),
             ty: Some(to),
+            span,
         }
     }
+
+    /// Set the span of the expression.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
 }
 
 /// Expression statement.
@@ -443,7 +569,20 @@ pub struct ReturnStmt {
 
 /// Statement.
 #[derive(Debug)]
-pub enum Stmt {
+pub struct Stmt {
+    /// Kind of the statement.
+    pub kind: StmtKind,
+    /// Location of the statement in the source.
+    pub span: Span,
+}
+
+impl Stmt {
+    pub fn new(kind: StmtKind, span: Span) -> Self { Self { kind, span } }
+}
+
+/// Kind of statements.
+#[derive(Debug)]
+pub enum StmtKind {
     /// Assignment statement.
     /// e.g. `a = 1;`
     Assign(LVal, Expr),
@@ -486,6 +625,7 @@ pub enum BlockItem {
 #[derive(Debug)]
 pub struct Block {
     pub items: Vec<BlockItem>,
+    pub span: Span,
 }
 
 /// Declaration.
@@ -507,7 +647,11 @@ pub enum Decl {
 #[derive(Debug)]
 pub struct ConstDef {
     pub ident: String,
+    /// Dimensions of the array, empty for scalar constants.
+    /// They are folded into constants during type checking.
+    pub dims: Vec<Expr>,
     pub init: Expr,
+    pub span: Span,
 }
 
 /// Variable definition.
@@ -520,7 +664,11 @@ pub struct ConstDef {
 #[derive(Debug)]
 pub struct VarDef {
     pub ident: String,
+    /// Dimensions of the array, empty for scalar variables.
+    /// They are folded into constants during type checking.
+    pub dims: Vec<Expr>,
     pub init: Option<Expr>,
+    pub span: Span,
 }
 
 /// Constant declaration.
@@ -530,6 +678,7 @@ pub struct VarDef {
 pub struct ConstDecl {
     pub ty: Type,
     pub defs: Vec<ConstDef>,
+    pub span: Span,
 }
 
 /// Variable declaration.
@@ -539,19 +688,28 @@ pub struct ConstDecl {
 pub struct VarDecl {
     pub ty: Type,
     pub defs: Vec<VarDef>,
+    pub span: Span,
 }
 
 /// Function Formal parameter.
 /// A parameter of a function definition.
 /// e.g.
 /// ```c
-/// int add(int a, int b) {}
-///         ^^^^^
+/// int add(int a, int b[][3]) {}
+///         ^^^^^  ^^^^^^^^^^
 /// ```
 #[derive(Debug)]
 pub struct FuncFParam {
+    /// Type of the parameter.
+    ///
+    /// For array parameters, this is the base type when parsed, and is
+    /// replaced with the decayed pointer type during type checking.
     pub ty: Type,
     pub ident: String,
+    /// Dimensions after the first `[]` of array parameters, `None` for scalar
+    /// parameters.
+    pub dims: Option<Vec<Expr>>,
+    pub span: Span,
 }
 
 /// Function definition.
@@ -566,6 +724,9 @@ pub struct FuncDef {
     pub params: Vec<FuncFParam>,
     /// Body of the function. It contains a block of statements.
     pub body: Block,
+    /// Location of the function definition in the source, from the return
+    /// type to the end of the body.
+    pub span: Span,
 }
 
 /// A global item.
@@ -593,6 +754,10 @@ pub struct SymbolEntry {
     pub ty: Type,
     /// The possible compile time value of the symbol.
     pub comptime: Option<ComptimeVal>,
+    /// Whether the symbol is a constant, which cannot be assigned to.
+    pub is_const: bool,
+    /// Location of the declaration, `None` for library functions.
+    pub span: Option<Span>,
     /// The IR value of the symbol.
     /// Its generated during IR generation.
     pub ir_value: Option<IrGenResult>,
@@ -604,6 +769,8 @@ impl SymbolEntry {
         Self {
             ty,
             comptime: None,
+            is_const: false,
+            span: None,
             ir_value: None,
         }
     }
@@ -619,6 +786,12 @@ pub struct SymbolTable {
 
     /// The current return type of the function.
     pub curr_ret_ty: Option<Type>,
+
+    /// The number of loops enclosing the current statement.
+    pub loop_depth: usize,
+
+    /// Diagnostics reported during type checking.
+    pub diagnostics: Diagnostics,
 }
 
 impl SymbolTable {
@@ -632,6 +805,9 @@ impl SymbolTable {
         self.stack.pop();
     }
 
+    /// Check if the current scope is the global scope.
+    pub fn is_global_scope(&self) -> bool { self.stack.len() == 1 }
+
     /// Insert a symbol into the current scope.
     pub fn insert(&mut self, name: impl Into<String>, entry: SymbolEntry) {
         self.stack.last_mut().unwrap().insert(name.into(), entry);
@@ -647,6 +823,36 @@ impl SymbolTable {
             .insert(name.into(), entry);
     }
 
+    /// Report the error in `result` if any, otherwise return the value.
+    pub fn ok_or_report<T>(&mut self, result: Result<T, Diagnostic>) -> Option<T> {
+        match result {
+            Ok(val) => Some(val),
+            Err(diag) => {
+                self.diagnostics.push(diag);
+                None
+            }
+        }
+    }
+
+    /// Declare a symbol in the current scope.
+    ///
+    /// If the symbol is already declared in the current scope, a redeclaration
+    /// error is reported and the previous declaration is kept.
+    pub fn declare(&mut self, name: impl Into<String>, entry: SymbolEntry) {
+        let name = name.into();
+        if let Some(prev) = self.stack.last().unwrap().get(&name) {
+            let diag = redeclaration(&name, entry.span.unwrap_or_default(), prev);
+            self.diagnostics.push(diag);
+            return;
+        }
+        self.insert(name, entry);
+    }
+
+    /// Lookup a symbol in the current scope only.
+    pub fn lookup_current(&self, name: &str) -> Option<&SymbolEntry> {
+        self.stack.last().unwrap().get(name)
+    }
+
     /// Lookup a symbol in the symbol table.
     pub fn lookup(&self, name: &str) -> Option<&SymbolEntry> {
         for scope in self.stack.iter().rev() {
@@ -669,40 +875,53 @@ impl SymbolTable {
 
     /// Register SysY library functions to the symbol table.
     pub fn register_sysylib(&mut self) {
-        // TODO: Register SysY library functions to the symbol table
-        // 定义返回类型和参数类型
-        let void_type = Type::void();
-        let bool_type = Type::bool();
-        let int_type = Type::int();
-
-        // Sysy库函数（存疑）
-        let functions = vec![
-            ("print", vec![int_type.clone()], int_type.clone()), // print(int) -> int
-            ("println", vec![int_type.clone()], int_type.clone()), // println(int) -> int
-            ("scanf", vec![void_type.clone()], bool_type.clone()), // scanf(void) -> bool
-            ("malloc", vec![int_type.clone()], int_type.clone()), // malloc(int) -> int
-            ("free", vec![int_type.clone()], void_type.clone()), // free(int) -> void
-        ];
-
-        // 将函数注册到当前作用域
-        for (name, params, ret) in functions {
-            let func_type = Type::func(params.clone(), ret);
-
-            // 构造新的 SymbolEntry
+        for (name, ty) in sysylib() {
             let entry = SymbolEntry {
-                ty: func_type,
-                comptime: None, // 可以根据需要设置为 Some(ComptimeVal)
-                ir_value: None, // 可以根据需要设置为 Some(IrGenResult)
+                ty,
+                comptime: None,
+                is_const: false,
+                span: None,
+                ir_value: None,
             };
-
             self.insert(name, entry);
         }
     }
 }
 
+/// Get the names and types of the functions in the SysY runtime library.
+///
+/// `starttime()` and `stoptime()` are not here, they are replaced by
+/// `_sysy_starttime` and `_sysy_stoptime` in preprocessing.
+pub fn sysylib() -> Vec<(&'static str, Type)> {
+    let void = Type::void();
+    let int = Type::int();
+    let float = Type::float();
+    let int_ptr = Type::ptr(int.clone());
+    let float_ptr = Type::ptr(float.clone());
+
+    vec![
+        ("getint", Type::func(vec![], int.clone())),
+        ("getch", Type::func(vec![], int.clone())),
+        ("getfloat", Type::func(vec![], float.clone())),
+        ("getarray", Type::func(vec![int_ptr.clone()], int.clone())),
+        ("getfarray", Type::func(vec![float_ptr.clone()], int.clone())),
+        ("putint", Type::func(vec![int.clone()], void.clone())),
+        ("putch", Type::func(vec![int.clone()], void.clone())),
+        ("putfloat", Type::func(vec![float.clone()], void.clone())),
+        ("putarray", Type::func(vec![int.clone(), int_ptr], void.clone())),
+        ("putfarray", Type::func(vec![int.clone(), float_ptr], void.clone())),
+        ("putf", Type::variadic_func(vec![Type::str()], void.clone())),
+        ("_sysy_starttime", Type::func(vec![int.clone()], void.clone())),
+        ("_sysy_stoptime", Type::func(vec![int], void)),
+    ]
+}
+
 impl CompUnit {
     /// Type check the compilation unit.
-    pub fn type_check(&mut self) {
+    ///
+    /// All the errors found are reported together, sorted by their locations.
+    /// If there are no errors, the warnings are returned instead.
+    pub fn type_check(&mut self) -> Result<Diagnostics, Diagnostics> {
         let mut symtable = SymbolTable::default();
         symtable.enter_scope();
 
@@ -715,6 +934,13 @@ impl CompUnit {
         }
 
         symtable.leave_scope();
+
+        let mut diagnostics = symtable.diagnostics;
+        diagnostics.sort();
+        if diagnostics.has_errors() {
+            return Err(diagnostics);
+        }
+        Ok(diagnostics)
     }
 }
 
@@ -731,26 +957,74 @@ impl Item {
                 ident,
                 params,
                 body,
+                span,
             }) => {
+                // Check the redeclaration of the function before entering the parameters scope
+                let redeclared = symtable
+                    .lookup_current(ident)
+                    .map(|prev| redeclaration(ident, *span, prev));
+                let is_redeclared = redeclared.is_some();
+                if let Some(diag) = redeclared {
+                    symtable.diagnostics.push(diag);
+                }
+
                 // Enter a new scope for function parameters
                 symtable.enter_scope();
 
                 // Insert the function parameters into the scope
                 let mut param_tys = Vec::new();
-                for param in params.iter() {
+                for param in params.iter_mut() {
+                    if let Some(dims) = param.dims.as_mut() {
+                        // Array parameters are decayed into pointers to the element type
+                        let dims = type_check_dims(dims, symtable);
+                        let dims = symtable.ok_or_report(dims).unwrap_or_default();
+                        param.ty = Type::ptr(Type::array_of(param.ty.clone(), &dims));
+                    }
                     param_tys.push(param.ty.clone());
-                    symtable.insert(param.ident.clone(), SymbolEntry::from_ty(param.ty.clone()));
+                    symtable.declare(
+                        param.ident.clone(),
+                        SymbolEntry {
+                            span: Some(param.span),
+                            ..SymbolEntry::from_ty(param.ty.clone())
+                        },
+                    );
                 }
 
                 let func_ty = Type::func(param_tys, ret_ty.clone());
 
                 // Insert the function symbol into the scope above the current scope, since we
                 // are in the parameters scope
-                symtable.insert_upper(ident.clone(), SymbolEntry::from_ty(func_ty), 1);
+                if !is_redeclared {
+                    symtable.insert_upper(
+                        ident.clone(),
+                        SymbolEntry {
+                            span: Some(*span),
+                            ..SymbolEntry::from_ty(func_ty)
+                        },
+                        1,
+                    );
+                }
                 symtable.curr_ret_ty = Some(ret_ty.clone());
 
-                // Type check the function body
-                body.type_check(symtable);
+                // Type check the function body. The parameters and the outermost block of the
+                // body are in the same scope.
+                let num_errors = symtable.diagnostics.num_errors();
+                body.type_check_items(symtable);
+
+                // Erroneous statements are dropped from the body, so only check the returns if
+                // the body has no errors.
+                let has_errors = symtable.diagnostics.num_errors() > num_errors;
+                if !has_errors && !ret_ty.is_void() && !body.always_returns() {
+                    let end = Span::new(body.span.end - 1, body.span.end);
+                    symtable.diagnostics.push(
+                        Diagnostic::error(
+                            ErrorCode::MissingReturn,
+                            format!("function `{}` may not return a value", ident),
+                            end,
+                        )
+                        .with_label(format!("the end of a function returning `{}`", ret_ty)),
+                    );
+                }
 
                 symtable.curr_ret_ty = None;
                 symtable.leave_scope();
@@ -764,40 +1038,106 @@ impl ConstDecl {
     pub fn type_check(&mut self, symtable: &mut SymbolTable) {
         let mut new_defs = Vec::new();
         for mut def in self.defs.drain(..) {
-            // TODO: array type checking
-
-            let ty = self.ty.clone();
-
-            // Type check the init expression
-            def.init = def.init.type_check(Some(&ty), symtable);
-
-            // Fold the init expression into a constant value
-            let folded = def.init.try_fold(symtable).expect("non-constant init");
-            def.init = Expr::const_(folded.clone());
+            let dims = type_check_dims(&mut def.dims, symtable);
+            let dims = symtable.ok_or_report(dims).unwrap_or_default();
+            let ty = Type::array_of(self.ty.clone(), &dims);
+            let ident = def.ident.clone();
+            let span = def.span;
+
+            // Type check the init expression. The constant is declared even if there are
+            // errors, so that its uses do not cause more errors.
+            let comptime = match def.type_check_init(&ty, symtable) {
+                Ok((def, comptime)) => {
+                    new_defs.push(def);
+                    comptime
+                }
+                Err(diag) => {
+                    symtable.diagnostics.push(diag);
+                    None
+                }
+            };
 
             // Insert the constant into the symbol table
-            symtable.insert(
-                def.ident.clone(),
+            symtable.declare(
+                ident,
                 SymbolEntry {
                     ty,
-                    comptime: Some(folded),
+                    comptime,
+                    is_const: true,
+                    span: Some(span),
                     ir_value: None,
                 },
             );
-            new_defs.push(def);
         }
         self.defs = new_defs;
     }
 }
 
+impl ConstDef {
+    /// Type check the init expression of the constant with type `ty`.
+    ///
+    /// Returns the definition and the folded value of the constant.
+    fn type_check_init(
+        mut self,
+        ty: &Type,
+        symtable: &mut SymbolTable,
+    ) -> Result<(Self, Option<ComptimeVal>), Diagnostic> {
+        let init = self.init.type_check_init(ty, symtable)?;
+
+        let non_constant = |span| {
+            Diagnostic::error(
+                ErrorCode::NonConstant,
+                format!("initializer of constant `{}` is not a constant", self.ident),
+                span,
+            )
+            .with_label("not a constant")
+        };
+
+        if ty.is_array() {
+            // Every element of a constant array should be folded
+            if let Some(elem) = init.find_non_const_init() {
+                return Err(non_constant(elem.span));
+            }
+            let folded = init.try_fold(symtable);
+            self.init = init;
+            Ok((self, folded))
+        } else {
+            // Fold the init expression into a constant value
+            let folded = init
+                .try_fold(symtable)
+                .ok_or_else(|| non_constant(init.span))?;
+            self.init = Expr::const_(folded.clone()).with_span(init.span);
+            Ok((self, Some(folded)))
+        }
+    }
+}
+
+impl VarDef {
+    /// Get the type of the (type checked) variable, given the base type of the
+    /// declaration.
+    pub fn ty(&self, base: &Type) -> Type {
+        let dims: Vec<usize> = self
+            .dims
+            .iter()
+            .map(|dim| match &dim.kind {
+                ExprKind::Const(ComptimeVal::Int(len)) => *len as usize,
+                _ => unreachable!("array dimensions should have been folded"),
+            })
+            .collect();
+        Type::array_of(base.clone(), &dims)
+    }
+}
+
 impl VarDecl {
     /// Type check the variable declaration.
     pub fn type_check(&mut self, symtable: &mut SymbolTable) {
         let mut new_defs = Vec::new();
         for mut def in self.defs.drain(..) {
-            // TODO: array type checking
-
-            let ty = self.ty.clone();
+            let dims = type_check_dims(&mut def.dims, symtable);
+            let dims = symtable.ok_or_report(dims).unwrap_or_default();
+            let ty = Type::array_of(self.ty.clone(), &dims);
+            let ident = def.ident.clone();
+            let span = def.span;
 
             // Type check the init expression, and fold it if possible
             let init = def
@@ -805,19 +1145,44 @@ impl VarDecl {
                 .map(|init| {
                     // fold as much as possible
                     // XXX: what if we do not fold here?
-                    let typed_init = init.type_check(Some(&ty), symtable);
-                    match typed_init.try_fold(symtable) {
-                        Some(val) => Expr::const_(val),
-                        None => typed_init,
+                    let typed_init = init.type_check_init(&ty, symtable)?;
+                    let folded = typed_init.try_fold(symtable);
+                    // Globals are initialized before the program runs
+                    if folded.is_none() && symtable.is_global_scope() {
+                        let elem = typed_init.find_non_const_init().unwrap_or(&typed_init);
+                        return Err(Diagnostic::error(
+                            ErrorCode::NonConstant,
+                            format!("initializer of global `{}` is not a constant", ident),
+                            elem.span,
+                        )
+                        .with_label("not a constant"));
                     }
+                    // Array initializers are kept as lists for irgen
+                    Ok(match folded {
+                        Some(val) if !ty.is_array() => {
+                            Expr::const_(val).with_span(typed_init.span)
+                        }
+                        _ => typed_init,
+                    })
                 })
-                .unwrap_or_else(|| todo!("what if there is no init value?"));
-
-            def.init = Some(init);
+                .transpose();
+
+            // The variable is declared even if there are errors, so that its uses do not
+            // cause more errors. Variables without initializers are zero-initialized if
+            // global, and left undefined if local.
+            if let Some(init) = symtable.ok_or_report(init) {
+                def.init = init;
+                new_defs.push(def);
+            }
 
             // Insert the variable into the symbol table
-            symtable.insert(def.ident.clone(), SymbolEntry::from_ty(ty));
-            new_defs.push(def);
+            symtable.declare(
+                ident,
+                SymbolEntry {
+                    span: Some(span),
+                    ..SymbolEntry::from_ty(ty)
+                },
+            );
         }
         self.defs = new_defs;
     }
@@ -828,6 +1193,12 @@ impl Block {
     pub fn type_check(&mut self, symtable: &mut SymbolTable) {
         // Enter a new scope
         symtable.enter_scope();
+        self.type_check_items(symtable);
+        symtable.leave_scope();
+    }
+
+    /// Type check the items of the block in the current scope.
+    pub fn type_check_items(&mut self, symtable: &mut SymbolTable) {
         let mut new_items = Vec::new();
 
         // Type check each block item in the block
@@ -851,73 +1222,179 @@ impl Block {
             new_items.push(item);
         }
         self.items = new_items;
-        symtable.leave_scope();
+    }
+
+    /// Check if the control flow never reaches the end of the (type checked)
+    /// block, i.e., it always returns.
+    pub fn always_returns(&self) -> bool {
+        self.items
+            .iter()
+            .any(|item| matches!(item, BlockItem::Stmt(stmt) if stmt.always_returns()))
     }
 }
 
 impl Stmt {
     /// Type check the statement.
+    ///
+    /// Errors are reported to the symbol table, and the erroneous statement is
+    /// replaced with an empty one.
     pub fn type_check(self, symtable: &mut SymbolTable) -> Self {
-        match self {
-            Stmt::Assign(LVal { ident }, expr) => {
-                // lookup the variable in the symbol table
-                let entry = symtable.lookup(&ident).expect("variable not found");
+        let span = self.span;
+        match self.type_check_inner(symtable) {
+            Ok(stmt) => stmt,
+            Err(diag) => {
+                symtable.diagnostics.push(diag);
+                Stmt::new(StmtKind::Expr(ExprStmt { expr: None }), span)
+            }
+        }
+    }
 
-                // TODO: array type checking
+    fn type_check_inner(self, symtable: &mut SymbolTable) -> Result<Self, Diagnostic> {
+        let Stmt { kind, span } = self;
+        let kind = match kind {
+            StmtKind::Assign(lval, expr) => {
+                // Type check the left value, including the indices
+                let (lval, ty) = lval.type_check(symtable)?;
+
+                let entry = symtable.lookup(&lval.ident).unwrap();
+                if entry.is_const {
+                    let mut diag = Diagnostic::error(
+                        ErrorCode::AssignToConst,
+                        format!("cannot assign to constant `{}`", lval.ident),
+                        lval.span,
+                    )
+                    .with_label("cannot be assigned to");
+                    if let Some(decl_span) = entry.span {
+                        diag = diag.with_secondary(decl_span, "constant declared here");
+                    }
+                    return Err(diag);
+                }
 
-                let ty = &entry.ty;
+                if !ty.is_scalar() {
+                    return Err(Diagnostic::error(
+                        ErrorCode::NotAssignable,
+                        format!("cannot assign to `{}` of type `{}`", lval.ident, ty),
+                        lval.span,
+                    )
+                    .with_label("cannot be assigned to"));
+                }
 
                 // Type check the expression
-                let expr = expr.type_check(Some(ty), symtable);
-                Stmt::Assign(LVal { ident }, expr)
+                let expr = expr.type_check(Some(&ty), symtable)?;
+                StmtKind::Assign(lval, expr)
             }
-            Stmt::Expr(ExprStmt { expr }) => {
+            StmtKind::Expr(ExprStmt { expr }) => {
                 // Type check the expression
-                let expr = expr.map(|expr| expr.type_check(None, symtable));
-                Stmt::Expr(ExprStmt { expr })
+                let expr = expr
+                    .map(|expr| expr.type_check(None, symtable))
+                    .transpose()?;
+                StmtKind::Expr(ExprStmt { expr })
             }
-            Stmt::Block(mut block) => {
+            StmtKind::Block(mut block) => {
                 // Type check the block
                 block.type_check(symtable);
-                Stmt::Block(block)
-            }
-            Stmt::Break => Stmt::Break,
-            Stmt::Continue => Stmt::Continue,
-            Stmt::Return(ReturnStmt { expr }) => {
-                // Type check the return expression
-                let expr =
-                    expr.map(|expr| expr.type_check(symtable.curr_ret_ty.as_ref(), symtable));
-
-                // Void return
-                if expr.is_none() {
-                    return Stmt::Return(ReturnStmt { expr });
+                StmtKind::Block(block)
+            }
+            StmtKind::Break | StmtKind::Continue => {
+                if symtable.loop_depth == 0 {
+                    let keyword = match kind {
+                        StmtKind::Break => "break",
+                        _ => "continue",
+                    };
+                    return Err(Diagnostic::error(
+                        ErrorCode::OutsideLoop,
+                        format!("`{}` outside of a loop", keyword),
+                        span,
+                    )
+                    .with_label(format!("cannot `{}` outside of a loop", keyword)));
                 }
-
-                let mut expr = expr.unwrap();
-                let ret_ty = symtable.curr_ret_ty.as_ref().unwrap();
-
-                if ret_ty.is_int() {
-                    // Coerce the expression to int if needed
-                    expr = Expr::coercion(expr, Type::int());
-                } else {
-                    panic!("unsupported return type");
+                kind
+            }
+            StmtKind::Return(ReturnStmt { expr }) => {
+                let ret_ty = symtable.curr_ret_ty.clone().unwrap();
+                match expr {
+                    Some(expr) if ret_ty.is_void() => {
+                        return Err(Diagnostic::error(
+                            ErrorCode::ReturnMismatch,
+                            "`return` with a value in a function returning `void`",
+                            expr.span,
+                        )
+                        .with_label("unexpected return value"));
+                    }
+                    None if !ret_ty.is_void() => {
+                        return Err(Diagnostic::error(
+                            ErrorCode::ReturnMismatch,
+                            format!(
+                                "`return` without a value in a function returning `{}`",
+                                ret_ty
+                            ),
+                            span,
+                        )
+                        .with_label(format!("expected a value of type `{}`", ret_ty)));
+                    }
+                    _ => {}
                 }
 
-                Stmt::Return(ReturnStmt { expr: Some(expr) })
+                // Type check the return expression, and coerce it to the return type
+                let expr = expr
+                    .map(|expr| expr.type_check(Some(&ret_ty), symtable))
+                    .transpose()?;
+                StmtKind::Return(ReturnStmt { expr })
             }
-            Stmt::If(cond, then_block, else_block) => {
-                // Type check the condition expression and the blocks
+            StmtKind::If(cond, then_block, else_block) => {
+                // Type check the condition expression and the blocks. The blocks are checked
+                // even if the condition has errors, to report the errors in them.
                 let cond = cond.type_check(Some(&Type::bool()), symtable);
                 let then_block = then_block.type_check(symtable);
                 let else_block = else_block.map(|block| block.type_check(symtable));
-                Stmt::If(cond, Box::new(then_block), else_block.map(Box::new))
+                StmtKind::If(cond?, Box::new(then_block), else_block.map(Box::new))
             }
-            Stmt::While(cond, block) => {
+            StmtKind::While(cond, block) => {
                 // Type check the condition expression and the block
                 let cond = cond.type_check(Some(&Type::bool()), symtable);
+                symtable.loop_depth += 1;
                 let block = block.type_check(symtable);
-                Stmt::While(cond, Box::new(block))
+                symtable.loop_depth -= 1;
+                StmtKind::While(cond?, Box::new(block))
             }
+        };
+        Ok(Stmt::new(kind, span))
+    }
+
+    /// Check if the control flow never reaches the end of the (type checked)
+    /// statement, i.e., it always returns.
+    ///
+    /// This is a conservative analysis: only the loops with constant true
+    /// conditions and without `break` are considered to never exit.
+    pub fn always_returns(&self) -> bool {
+        match &self.kind {
+            StmtKind::Return(_) => true,
+            StmtKind::Block(block) => block.always_returns(),
+            StmtKind::If(_, then_stmt, Some(else_stmt)) => {
+                then_stmt.always_returns() && else_stmt.always_returns()
+            }
+            StmtKind::While(cond, body) => {
+                let infinite = matches!(&cond.kind, ExprKind::Const(val) if !val.is_zero());
+                infinite && !body.contains_break()
+            }
+            _ => false,
+        }
+    }
+
+    /// Check if the statement contains a `break` out of it, ignoring the ones
+    /// in nested loops.
+    fn contains_break(&self) -> bool {
+        match &self.kind {
+            StmtKind::Break => true,
+            StmtKind::Block(block) => block.items.iter().any(|item| match item {
+                BlockItem::Stmt(stmt) => stmt.contains_break(),
+                BlockItem::Decl(_) => false,
+            }),
+            StmtKind::If(_, then_stmt, else_stmt) => {
+                then_stmt.contains_break()
+                    || else_stmt.as_ref().is_some_and(|stmt| stmt.contains_break())
+            }
+            _ => false,
         }
     }
 }
@@ -942,8 +1419,8 @@ impl Expr {
                     Bo::Add => Some(lhs + rhs),
                     Bo::Sub => Some(lhs - rhs),
                     Bo::Mul => Some(lhs * rhs),
-                    Bo::Div => Some(lhs / rhs),
-                    Bo::Mod => Some(lhs % rhs),
+                    Bo::Div => lhs.checked_div(rhs),
+                    Bo::Mod => lhs.checked_rem(rhs),
                     Bo::Lt => Some(ComptimeVal::bool(lhs < rhs)),
                     Bo::Gt => Some(ComptimeVal::bool(lhs > rhs)),
                     Bo::Le => Some(ComptimeVal::bool(lhs <= rhs)),
@@ -962,11 +1439,28 @@ impl Expr {
                     UnaryOp::Not => Some(!expr),
                 }
             }
-            ExprKind::FuncCall(_) => None,
-            ExprKind::LVal(LVal { ident }) => {
-                // TODO: what if there are indices?
+            ExprKind::FuncCall(_) | ExprKind::Str(_) => None,
+            ExprKind::InitList(elems) => {
+                // Only normalized initializer lists are typed, and can be folded
+                let ty = self.ty.clone()?;
+                let elems = elems
+                    .iter()
+                    .map(|elem| elem.try_fold(symtable))
+                    .collect::<Option<_>>()?;
+                Some(ComptimeVal::Array(ty, elems))
+            }
+            ExprKind::LVal(LVal { ident, indices, .. }) => {
                 let entry = symtable.lookup(ident).unwrap();
-                Some(entry.comptime.as_ref()?.clone())
+                let mut val = entry.comptime.clone()?;
+                for index in indices {
+                    let index = index.try_fold(symtable)?.unwrap_int();
+                    val = val.index(index)?;
+                }
+                // The address of a (sub-)array is not a constant
+                match val {
+                    ComptimeVal::Array(..) => None,
+                    val => Some(val),
+                }
             }
             ExprKind::Coercion(expr) => {
                 // Coerce the expression to the target type
@@ -977,6 +1471,7 @@ impl Expr {
                             ComptimeVal::Bool(val) => val,
                             ComptimeVal::Int(val) => val != 0,
                             ComptimeVal::Float(val) => val != 0.0,
+                            ComptimeVal::Array(..) => unreachable!(),
                         };
                         Some(ComptimeVal::bool(expr))
                     }
@@ -985,6 +1480,7 @@ impl Expr {
                             ComptimeVal::Bool(val) => val as i32,
                             ComptimeVal::Int(val) => val,
                             ComptimeVal::Float(val) => val as i32,
+                            ComptimeVal::Array(..) => unreachable!(),
                         };
                         Some(ComptimeVal::int(expr))
                     }
@@ -993,10 +1489,13 @@ impl Expr {
                             ComptimeVal::Bool(val) => val as i32 as f32,
                             ComptimeVal::Int(val) => val as f32,
                             ComptimeVal::Float(val) => val,
+                            ComptimeVal::Array(..) => unreachable!(),
                         };
                         Some(ComptimeVal::float(expr))
                     }
-                    Tk::Void | Tk::Func(..) => {
+                    // Array-to-pointer decay cannot be folded
+                    Tk::Array(..) | Tk::Ptr(..) => None,
+                    Tk::Void | Tk::Str | Tk::Func(..) => {
                         panic!("unsupported type coercion")
                     }
                 }
@@ -1007,23 +1506,49 @@ impl Expr {
     /// Type check the expression.
     /// If `expect` is `Some`, the expression is expected to be coerced to the
     /// given type.
-    pub fn type_check(self, expect: Option<&Type>, symtable: &SymbolTable) -> Self {
+    pub fn type_check(
+        self,
+        expect: Option<&Type>,
+        symtable: &mut SymbolTable,
+    ) -> Result<Self, Diagnostic> {
         // If the expression is already known, and no expected type is
         // given, return the expression as is.
         if self.ty.is_some() && expect.is_none() {
-            return self;
+            return Ok(self);
         }
 
+        let span = self.span;
+
         let mut expr = match self.kind {
-            ExprKind::Const(_) => self,
+            ExprKind::Const(_) | ExprKind::Str(_) => self,
+            ExprKind::Binary(op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr), lhs, rhs) => {
+                // The operands are conditions, and the result is a boolean
+                let lhs = lhs.type_check(Some(&Type::bool()), symtable)?;
+                let rhs = rhs.type_check(Some(&Type::bool()), symtable)?;
+
+                let mut expr = Expr::binary(op, lhs, rhs);
+                expr.ty = Some(Type::bool());
+                expr
+            }
             ExprKind::Binary(op, lhs, rhs) => {
                 // Type check the left and right hand side expressions
-                let mut lhs = lhs.type_check(None, symtable);
-                let mut rhs = rhs.type_check(None, symtable);
+                let mut lhs = lhs.type_check(None, symtable)?;
+                let mut rhs = rhs.type_check(None, symtable)?;
 
                 let lhs_ty = lhs.ty();
                 let rhs_ty = rhs.ty();
 
+                // `%` is only defined on integers
+                if op == BinaryOp::Mod && (lhs_ty.is_float() || rhs_ty.is_float()) {
+                    return Err(Diagnostic::error(
+                        ErrorCode::InvalidOperand,
+                        format!("cannot apply `{:?}` to `{}` and `{}`", op, lhs_ty, rhs_ty),
+                        span,
+                    )
+                    .with_secondary(lhs.span, format!("`{}`", lhs_ty))
+                    .with_secondary(rhs.span, format!("`{}`", rhs_ty)));
+                }
+
                 // Coerce the types if needed
                 match (lhs_ty.kind(), rhs_ty.kind()) {
                     (Tk::Bool, Tk::Int) => {
@@ -1045,12 +1570,25 @@ impl Expr {
                         rhs = Expr::coercion(rhs, Type::float());
                     }
                     _ => {
-                        if lhs_ty != rhs_ty {
-                            panic!("unsupported type coercion: {:?} -> {:?}", lhs_ty, rhs_ty);
+                        if lhs_ty != rhs_ty || !lhs_ty.is_scalar() {
+                            return Err(Diagnostic::error(
+                                ErrorCode::InvalidOperand,
+                                format!(
+                                    "cannot apply `{:?}` to `{}` and `{}`",
+                                    op, lhs_ty, rhs_ty
+                                ),
+                                span,
+                            )
+                            .with_secondary(lhs.span, format!("`{}`", lhs_ty))
+                            .with_secondary(rhs.span, format!("`{}`", rhs_ty)));
                         }
                     }
                 }
 
+                if matches!(op, BinaryOp::Div | BinaryOp::Mod) {
+                    Self::check_division(op, &lhs, &rhs, span, symtable);
+                }
+
                 let lhs_ty = lhs.ty().clone();
 
                 // Create the binary expression
@@ -1060,51 +1598,128 @@ impl Expr {
                     | BinaryOp::Sub
                     | BinaryOp::Mul
                     | BinaryOp::Div
-                    | BinaryOp::Mod
-                    | BinaryOp::Lt
+                    | BinaryOp::Mod => {
+                        expr.ty = Some(lhs_ty.clone());
+                    }
+                    // Comparisons always result in booleans
+                    BinaryOp::Lt
                     | BinaryOp::Gt
                     | BinaryOp::Le
                     | BinaryOp::Ge
                     | BinaryOp::Eq
-                    | BinaryOp::Ne
-                    | BinaryOp::LogicalAnd
-                    | BinaryOp::LogicalOr => {
-                        expr.ty = Some(lhs_ty.clone());
-                    } // TODO: support other binary operations
+                    | BinaryOp::Ne => {
+                        expr.ty = Some(Type::bool());
+                    }
+                    BinaryOp::LogicalAnd | BinaryOp::LogicalOr => unreachable!(),
                 }
                 expr
             }
             ExprKind::Coercion(_) => unreachable!(),
             ExprKind::FuncCall(FuncCall { ident, args }) => {
                 // Lookup the function in the symbol table
-                let entry = symtable.lookup(&ident).unwrap();
-
-                let (param_tys, ret_ty) = entry.ty.unwrap_func();
+                let entry = symtable.lookup(&ident).ok_or_else(|| {
+                    Diagnostic::error(
+                        ErrorCode::UndeclaredIdent,
+                        format!("cannot find function `{}` in this scope", ident),
+                        span,
+                    )
+                    .with_label("not found in this scope")
+                })?;
+
+                if !matches!(entry.ty.kind(), Tk::Func(..)) {
+                    return Err(Diagnostic::error(
+                        ErrorCode::NotAFunction,
+                        format!("`{}` of type `{}` is not a function", ident, entry.ty),
+                        span,
+                    )
+                    .with_label("cannot be called"));
+                }
+                // The arguments may report warnings into the symbol table
+                let func_ty = entry.ty.clone();
+                let (param_tys, ret_ty) = func_ty.unwrap_func();
+                let variadic = func_ty.is_variadic();
+
+                // Variadic functions take at least the declared params
+                if args.len() < param_tys.len() || !variadic && args.len() > param_tys.len() {
+                    let plural = |n: usize| if n == 1 { "" } else { "s" };
+                    let mut diag = Diagnostic::error(
+                        ErrorCode::ArgCountMismatch,
+                        format!(
+                            "function `{}` takes {}{} argument{} but {} argument{} supplied",
+                            ident,
+                            if variadic { "at least " } else { "" },
+                            param_tys.len(),
+                            plural(param_tys.len()),
+                            args.len(),
+                            if args.len() == 1 { " was" } else { "s were" },
+                        ),
+                        span,
+                    );
+                    if let Some(decl_span) = entry.span {
+                        diag = diag.with_secondary(decl_span, "function defined here");
+                    }
+                    return Err(diag);
+                }
 
-                // Type check the arguments
+                // Type check the arguments. The variadic ones have no expected type,
+                // but booleans are promoted to int, and floats are promoted to
+                // double in irgen.
                 let args = args
                     .into_iter()
-                    .zip(param_tys)
-                    .map(|(arg, ty)| arg.type_check(Some(ty), symtable))
-                    .collect();
+                    .enumerate()
+                    .map(|(i, arg)| match param_tys.get(i) {
+                        Some(ty) => arg.type_check(Some(ty), symtable),
+                        None => {
+                            let arg = arg.type_check(None, symtable)?;
+                            if !arg.ty().is_scalar() {
+                                return Err(Diagnostic::error(
+                                    ErrorCode::MismatchedTypes,
+                                    format!("cannot pass `{}` as a variadic argument", arg.ty()),
+                                    arg.span,
+                                ));
+                            }
+                            if arg.ty().is_bool() {
+                                Ok(Expr::coercion(arg, Type::int()))
+                            } else {
+                                Ok(arg)
+                            }
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
 
                 // Create the function call expression
                 let mut expr = Expr::func_call(ident, args);
                 expr.ty = Some(ret_ty.clone());
                 expr
             }
-            ExprKind::LVal(LVal { ident }) => {
-                // Lookup the variable in the symbol table
-                let entry = symtable.lookup(&ident).unwrap();
+            ExprKind::LVal(lval) => {
+                // Type check the left value, including the indices
+                let (lval, ty) = lval.type_check(symtable)?;
 
                 // Create the left value expression
-                let mut expr = Expr::lval(LVal { ident });
-                expr.ty = Some(entry.ty.clone());
+                let mut expr = Expr::lval(lval);
+                expr.ty = Some(ty);
                 expr
             }
+            ExprKind::InitList(_) => {
+                return Err(Diagnostic::error(
+                    ErrorCode::InvalidInitList,
+                    "initializer list is only allowed in declarations",
+                    span,
+                ));
+            }
             ExprKind::Unary(op, expr) => {
                 // Type check the expression
-                let mut expr = expr.type_check(None, symtable);
+                let mut expr = expr.type_check(None, symtable)?;
+
+                let invalid_operand = |expr: &Expr| {
+                    Diagnostic::error(
+                        ErrorCode::InvalidOperand,
+                        format!("cannot apply `{:?}` to `{}`", op, expr.ty()),
+                        span,
+                    )
+                    .with_secondary(expr.span, format!("`{}`", expr.ty()))
+                };
 
                 // Coerce the expression to int if needed
                 let ty = match op {
@@ -1117,25 +1732,15 @@ impl Expr {
                         if ty.is_int() || ty.is_float() {
                             ty.clone()
                         } else {
-                            panic!("unsupported type for negation: {:?}", ty);
+                            return Err(invalid_operand(&expr));
                         }
                     }
                     UnaryOp::Not => {
-                        let ty = expr.ty();
-                        if ty.is_bool() {
-                            // Do nothing
-                        } else if ty.is_int() || ty.is_float() {
-                            // TODO: How do we convert int to bool?
-                            let zero = if ty.is_int() {
-                                Expr::const_(ComptimeVal::int(0))
-                            } else {
-                                Expr::const_(ComptimeVal::float(0.0))
-                            };
-
-                            expr = Expr::binary(BinaryOp::Ne, expr, zero);
-                        } else {
-                            panic!("unsupported type for logical not: {:?}", ty);
+                        // The operand is a condition, converted to bool
+                        if !expr.ty().is_scalar() {
+                            return Err(invalid_operand(&expr));
                         }
+                        expr = Expr::coercion(expr, Type::bool());
                         Type::bool()
                     }
                 };
@@ -1147,30 +1752,354 @@ impl Expr {
             }
         };
 
+        expr.span = span;
+
         // Coerce the expression to the expected type if needed
         if let Some(ty) = expect {
-            if ty.is_int() || ty.is_bool() {
-                match ty.kind() {
-                    Tk::Bool => expr = Expr::coercion(expr, Type::bool()),
-                    Tk::Int => expr = Expr::coercion(expr, Type::int()),
-                    Tk::Float => expr = Expr::coercion(expr, Type::float()),
-                    Tk::Func(..) | Tk::Void => {
-                        unreachable!()
+            let mismatched = |expr: &Expr| {
+                Diagnostic::error(
+                    ErrorCode::MismatchedTypes,
+                    format!("mismatched types: expected `{}`, found `{}`", ty, expr.ty()),
+                    expr.span,
+                )
+                .with_label(format!("expected `{}`", ty))
+            };
+
+            if ty.is_scalar() {
+                // Scalars can be converted to each other
+                if !expr.ty().is_scalar() {
+                    return Err(mismatched(&expr));
+                }
+                expr = Expr::coercion(expr, ty.clone());
+            } else if let Tk::Ptr(elem) = ty.kind() {
+                match expr.ty().kind() {
+                    // Array-to-pointer decay, e.g., passing `int a[2][3]` or `a[1]` to
+                    // `int b[][3]` or `int b[]`
+                    Tk::Array(expr_elem, _) if expr_elem == elem => {
+                        expr = Expr::coercion(expr, ty.clone());
                     }
+                    Tk::Ptr(expr_elem) if expr_elem == elem => {}
+                    _ => return Err(mismatched(&expr)),
                 }
-                expr.ty = Some(ty.clone());
             } else if ty != expr.ty() {
-                panic!("unsupported type coercion: {:?}", ty);
+                return Err(mismatched(&expr));
             }
         }
 
         // try to fold the expression into a constant value
         if let Some(comptime) = expr.try_fold(symtable) {
-            expr = Expr::const_(comptime);
+            expr = Expr::const_(comptime).with_span(span);
+        }
+
+        Ok(expr)
+    }
+
+    /// Warn about an integer division or remainder that is known to be
+    /// undefined, i.e., by zero or `INT_MIN / -1`. Such operations are not
+    /// folded, and are left to the runtime.
+    fn check_division(
+        op: BinaryOp,
+        lhs: &Expr,
+        rhs: &Expr,
+        span: Span,
+        symtable: &mut SymbolTable,
+    ) {
+        if lhs.ty().is_float() {
+            return;
+        }
+        let divisor = rhs.try_fold(symtable).map(|val| val.unwrap_int());
+        let dividend = lhs.try_fold(symtable).map(|val| val.unwrap_int());
+        let label = match (op, dividend, divisor) {
+            (BinaryOp::Div, _, Some(0)) => "attempt to divide by zero".to_string(),
+            (BinaryOp::Mod, _, Some(0)) => {
+                "attempt to calculate the remainder with a divisor of zero".to_string()
+            }
+            (BinaryOp::Div, Some(i32::MIN), Some(-1)) => {
+                format!("attempt to divide `{}` by `-1`, which overflows", i32::MIN)
+            }
+            (BinaryOp::Mod, Some(i32::MIN), Some(-1)) => format!(
+                "attempt to calculate the remainder of `{}` divided by `-1`, which overflows",
+                i32::MIN
+            ),
+            _ => return,
+        };
+        symtable.diagnostics.push(
+            Diagnostic::warning(
+                ErrorCode::UndefinedDivision,
+                "this operation is undefined at runtime",
+                span,
+            )
+            .with_label(label)
+            .with_note("the expression is not folded into a constant"),
+        );
+    }
+
+    /// Type check the initializer of a declaration with type `ty`.
+    ///
+    /// Scalars are type checked as normal expressions, and initializer lists of
+    /// arrays are normalized following the brace elision rules of SysY (and C).
+    /// The elements are filled into the array in row-major order, and a nested
+    /// list initializes the largest sub-array aligned at the current position.
+    /// e.g. for `int a[2][3]`, `{1, 2, 3, {4}}` is normalized into
+    /// `{{1, 2, 3}, {4}}`.
+    pub fn type_check_init(
+        self,
+        ty: &Type,
+        symtable: &mut SymbolTable,
+    ) -> Result<Self, Diagnostic> {
+        let span = self.span;
+        match self.kind {
+            ExprKind::InitList(elems) => {
+                if !ty.is_array() {
+                    return Err(Diagnostic::error(
+                        ErrorCode::InvalidInitList,
+                        format!("scalar of type `{}` cannot be initialized with a list", ty),
+                        span,
+                    ));
+                }
+                let (base, dims) = ty.split_dims();
+
+                // sizes[i] is the number of scalars in the sub-array of level i, the last
+                // one is the scalar itself.
+                let mut sizes = vec![1; dims.len() + 1];
+                for i in (0..dims.len()).rev() {
+                    sizes[i] = sizes[i + 1] * dims[i];
+                }
+
+                let mut flattened = Vec::new();
+                Self::flatten_init_list(elems, base, &sizes, 0, symtable, &mut flattened)?;
+                // The nested lists are rebuilt and have no exact counterpart in the source,
+                // only the outermost list keeps its span.
+                Ok(
+                    Self::build_init_list(&mut flattened.into_iter().peekable(), ty, 0)
+                        .with_span(span),
+                )
+            }
+            _ => {
+                if ty.is_array() {
+                    return Err(Diagnostic::error(
+                        ErrorCode::InvalidInitList,
+                        format!("array of type `{}` must be initialized with a list", ty),
+                        span,
+                    ));
+                }
+                self.type_check(Some(ty), symtable)
+            }
+        }
+    }
+
+    /// Type check the elements in an initializer list and flatten them into
+    /// `(index, scalar)` pairs, where `index` is the row-major index of the
+    /// scalar in the whole array.
+    ///
+    /// `sizes[0]` is the number of scalars in the sub-array initialized by this
+    /// list, which starts at `offset`.
+    fn flatten_init_list(
+        elems: Vec<Expr>,
+        base: &Type,
+        sizes: &[usize],
+        offset: usize,
+        symtable: &mut SymbolTable,
+        flattened: &mut Vec<(usize, Expr)>,
+    ) -> Result<(), Diagnostic> {
+        let mut cursor = offset;
+        for elem in elems {
+            if cursor >= offset + sizes[0] {
+                return Err(Diagnostic::error(
+                    ErrorCode::InvalidInitList,
+                    "excess elements in array initializer",
+                    elem.span,
+                )
+                .with_label(format!("expected at most {} elements", sizes[0])));
+            }
+            match elem {
+                Expr {
+                    kind: ExprKind::InitList(elems),
+                    ..
+                } => {
+                    // Find the largest sub-array aligned at the cursor. Braces around a
+                    // scalar just initialize the scalar itself.
+                    let level = sizes[1..]
+                        .iter()
+                        .position(|size| (cursor - offset).is_multiple_of(*size))
+                        .map_or(0, |level| level + 1);
+                    Self::flatten_init_list(
+                        elems,
+                        base,
+                        &sizes[level..],
+                        cursor,
+                        symtable,
+                        flattened,
+                    )?;
+                    cursor += sizes[level];
+                }
+                elem => {
+                    flattened.push((cursor, elem.type_check(Some(base), symtable)?));
+                    cursor += 1;
+                }
+            }
         }
+        Ok(())
+    }
 
+    /// Build the normalized initializer list of type `ty` starting at the
+    /// row-major index `offset` from the flattened scalars.
+    fn build_init_list(
+        flattened: &mut std::iter::Peekable<std::vec::IntoIter<(usize, Expr)>>,
+        ty: &Type,
+        offset: usize,
+    ) -> Self {
+        let (elem_ty, len) = match ty.kind() {
+            Tk::Array(elem_ty, len) => (elem_ty, *len),
+            _ => unreachable!(),
+        };
+        // The number of scalars in each element.
+        let stride: usize = elem_ty.split_dims().1.iter().product();
+
+        let mut elems = Vec::new();
+        while let Some((index, _)) = flattened.peek() {
+            if *index >= offset + stride * len {
+                break;
+            }
+            let i = (index - offset) / stride;
+            // Fill the holes before this element with zeros
+            while elems.len() < i {
+                elems.push(Self::zero_init(elem_ty));
+            }
+            if elem_ty.is_array() {
+                elems.push(Self::build_init_list(flattened, elem_ty, offset + i * stride));
+            } else {
+                elems.push(flattened.next().unwrap().1);
+            }
+        }
+
+        let mut expr = Expr::init_list(elems);
+        expr.ty = Some(ty.clone());
         expr
     }
+
+    /// Create a zero initializer of the given type.
+    fn zero_init(ty: &Type) -> Self {
+        match ty.kind() {
+            Tk::Bool => Expr::const_(ComptimeVal::bool(false)),
+            Tk::Int => Expr::const_(ComptimeVal::int(0)),
+            Tk::Float => Expr::const_(ComptimeVal::float(0.0)),
+            Tk::Array(..) => {
+                let mut expr = Expr::init_list(vec![]);
+                expr.ty = Some(ty.clone());
+                expr
+            }
+            Tk::Void | Tk::Ptr(..) | Tk::Str | Tk::Func(..) => unreachable!(),
+        }
+    }
+
+    /// Find the first element in the (type checked) initializer that is not a
+    /// constant.
+    pub fn find_non_const_init(&self) -> Option<&Expr> {
+        match &self.kind {
+            ExprKind::Const(_) => None,
+            ExprKind::InitList(elems) => elems.iter().find_map(Expr::find_non_const_init),
+            _ => Some(self),
+        }
+    }
+}
+
+impl LVal {
+    /// Type check the left value and get the type of it.
+    ///
+    /// The type can be an array type if the left value is not fully indexed.
+    pub fn type_check(self, symtable: &mut SymbolTable) -> Result<(Self, Type), Diagnostic> {
+        let LVal {
+            ident,
+            indices,
+            span,
+        } = self;
+
+        // Lookup the variable in the symbol table
+        let entry = symtable.lookup(&ident).ok_or_else(|| {
+            Diagnostic::error(
+                ErrorCode::UndeclaredIdent,
+                format!("cannot find `{}` in this scope", ident),
+                span,
+            )
+            .with_label("not found in this scope")
+        })?;
+        let mut ty = entry.ty.clone();
+
+        // Type check the indices, each of them removes one dimension
+        let mut new_indices = Vec::new();
+        for index in indices {
+            ty = match ty.indexed() {
+                Some(elem) => elem.clone(),
+                None => {
+                    return Err(Diagnostic::error(
+                        ErrorCode::NotAnArray,
+                        format!("cannot index into a value of type `{}`", ty),
+                        index.span,
+                    )
+                    .with_secondary(span, format!("`{}` is not an array", ident)));
+                }
+            };
+            new_indices.push(index.type_check(Some(&Type::int()), symtable)?);
+        }
+
+        let lval = LVal {
+            ident,
+            indices: new_indices,
+            span,
+        };
+        Ok((lval, ty))
+    }
+}
+
+/// Create a redeclaration error of `name` declared at `span`.
+fn redeclaration(name: &str, span: Span, prev: &SymbolEntry) -> Diagnostic {
+    let diag = Diagnostic::error(
+        ErrorCode::Redeclaration,
+        format!("`{}` is declared more than once in the same scope", name),
+        span,
+    )
+    .with_label("redeclared here");
+    match prev.span {
+        Some(prev_span) => diag.with_secondary(prev_span, "previously declared here"),
+        None => diag.with_note(format!("`{}` is a library function", name)),
+    }
+}
+
+/// Type check the dimensions of an array and fold them into constants.
+///
+/// Returns the lengths of the dimensions.
+fn type_check_dims(
+    dims: &mut [Expr],
+    symtable: &mut SymbolTable,
+) -> Result<Vec<usize>, Diagnostic> {
+    let mut lens = Vec::new();
+    for dim in dims.iter_mut() {
+        let span = dim.span;
+        let expr = std::mem::replace(dim, Expr::const_(ComptimeVal::int(0)));
+        let len = expr
+            .type_check(Some(&Type::int()), symtable)?
+            .try_fold(symtable)
+            .ok_or_else(|| {
+                Diagnostic::error(
+                    ErrorCode::NonConstant,
+                    "array dimension is not a constant",
+                    span,
+                )
+                .with_label("not a constant")
+            })?
+            .unwrap_int();
+        if len < 0 {
+            return Err(Diagnostic::error(
+                ErrorCode::InvalidArrayDim,
+                format!("array dimension is negative: {}", len),
+                span,
+            ));
+        }
+        *dim = Expr::const_(ComptimeVal::int(len)).with_span(span);
+        lens.push(len as usize);
+    }
+    Ok(lens)
 }
 
 use std::fmt::{self, Display};
@@ -1257,7 +2186,12 @@ impl TreeDisplay for Item {
                 for (i, param) in func.params.iter().enumerate() {
                     let is_last_param = i == func.params.len() - 1;
                     tf.write_branch(is_last_param && func.body.items.is_empty())?;
-                    writeln!(tf.f, "Param {:?} {}", param.ty, param.ident)?;
+                    write!(tf.f, "Param {:?} {}", param.ty, param.ident)?;
+                    if let Some(dims) = &param.dims {
+                        write!(tf.f, "[]")?;
+                        fmt_dims(tf.f, dims)?;
+                    }
+                    writeln!(tf.f)?;
                 }
 
                 // Format body
@@ -1282,7 +2216,9 @@ impl TreeDisplay for Decl {
                 tf.begin_child(is_last);
                 for (i, def) in decl.defs.iter().enumerate() {
                     tf.write_branch(i == decl.defs.len() - 1)?;
-                    write!(tf.f, "{} = ", def.ident)?;
+                    write!(tf.f, "{}", def.ident)?;
+                    fmt_dims(tf.f, &def.dims)?;
+                    write!(tf.f, " = ")?;
                     def.init.fmt_expr(tf.f)?;
                     writeln!(tf.f)?;
                 }
@@ -1296,6 +2232,7 @@ impl TreeDisplay for Decl {
                 for (i, def) in decl.defs.iter().enumerate() {
                     tf.write_branch(i == decl.defs.len() - 1)?;
                     write!(tf.f, "{}", def.ident)?;
+                    fmt_dims(tf.f, &def.dims)?;
                     if let Some(init) = &def.init {
                         write!(tf.f, " = ")?;
                         init.fmt_expr(tf.f)?;
@@ -1330,22 +2267,23 @@ impl TreeDisplay for Block {
 
 impl TreeDisplay for Stmt {
     fn fmt_tree(&self, tf: &mut TreeFormatter, is_last: bool) -> fmt::Result {
-        match self {
-            Stmt::Assign(lval, expr) => {
+        match &self.kind {
+            StmtKind::Assign(lval, expr) => {
                 tf.write_branch(is_last)?;
-                write!(tf.f, "{} = ", lval.ident)?;
+                lval.fmt_expr(tf.f)?;
+                write!(tf.f, " = ")?;
                 expr.fmt_expr(tf.f)?;
                 writeln!(tf.f)
             }
-            Stmt::Expr(ExprStmt { expr }) => {
+            StmtKind::Expr(ExprStmt { expr }) => {
                 tf.write_branch(is_last)?;
                 if let Some(expr) = expr {
                     expr.fmt_expr(tf.f)?;
                 }
                 writeln!(tf.f)
             }
-            Stmt::Block(block) => block.fmt_tree(tf, is_last),
-            Stmt::If(cond, then_block, else_block) => {
+            StmtKind::Block(block) => block.fmt_tree(tf, is_last),
+            StmtKind::If(cond, then_block, else_block) => {
                 tf.write_branch(is_last)?;
                 write!(tf.f, "if ")?;
                 cond.fmt_expr(tf.f)?;
@@ -1361,7 +2299,7 @@ impl TreeDisplay for Stmt {
                 tf.end_child();
                 Ok(())
             }
-            Stmt::While(cond, block) => {
+            StmtKind::While(cond, block) => {
                 tf.write_branch(is_last)?;
                 write!(tf.f, "while ")?;
                 cond.fmt_expr(tf.f)?;
@@ -1372,15 +2310,15 @@ impl TreeDisplay for Stmt {
                 tf.end_child();
                 Ok(())
             }
-            Stmt::Break => {
+            StmtKind::Break => {
                 tf.write_branch(is_last)?;
                 writeln!(tf.f, "break")
             }
-            Stmt::Continue => {
+            StmtKind::Continue => {
                 tf.write_branch(is_last)?;
                 writeln!(tf.f, "continue")
             }
-            Stmt::Return(ReturnStmt { expr }) => {
+            StmtKind::Return(ReturnStmt { expr }) => {
                 tf.write_branch(is_last)?;
                 write!(tf.f, "return")?;
                 if let Some(expr) = expr {
@@ -1405,6 +2343,7 @@ impl ExprFormat for Expr {
                 ComptimeVal::Bool(b) => write!(f, "{}", b),
                 ComptimeVal::Int(i) => write!(f, "{}", i),
                 ComptimeVal::Float(fl) => write!(f, "{}", fl),
+                ComptimeVal::Array(..) => write!(f, "{:?}", val),
             },
             ExprKind::Binary(op, lhs, rhs) => {
                 write!(f, "(")?;
@@ -1427,11 +2366,624 @@ impl ExprFormat for Expr {
                 }
                 write!(f, ")")
             }
-            ExprKind::LVal(LVal { ident }) => write!(f, "{}", ident),
+            ExprKind::LVal(lval) => lval.fmt_expr(f),
             ExprKind::Coercion(expr) => {
                 write!(f, "({:?})", self.ty.as_ref().unwrap())?;
                 expr.fmt_expr(f)
             }
+            ExprKind::InitList(elems) => {
+                write!(f, "{{")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    elem.fmt_expr(f)?;
+                }
+                write!(f, "}}")
+            }
+            ExprKind::Str(value) => write!(f, "{:?}", String::from_utf8_lossy(value)),
+        }
+    }
+}
+
+impl ExprFormat for LVal {
+    fn fmt_expr(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.ident)?;
+        fmt_dims(f, &self.indices)
+    }
+}
+
+/// Format the dimensions of arrays or the indices of left values.
+fn fmt_dims(f: &mut fmt::Formatter<'_>, dims: &[Expr]) -> fmt::Result {
+    for dim in dims {
+        write!(f, "[")?;
+        dim.fmt_expr(f)?;
+        write!(f, "]")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::{Severity, SysYParser};
+
+    fn type_check(src: &str) -> CompUnit {
+        let mut ast = SysYParser::new().parse(src).unwrap();
+        if let Err(diagnostics) = ast.type_check() {
+            panic!("{}", diagnostics.render("test.sy", src));
+        }
+        ast
+    }
+
+    /// Type check the source and get the error codes.
+    fn type_check_err(src: &str) -> Vec<ErrorCode> {
+        let mut ast = SysYParser::new().parse(src).unwrap();
+        let diagnostics = ast.type_check().unwrap_err();
+        diagnostics.into_iter().map(|diag| diag.code).collect()
+    }
+
+    #[test]
+    fn test_init_list_brace_elision() {
+        let ast = type_check(
+            r#"
+            const int a[2][3] = {1, 2, 3, {4}};
+            int b[4][2] = {1, 2, {3}, {5, 6}, 7};
+            int c[2][3][4] = {1, {2}, {3}};
+            int d[3][2] = {{}, {1}};
+            int e[3] = {};
+            "#,
+        );
+        let tree = ast.to_string();
+        assert!(tree.contains("a[2][3] = {{1, 2, 3}, {4}}"));
+        assert!(tree.contains("b[4][2] = {{1, 2}, {3}, {5, 6}, {7}}"));
+        assert!(tree.contains("c[2][3][4] = {{{1, 2, 3}}}"));
+        assert!(tree.contains("d[3][2] = {{}, {1}}"));
+        assert!(tree.contains("e[3] = {}"));
+    }
+
+    #[test]
+    fn test_array_dims_fold() {
+        let ast = type_check(
+            r#"
+            const int N = 2;
+            int a[N + 1][N * 2] = {{1}, {N}};
+            "#,
+        );
+        assert!(ast.to_string().contains("a[3][4] = {{1}, {2}}"));
+    }
+
+    #[test]
+    fn test_const_array_fold() {
+        let ast = type_check(
+            r#"
+            const int N[3] = {1, 2, 3};
+            const int M[2][2] = {{4}, {N[2], 6}};
+            int a[N[1]][M[1][0]] = {M[0][1], N[0]};
+            int main() {
+                int i = 0;
+                return N[i] + M[1][1];
+            }
+            "#,
+        );
+        let tree = ast.to_string();
+        // Missing elements of constant arrays are zeros
+        assert!(tree.contains("a[2][3] = {{0, 1}}"));
+        // Only constant indices are folded
+        assert!(tree.contains("N[i] Add 6"));
+    }
+
+    #[test]
+    fn test_init_list_excess_elements() {
+        assert_eq!(
+            type_check_err("int a[2][2] = {1, 2, 3, 4, 5};"),
+            [ErrorCode::InvalidInitList]
+        );
+    }
+
+    #[test]
+    fn test_array_param_decay() {
+        let ast = type_check(
+            r#"
+            int f(int a[][3], int b[]) { return a[1][2] + b[0]; }
+            int main() {
+                int x[2][3] = {};
+                return f(x, x[1]);
+            }
+            "#,
+        );
+
+        let Item::FuncDef(f) = &ast.items[0] else {
+            panic!("expected function definition");
+        };
+        assert_eq!(f.params[0].ty, Type::ptr(Type::array(Type::int(), 3)));
+        assert_eq!(f.params[1].ty, Type::ptr(Type::int()));
+
+        let Item::FuncDef(main) = &ast.items[1] else {
+            panic!("expected function definition");
+        };
+        let Some(BlockItem::Stmt(Stmt {
+            kind: StmtKind::Return(ReturnStmt { expr: Some(expr) }),
+            ..
+        })) = main.body.items.last()
+        else {
+            panic!("expected return statement");
+        };
+        let ExprKind::FuncCall(FuncCall { args, .. }) = &expr.kind else {
+            panic!("expected function call");
+        };
+        // Both arguments are decayed into pointers.
+        assert!(matches!(args[0].kind, ExprKind::Coercion(_)));
+        assert_eq!(args[0].ty(), &Type::ptr(Type::array(Type::int(), 3)));
+        assert!(matches!(args[1].kind, ExprKind::Coercion(_)));
+        assert_eq!(args[1].ty(), &Type::ptr(Type::int()));
+    }
+
+    #[test]
+    fn test_index_scalar() {
+        assert_eq!(
+            type_check_err("int main() { int a = 1; return a[0]; }"),
+            [ErrorCode::NotAnArray]
+        );
+    }
+
+    #[test]
+    fn test_float_mod() {
+        assert_eq!(
+            type_check_err("int main() { float a = 1.0 % 2.0; return 0; }"),
+            [ErrorCode::InvalidOperand]
+        );
+        assert_eq!(
+            type_check_err("int main() { float a = 1.0; return 3 % a; }"),
+            [ErrorCode::InvalidOperand]
+        );
+    }
+
+    #[test]
+    fn test_non_constant_global_init() {
+        let src = r#"
+            int a = getint();
+            int b[2] = {1, a};
+            const int c = 2;
+            int d[2] = {c, c * 2};
+            int main() {
+                int e = getint();
+                return e;
+            }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [ErrorCode::NonConstant, ErrorCode::NonConstant]
+        );
+    }
+
+    #[test]
+    fn test_report_all_errors() {
+        let src = r#"
+            int n = 1;
+            const int N = n;
+            int main() {
+                int a[2] = {1, 2};
+                if (x) {
+                    a = 1;
+                }
+                while (a) {}
+                return f(a[0][1]);
+            }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [
+                ErrorCode::NonConstant,
+                ErrorCode::UndeclaredIdent,
+                ErrorCode::NotAssignable,
+                ErrorCode::MismatchedTypes,
+                ErrorCode::UndeclaredIdent,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outside_loop() {
+        assert_eq!(
+            type_check_err("int main() { if (1) { break; } continue; return 0; }"),
+            [ErrorCode::OutsideLoop, ErrorCode::OutsideLoop]
+        );
+        type_check("int main() { while (1) { if (1) { continue; } break; } return 0; }");
+    }
+
+    #[test]
+    fn test_assign_to_const() {
+        let src = r#"
+            const int a = 1;
+            const int b[2] = {1, 2};
+            int main() {
+                a = 2;
+                b[1] = 3;
+                return 0;
+            }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [ErrorCode::AssignToConst, ErrorCode::AssignToConst]
+        );
+    }
+
+    #[test]
+    fn test_redeclaration() {
+        let src = r#"
+            int a = 0;
+            float a = 1.0;
+            int f(int x, int x) { return 0; }
+            int g(int y) { int y = 1; return y; }
+            int f() { return 1; }
+            int main() { return 0; }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [
+                ErrorCode::Redeclaration,
+                ErrorCode::Redeclaration,
+                ErrorCode::Redeclaration,
+                ErrorCode::Redeclaration,
+            ]
+        );
+
+        // Shadowing in nested scopes is allowed
+        type_check("int a = 0; int main() { int a = 1; { int a = 2; } return a; }");
+    }
+
+    #[test]
+    fn test_arg_count_mismatch() {
+        let src = r#"
+            int f(int a, int b) { return a + b; }
+            int main() { f(1); return f(1, 2, 3); }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [ErrorCode::ArgCountMismatch, ErrorCode::ArgCountMismatch]
+        );
+    }
+
+    #[test]
+    fn test_sysylib() {
+        type_check(
+            r#"
+            int main() {
+                int a[4] = {}, b[2][3] = {};
+                float c[4] = {};
+                int n = getarray(a) + getfarray(c) + getint() + getch();
+                float f = getfloat();
+                putarray(n, b[1]);
+                putfarray(4, c);
+                putint(a[0]);
+                putch(10);
+                putfloat(f);
+                _sysy_starttime(1);
+                _sysy_stoptime(2);
+                return 0;
+            }
+            "#,
+        );
+
+        let src = r#"
+            int main() {
+                int a[4] = {};
+                putf();
+                getint(1);
+                putint(a);
+                getfarray(a);
+                return 0;
+            }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [
+                ErrorCode::ArgCountMismatch,
+                ErrorCode::ArgCountMismatch,
+                ErrorCode::MismatchedTypes,
+                ErrorCode::MismatchedTypes,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_putf_string() {
+        let ast = type_check(
+            r#"
+            int main() {
+                int a = 1;
+                putf("%d: %f\n", a, 1.5);
+                putf("a > 0: %d\n", a > 0);
+                putf("done\n");
+                return 0;
+            }
+            "#,
+        );
+        let Item::FuncDef(FuncDef { body, .. }) = &ast.items[0] else {
+            panic!("expected function definition");
+        };
+        let BlockItem::Stmt(stmt) = &body.items[1] else {
+            panic!("expected statement");
+        };
+        let StmtKind::Expr(ExprStmt { expr: Some(expr) }) = &stmt.kind else {
+            panic!("expected expression statement");
+        };
+        let ExprKind::FuncCall(FuncCall { args, .. }) = &expr.kind else {
+            panic!("expected function call");
+        };
+        assert_eq!(args[0].kind, ExprKind::Str(b"%d: %f\n".to_vec()));
+        assert_eq!(args[0].ty(), &Type::str());
+
+        let src = r#"
+            int main() {
+                putf(1);
+                putint("1");
+                putf("%s", "a");
+                return 0;
+            }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [
+                ErrorCode::MismatchedTypes,
+                ErrorCode::MismatchedTypes,
+                ErrorCode::MismatchedTypes,
+            ]
+        );
+
+        // Strings are not expressions
+        assert!(SysYParser::new()
+            .parse(r#"int main() { int a = "a"; return 0; }"#)
+            .is_err());
+    }
+
+    #[test]
+    fn test_comparison_type() {
+        let ast = type_check("int main() { int a = 1; float b = 2.0; return a < b; }");
+        let Item::FuncDef(main) = &ast.items[0] else {
+            panic!("expected function definition");
+        };
+        let BlockItem::Stmt(stmt) = &main.body.items[2] else {
+            panic!("expected statement");
+        };
+        let StmtKind::Return(ReturnStmt { expr: Some(expr) }) = &stmt.kind else {
+            panic!("expected return statement");
+        };
+        // The boolean result is coerced to the return type
+        let ExprKind::Coercion(cmp) = &expr.kind else {
+            panic!("expected coercion to int");
+        };
+        assert_eq!(cmp.ty(), &Type::bool());
+        let ExprKind::Binary(BinaryOp::Lt, lhs, _) = &cmp.kind else {
+            panic!("expected comparison");
+        };
+        assert_eq!(lhs.ty(), &Type::float());
+    }
+
+    #[test]
+    fn test_logical_type() {
+        let ast = type_check("int main() { int a = 1; float b = 2.0; return a && b; }");
+        let Item::FuncDef(main) = &ast.items[0] else {
+            panic!("expected function definition");
+        };
+        let BlockItem::Stmt(stmt) = &main.body.items[2] else {
+            panic!("expected statement");
+        };
+        let StmtKind::Return(ReturnStmt { expr: Some(expr) }) = &stmt.kind else {
+            panic!("expected return statement");
+        };
+        let ExprKind::Coercion(and) = &expr.kind else {
+            panic!("expected coercion to int");
+        };
+        assert_eq!(and.ty(), &Type::bool());
+        // The operands are converted to booleans separately
+        let ExprKind::Binary(BinaryOp::LogicalAnd, lhs, rhs) = &and.kind else {
+            panic!("expected logical and");
+        };
+        let (ExprKind::Coercion(lhs), ExprKind::Coercion(rhs)) = (&lhs.kind, &rhs.kind) else {
+            panic!("expected coercions to bool");
+        };
+        assert_eq!(lhs.ty(), &Type::int());
+        assert_eq!(rhs.ty(), &Type::float());
+    }
+
+    #[test]
+    fn test_wrapping_fold() {
+        let ast = type_check(
+            r#"
+            const int a = 2147483647 + 1;
+            const int b = -2147483648;
+            const int c = 65536 * 65536 + -b;
+            int d[3] = {a, b, c};
+            "#,
+        );
+        assert!(ast
+            .to_string()
+            .contains("d[3] = {-2147483648, -2147483648, -2147483648}"));
+    }
+
+    #[test]
+    fn test_undefined_division() {
+        let ast = type_check(
+            r#"
+            int main() {
+                int a = 1 / 0;
+                int b = -2147483648 / -1;
+                return a % (1 - 1);
+            }
+            "#,
+        );
+        // The operations are left to the runtime
+        let tree = ast.to_string();
+        assert!(tree.contains("(1 Div 0)"));
+        assert!(tree.contains("(-2147483648 Div -1)"));
+
+        let mut ast = SysYParser::new()
+            .parse("int main() { return 1 % 0; }")
+            .unwrap();
+        let warnings = ast.type_check().unwrap();
+        let [warning] = &warnings.into_iter().collect::<Vec<_>>()[..] else {
+            panic!("expected exactly one warning");
+        };
+        assert_eq!(warning.severity, Severity::Warning);
+        assert_eq!(warning.code, ErrorCode::UndefinedDivision);
+
+        // A constant must still be folded
+        assert_eq!(
+            type_check_err("const int c = 1 / 0;"),
+            [ErrorCode::UndefinedDivision, ErrorCode::NonConstant]
+        );
+    }
+
+    /// Folding integer operations should agree with the IR evaluator, i.e., the
+    /// runtime semantics of the target.
+    #[test]
+    fn test_fold_matches_ir_eval() {
+        use crate::ir::{IntBinaryOp as Ib, IntCmpCond as Ic};
+
+        let ops = [
+            (BinaryOp::Add, Ib::Add),
+            (BinaryOp::Sub, Ib::Sub),
+            (BinaryOp::Mul, Ib::Mul),
+            (BinaryOp::Div, Ib::SDiv),
+            (BinaryOp::Mod, Ib::SRem),
+            (BinaryOp::Lt, Ib::ICmp { cond: Ic::Slt }),
+            (BinaryOp::Gt, Ib::ICmp { cond: Ic::Sgt }),
+            (BinaryOp::Le, Ib::ICmp { cond: Ic::Sle }),
+            (BinaryOp::Ge, Ib::ICmp { cond: Ic::Sge }),
+            (BinaryOp::Eq, Ib::ICmp { cond: Ic::Eq }),
+            (BinaryOp::Ne, Ib::ICmp { cond: Ic::Ne }),
+        ];
+
+        // Edge cases, followed by pseudo-random values from a xorshift generator
+        let mut vals = vec![0, 1, -1, 2, -2, i32::MAX, i32::MIN, i32::MIN + 1];
+        let mut state = 0x2545f491u32;
+        for _ in 0..64 {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            vals.push(state as i32);
         }
+
+        let symtable = SymbolTable::default();
+        for &lhs in vals.iter() {
+            for &rhs in vals.iter() {
+                for (op, ir_op) in ops.iter() {
+                    let expr = Expr::binary(
+                        *op,
+                        Expr::const_(ComptimeVal::int(lhs)),
+                        Expr::const_(ComptimeVal::int(rhs)),
+                    );
+                    let folded = expr.try_fold(&symtable).map(|val| val.unwrap_int());
+                    assert_eq!(
+                        folded,
+                        ir_op.eval(lhs, rhs),
+                        "folding `{} {:?} {}`",
+                        lhs,
+                        op,
+                        rhs
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let src = r#"
+            int f(int a) { if (a) { return 1; } }
+            int g(int a) { while (1) { if (a) { break; } return 1; } }
+            int main() { }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [
+                ErrorCode::MissingReturn,
+                ErrorCode::MissingReturn,
+                ErrorCode::MissingReturn,
+            ]
+        );
+
+        type_check(
+            r#"
+            int f(int a) { if (a) return 1; else { return 2; } }
+            int g(int a) { while (1) { while (a) { break; } return a; } }
+            int h() { return 0; h(); }
+            void v() { }
+            int main() { { return 0; } }
+            "#,
+        );
+    }
+
+    #[test]
+    fn test_return_mismatch() {
+        let src = r#"
+            void f() { return 1; }
+            int main() { return; }
+            "#;
+        assert_eq!(
+            type_check_err(src),
+            [ErrorCode::ReturnMismatch, ErrorCode::ReturnMismatch]
+        );
+    }
+
+    #[test]
+    fn test_render_type_error() {
+        let src = "int main() {\n  int a[2] = {};\n  return a + 1;\n}";
+        let mut ast = SysYParser::new().parse(src).unwrap();
+        let rendered = ast.type_check().unwrap_err().render("test.sy", src);
+        assert_eq!(
+            rendered,
+            "\
+error[E0008]: cannot apply `Add` to `int[2]` and `int`
+ --> test.sy:3:10
+  |
+3 |   return a + 1;
+  |          ^^^^^
+  |          - `int[2]`
+  |              - `int`
+
+error: aborting due to 1 previous error(s)
+"
+        );
+    }
+
+    #[test]
+    fn test_spans() {
+        let src = "int main() {\n  int a[2] = {1};\n  return a[0] + (2.5);\n}";
+        let ast = type_check(src);
+        let text = |span: Span| &src[span.start..span.end];
+
+        let Item::FuncDef(main) = &ast.items[0] else {
+            panic!("expected function definition");
+        };
+        assert_eq!(text(main.span), src);
+
+        let BlockItem::Decl(Decl::VarDecl(decl)) = &main.body.items[0] else {
+            panic!("expected variable declaration");
+        };
+        assert_eq!(text(decl.span), "int a[2] = {1};");
+        assert_eq!(text(decl.defs[0].span), "a[2] = {1}");
+        assert_eq!(text(decl.defs[0].init.as_ref().unwrap().span), "{1}");
+
+        let BlockItem::Stmt(stmt) = &main.body.items[1] else {
+            panic!("expected statement");
+        };
+        assert_eq!(text(stmt.span), "return a[0] + (2.5);");
+        let StmtKind::Return(ReturnStmt { expr: Some(expr) }) = &stmt.kind else {
+            panic!("expected return statement");
+        };
+        // The coercions inserted by type checking keep the original spans.
+        let ExprKind::Coercion(expr) = &expr.kind else {
+            panic!("expected coercion to int");
+        };
+        assert_eq!(text(expr.span), "a[0] + (2.5)");
+        let ExprKind::Binary(_, lhs, rhs) = &expr.kind else {
+            panic!("expected binary expression");
+        };
+        assert!(matches!(lhs.kind, ExprKind::Coercion(_)));
+        assert_eq!(text(lhs.span), "a[0]");
+        assert_eq!(text(rhs.span), "(2.5)");
     }
 }
@@ -0,0 +1,343 @@
+//! Diagnostics of SysY programs.
+//!
+//! Errors found in the source program are reported as [`Diagnostic`]s instead
+//! of panicking, and are collected into [`Diagnostics`] so that all errors in a
+//! file can be reported in one run. A diagnostic can be rendered with the
+//! source snippets it points to, in a format similar to rustc:
+//!
+//! ```text
+//! error[E0001]: cannot find `x` in this scope
+//!  --> test.sy:3:5
+//!   |
+//! 3 |     x = 1;
+//!   |     ^ not found in this scope
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use thiserror::Error;
+
+use crate::infra::span::{LineIndex, Span};
+
+/// Severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Note => write!(f, "note"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Error codes of diagnostics.
+///
+/// Each code stands for a class of errors, and is printed as `E` followed by a
+/// four-digit number, e.g., `E0001`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// Use of an identifier that is not declared.
+    UndeclaredIdent = 1,
+    /// The type of an expression does not match the expected type.
+    MismatchedTypes = 2,
+    /// Indexing into a value that is not an array.
+    NotAnArray = 3,
+    /// Calling a value that is not a function.
+    NotAFunction = 4,
+    /// A constant or an array dimension is not a compile-time constant.
+    NonConstant = 5,
+    /// An array dimension is negative.
+    InvalidArrayDim = 6,
+    /// An initializer list does not match the type to initialize.
+    InvalidInitList = 7,
+    /// An operator is applied to operands of unsupported types.
+    InvalidOperand = 8,
+    /// Assigning to something that cannot be assigned to.
+    NotAssignable = 9,
+    /// `break` or `continue` outside of a loop.
+    OutsideLoop = 10,
+    /// Assigning to a constant.
+    AssignToConst = 11,
+    /// Declaring a symbol twice in the same scope.
+    Redeclaration = 12,
+    /// Calling a function with a wrong number of arguments.
+    ArgCountMismatch = 13,
+    /// A non-void function may reach its end without returning a value.
+    MissingReturn = 14,
+    /// A `return` statement does not match the return type of the function.
+    ReturnMismatch = 15,
+    /// An integer division or remainder by zero, or `INT_MIN / -1`, whose
+    /// result is undefined.
+    UndefinedDivision = 16,
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "E{:04}", *self as u32) }
+}
+
+/// A span in the source with an optional message.
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: Option<String>,
+}
+
+/// A diagnostic message pointing into the source.
+#[derive(Debug, Clone, Error)]
+#[error("{severity}[{code}]: {message}")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: ErrorCode,
+    pub message: String,
+    /// The span where the problem is, underlined with `^`.
+    pub primary: Label,
+    /// Related spans, underlined with `-`.
+    pub secondary: Vec<Label>,
+    /// Additional notes attached at the end.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        code: ErrorCode,
+        message: impl Into<String>,
+        span: Span,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            message: message.into(),
+            primary: Label {
+                span,
+                message: None,
+            },
+            secondary: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// Create an error diagnostic pointing at `span`.
+    pub fn error(code: ErrorCode, message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Error, code, message, span)
+    }
+
+    /// Create a warning diagnostic pointing at `span`.
+    pub fn warning(code: ErrorCode, message: impl Into<String>, span: Span) -> Self {
+        Self::new(Severity::Warning, code, message, span)
+    }
+
+    /// Set the message of the primary label.
+    pub fn with_label(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = Some(message.into());
+        self
+    }
+
+    /// Add a secondary label.
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
+            span,
+            message: Some(message.into()),
+        });
+        self
+    }
+
+    /// Add a note.
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Render the diagnostic with source snippets.
+    ///
+    /// `file_name` is only used to print the location, `src` should be the
+    /// source the spans refer to.
+    pub fn render(&self, file_name: &str, src: &str) -> String {
+        self.render_with_index(file_name, &LineIndex::new(src))
+    }
+
+    fn render_with_index(&self, file_name: &str, index: &LineIndex) -> String {
+        // Group the underlines by line. Spans across multiple lines are underlined
+        // to the end of the first line.
+        let mut lines: BTreeMap<usize, Vec<Underline>> = BTreeMap::new();
+        let labels = std::iter::once((&self.primary, true))
+            .chain(self.secondary.iter().map(|label| (label, false)));
+        for (label, primary) in labels {
+            let start = index.line_col(label.span.start);
+            let end = index.line_col(label.span.end);
+            let end_col = if end.line == start.line {
+                end.col
+            } else {
+                index.line(start.line).chars().count() + 1
+            };
+            // Empty spans are still underlined with one character.
+            let end_col = end_col.max(start.col + 1);
+            lines.entry(start.line).or_default().push((
+                start.col,
+                end_col,
+                primary,
+                label.message.as_deref(),
+            ));
+        }
+
+        let width = lines.keys().last().unwrap().to_string().len();
+        let pad = " ".repeat(width);
+        let loc = index.line_col(self.primary.span.start);
+
+        let mut out = String::new();
+        writeln!(out, "{}", self).unwrap();
+        writeln!(out, "{}--> {}:{}", pad, file_name, loc).unwrap();
+        writeln!(out, "{} |", pad).unwrap();
+
+        let mut last_line = None;
+        for (line, marks) in lines.iter_mut() {
+            if matches!(last_line, Some(last) if line - last > 1) {
+                writeln!(out, "...").unwrap();
+            }
+            last_line = Some(*line);
+
+            // Tabs are printed as single spaces to keep the underlines aligned.
+            let text = index.line(*line).replace('\t', " ");
+            writeln!(out, "{:>width$} | {}", line, text, width = width).unwrap();
+
+            // The primary label goes first, then the others from left to right.
+            marks.sort_by_key(|&(start, end, primary, _)| (!primary, start, end));
+            for (start, end, primary, message) in marks.iter() {
+                let mark = if *primary { "^" } else { "-" };
+                write!(
+                    out,
+                    "{} | {}{}",
+                    pad,
+                    " ".repeat(start - 1),
+                    mark.repeat(end - start)
+                )
+                .unwrap();
+                if let Some(message) = message {
+                    write!(out, " {}", message).unwrap();
+                }
+                writeln!(out).unwrap();
+            }
+        }
+
+        if !self.notes.is_empty() {
+            writeln!(out, "{} |", pad).unwrap();
+        }
+        for note in self.notes.iter() {
+            writeln!(out, "{} = note: {}", pad, note).unwrap();
+        }
+        out
+    }
+}
+
+/// An underline in a line of the rendered source, as `(start col, end col,
+/// primary, message)`.
+type Underline<'a> = (usize, usize, bool, Option<&'a str>);
+
+/// A collection of diagnostics.
+#[derive(Debug, Default, Error)]
+#[error("aborting due to {} previous error(s)", self.num_errors())]
+pub struct Diagnostics {
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Add a diagnostic.
+    pub fn push(&mut self, diagnostic: Diagnostic) { self.diagnostics.push(diagnostic); }
+
+    /// Get the number of error diagnostics.
+    pub fn num_errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diag| diag.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool { self.num_errors() > 0 }
+
+    pub fn is_empty(&self) -> bool { self.diagnostics.is_empty() }
+
+    /// Iterate over the diagnostics.
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> + '_ { self.diagnostics.iter() }
+
+    /// Map the spans of all the diagnostics with `f`, e.g., from the
+    /// preprocessed source back to the original source.
+    pub fn map_spans(&mut self, f: impl Fn(Span) -> Span) {
+        for diag in self.diagnostics.iter_mut() {
+            diag.primary.span = f(diag.primary.span);
+            for label in diag.secondary.iter_mut() {
+                label.span = f(label.span);
+            }
+        }
+    }
+
+    /// Sort the diagnostics by their locations in the source.
+    pub fn sort(&mut self) {
+        self.diagnostics
+            .sort_by_key(|diag| (diag.primary.span.start, diag.primary.span.end));
+    }
+
+    /// Render all the diagnostics with source snippets, separated by empty
+    /// lines. A summary line is appended if there are errors.
+    pub fn render(&self, file_name: &str, src: &str) -> String {
+        let index = LineIndex::new(src);
+        let mut out = String::new();
+        for diagnostic in self.diagnostics.iter() {
+            out += &diagnostic.render_with_index(file_name, &index);
+            out += "\n";
+        }
+        if self.has_errors() {
+            writeln!(out, "{}: {}", Severity::Error, self).unwrap();
+        }
+        out
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter { self.diagnostics.into_iter() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let src = "int main() {\n\tint a = 1;\n  return b + a;\n}\n";
+        let b = src.find('b').unwrap();
+        let a = src.find("a =").unwrap();
+        let diag = Diagnostic::error(
+            ErrorCode::UndeclaredIdent,
+            "cannot find `b` in this scope",
+            Span::new(b, b + 1),
+        )
+        .with_label("not found in this scope")
+        .with_secondary(Span::new(a, a + 1), "a similar name exists")
+        .with_note("declare `b` before using it");
+
+        assert_eq!(
+            diag.render("test.sy", src),
+            "\
+error[E0001]: cannot find `b` in this scope
+ --> test.sy:3:10
+  |
+2 |  int a = 1;
+  |      - a similar name exists
+3 |   return b + a;
+  |          ^ not found in this scope
+  |
+  = note: declare `b` before using it
+"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 //! IR generation from AST.
 
+use std::collections::HashMap;
+
 use super::ast::{
     self,
     BinaryOp,
@@ -17,15 +19,32 @@ use super::ast::{
     Item,
     ReturnStmt,
     Stmt,
+    StmtKind,
     SymbolEntry,
     SymbolTable,
     VarDecl,
     VarDef,
+    sysylib,
 };
 use super::types::{Type, TypeKind as Tk};
 use crate::frontend::ast::{FuncCall, LVal, UnaryOp};
 use crate::infra::linked_list::LinkedListContainer;
-use crate::ir::{Block, ConstantValue, Context, Func, Global, Inst, TargetInfo, Ty, Value};
+use crate::infra::span::Span;
+use crate::ir::{
+    Block,
+    CastOp,
+    ConstantValue,
+    Context,
+    FloatCmpCond,
+    Func,
+    Global,
+    Inst,
+    IntCmpCond,
+    TargetInfo,
+    Ty,
+    Value,
+    debug_verify,
+};
 
 /// Generate IR from the AST.
 pub fn irgen(ast: &CompUnit, pointer_width: u8) -> Context {
@@ -43,6 +62,19 @@ pub fn irgen(ast: &CompUnit, pointer_width: u8) -> Context {
     irgen.finish()
 }
 
+/// Parse, type check and generate IR from SysY source, with 64-bit pointers.
+///
+/// This is a fixture for tests, and panics with the rendered diagnostics if
+/// the source is not well-typed.
+#[cfg(test)]
+pub fn irgen_src(src: &str) -> Context {
+    let mut ast = super::SysYParser::new().parse(src).unwrap();
+    if let Err(diagnostics) = ast.type_check() {
+        panic!("{}", diagnostics.render("test.sy", src));
+    }
+    irgen(&ast, 8)
+}
+
 /// Generated IR result.
 /// Its used to map AST nodes to IR values.
 /// It can be either a Global or a Value.
@@ -85,20 +117,41 @@ pub struct IrGenContext {
     // Return block and slot
     pub curr_ret_slot: Option<Value>,
     pub curr_ret_block: Option<Block>,
+
+    // Globals of the string literals, identical strings share the same global.
+    pub strings: HashMap<Vec<u8>, Global>,
+
+    // The `memset` function to zero-fill local arrays, declared on demand.
+    pub memset: Option<Func>,
 }
 
 impl IrGenContext {
     /// Consume the context and return the generated IR.
     pub fn finish(self) -> Context {
+        debug_verify(&self.ctx);
         self.ctx
     }
 
     // Generate a new global constant value in ir given a comptime value in AST.
+    // Arrays that are all zero are folded into `zeroinitializer`.
     fn gen_global_comptime(&mut self, val: &Cv) -> ConstantValue {
         match val {
             Cv::Bool(a) => ConstantValue::i1(&mut self.ctx, *a),
             Cv::Int(a) => ConstantValue::i32(&mut self.ctx, *a),
             Cv::Float(a) => ConstantValue::f32(&mut self.ctx, *a),
+            Cv::Array(ty, elems) => {
+                let ty = self.gen_type(ty);
+                let (elem_ty, len) = ty.as_array(&self.ctx).unwrap();
+                let mut elems: Vec<_> = elems.iter().map(|e| self.gen_global_comptime(e)).collect();
+                if elems.iter().all(ConstantValue::is_zero) {
+                    return ConstantValue::zero(&self.ctx, ty);
+                }
+                // Fill the trailing elements not in the list with zeros
+                while elems.len() < len {
+                    elems.push(ConstantValue::zero(&self.ctx, elem_ty));
+                }
+                ConstantValue::array(&mut self.ctx, elem_ty, elems)
+            }
         }
     }
 
@@ -109,6 +162,11 @@ impl IrGenContext {
             Tk::Bool => Ty::i1(&mut self.ctx),
             Tk::Int => Ty::i32(&mut self.ctx),
             Tk::Float => Ty::f32(&mut self.ctx),
+            Tk::Array(elem, len) => {
+                let elem = self.gen_type(elem);
+                Ty::array(&mut self.ctx, elem, *len)
+            }
+            Tk::Ptr(_) | Tk::Str => Ty::ptr(&mut self.ctx),
             Tk::Func(..) => unreachable!("function type should be handled separately"),
         }
     }
@@ -119,14 +177,44 @@ impl IrGenContext {
             Cv::Bool(a) => Value::i1(&mut self.ctx, *a),
             Cv::Int(a) => Value::i32(&mut self.ctx, *a),
             Cv::Float(a) => Value::f32(&mut self.ctx, *a),
+            Cv::Array(..) => unreachable!("constant arrays are only used as initializers"),
         }
     }
 
+    // Append an instruction to the current block, and attribute it to the source
+    // location `span`.
+    fn push_inst(&mut self, inst: Inst, span: Span) {
+        inst.set_span(&mut self.ctx, span);
+        self.curr_block
+            .unwrap()
+            .push_back(&mut self.ctx, inst)
+            .unwrap();
+    }
+
+    // Append a block to the current function, and continue generating
+    // instructions in it.
+    fn append_block(&mut self, block: Block) {
+        self.curr_func
+            .unwrap()
+            .push_back(&mut self.ctx, block)
+            .unwrap();
+        self.curr_block = Some(block);
+    }
+
+    // Jump to `dest` from the current block. Instructions after the jump are
+    // unreachable, and they are generated in a new block without predecessors,
+    // so that no instruction is appended after the terminator.
+    fn gen_jump(&mut self, dest: Block, span: Span) {
+        let jump = Inst::br(&mut self.ctx, dest);
+        self.push_inst(jump, span);
+        let block = Block::new(&mut self.ctx);
+        self.append_block(block);
+    }
+
     // Generate a new local expression in ir given an expression in AST.
     fn gen_local_expr(&mut self, expr: &Expr) -> Option<Value> {
         use BinaryOp as Bo;
-
-        let curr_block = self.curr_block.unwrap();
+        use IntCmpCond as Ic;
 
         match &expr.kind {
             // Constants -> generate a local constant value
@@ -143,89 +231,432 @@ impl IrGenContext {
                 | Bo::Le
                 | Bo::Ge
                 | Bo::Eq
-                | Bo::Ne
-                | Bo::LogicalAnd
-                | Bo::LogicalOr => {
+                | Bo::Ne => {
                     let lhs = self.gen_local_expr(lhs).unwrap(); // Generate lhs
                     let rhs = self.gen_local_expr(rhs).unwrap(); // Generate rhs
 
                     let lhs_ty = lhs.ty(&self.ctx);
 
-                    let inst = match op {
-                        // Generate add instruction
-                        Bo::Add => Inst::add(&mut self.ctx, lhs, rhs, lhs_ty),
-                        // TODO: Implement other binary operations
-                        Bo::Sub => Inst::sub(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Mul => Inst::mul(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Div => Inst::SDiv(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Mod => Inst::SRem(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Lt => Inst::Lt(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Gt => Inst::Gt(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Le => Inst::Le(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Ge => Inst::Ge(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Eq => Inst::Eq(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::Ne => Inst::Ne(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::LogicalAnd => Inst::LogicalAnd(&mut self.ctx, lhs, rhs, lhs_ty),
-                        Bo::LogicalOr => Inst::LogicalOr(&mut self.ctx, lhs, rhs, lhs_ty),
+                    let inst = if lhs_ty.is_float(&self.ctx) {
+                        self.gen_float_binary(*op, lhs, rhs)
+                    } else {
+                        let ctx = &mut self.ctx;
+                        match op {
+                            Bo::Add => Inst::add(ctx, lhs, rhs, lhs_ty),
+                            Bo::Sub => Inst::sub(ctx, lhs, rhs, lhs_ty),
+                            Bo::Mul => Inst::mul(ctx, lhs, rhs, lhs_ty),
+                            Bo::Div => Inst::sdiv(ctx, lhs, rhs, lhs_ty),
+                            Bo::Mod => Inst::srem(ctx, lhs, rhs, lhs_ty),
+                            Bo::Lt => Inst::icmp(ctx, Ic::Slt, lhs, rhs),
+                            Bo::Gt => Inst::icmp(ctx, Ic::Sgt, lhs, rhs),
+                            Bo::Le => Inst::icmp(ctx, Ic::Sle, lhs, rhs),
+                            Bo::Ge => Inst::icmp(ctx, Ic::Sge, lhs, rhs),
+                            Bo::Eq => Inst::icmp(ctx, Ic::Eq, lhs, rhs),
+                            Bo::Ne => Inst::icmp(ctx, Ic::Ne, lhs, rhs),
+                            Bo::LogicalAnd | Bo::LogicalOr => unreachable!(),
+                        }
                     };
 
                     // Push the instruction to the current block
-                    curr_block.push_back(&mut self.ctx, inst).unwrap();
+                    self.push_inst(inst, expr.span);
                     Some(inst.result(&self.ctx).unwrap())
                 }
-            },
-            // Unary operations -> generate the operation
-            ExprKind::Unary(op, _) => match op {
-                // TODO: Implement unary operations
-                UnaryOp::Neg => {
-                    todo!("implement neg");
-                }
-                UnaryOp::Not => {
-                    todo!("implement not");
+                // Logical operations -> evaluate the rhs only if needed, and
+                // merge the result with a phi
+                Bo::LogicalAnd | Bo::LogicalOr => {
+                    let rhs_block = Block::new(&mut self.ctx);
+                    let merge_block = Block::new(&mut self.ctx);
+
+                    // The result is known from the lhs: false for `&&`, true for `||`.
+                    let short_circuit = matches!(op, Bo::LogicalOr);
+                    let lhs = self.gen_local_expr(lhs).unwrap();
+                    let lhs_block = self.curr_block.unwrap();
+                    let br = if short_circuit {
+                        Inst::cond_br(&mut self.ctx, lhs, merge_block, rhs_block)
+                    } else {
+                        Inst::cond_br(&mut self.ctx, lhs, rhs_block, merge_block)
+                    };
+                    self.push_inst(br, expr.span);
+
+                    self.append_block(rhs_block);
+                    let rhs = self.gen_local_expr(rhs).unwrap();
+                    let rhs_end = self.curr_block.unwrap();
+                    let jump = Inst::br(&mut self.ctx, merge_block);
+                    self.push_inst(jump, expr.span);
+
+                    self.append_block(merge_block);
+                    let i1 = Ty::i1(&mut self.ctx);
+                    let phi = Inst::phi(&mut self.ctx, i1);
+                    let known = Value::i1(&mut self.ctx, short_circuit);
+                    phi.insert_incoming(&mut self.ctx, lhs_block, known);
+                    phi.insert_incoming(&mut self.ctx, rhs_end, rhs);
+                    self.push_inst(phi, expr.span);
+                    Some(phi.result(&self.ctx).unwrap())
                 }
             },
-            // LValues -> Get the value
-            ExprKind::LVal(LVal { ident }) => {
-                // Look up the symbol in the symbol table to get the IR value
-                let entry = self.symtable.lookup(ident).unwrap();
-                let ir_value = entry.ir_value.unwrap();
-
-                let ir_base_ty = self.gen_type(&entry.ty.clone());
-
-                let slot = if let IrGenResult::Global(slot) = ir_value {
-                    // If the value is a global, get the global reference
-                    let name = slot.name(&self.ctx).to_string();
-                    let value_ty = slot.ty(&self.ctx);
-                    Value::global_ref(&mut self.ctx, name, value_ty)
-                } else if let IrGenResult::Value(slot) = ir_value {
-                    // If the value is a local, get the value
-                    slot
-                } else {
-                    unreachable!()
+            // Unary operations -> generate the operation
+            ExprKind::Unary(op, operand) => {
+                let val = self.gen_local_expr(operand).unwrap();
+                let ty = val.ty(&self.ctx);
+                let ctx = &mut self.ctx;
+                let inst = match op {
+                    UnaryOp::Neg if ty.is_float(ctx) => Inst::fneg(ctx, val),
+                    UnaryOp::Neg => {
+                        let zero = Value::i32(ctx, 0);
+                        Inst::sub(ctx, zero, val, ty)
+                    }
+                    // The operand has been converted to bool in type checking
+                    UnaryOp::Not => {
+                        let true_ = Value::i1(ctx, true);
+                        Inst::xor(ctx, val, true_, ty)
+                    }
                 };
-
-                if slot.is_param(&self.ctx) {
-                    // If the value is a parameter, just return the value
-                    Some(slot)
+                self.push_inst(inst, expr.span);
+                Some(inst.result(&self.ctx).unwrap())
+            }
+            // LValues -> Get the value
+            ExprKind::LVal(lval) => {
+                let (addr, ty) = self.gen_lval_addr(lval, expr.span);
+                if ty.is_array() || ty.is_ptr() {
+                    // Arrays are used by their addresses, and pointer parameters
+                    // are values themselves
+                    Some(addr)
                 } else {
                     // Otherwise, we need to load the value, generate a load instruction
-                    let load = Inst::load(&mut self.ctx, slot, ir_base_ty);
-                    curr_block.push_back(&mut self.ctx, load).unwrap();
+                    let ir_ty = self.gen_type(&ty);
+                    let load = Inst::load(&mut self.ctx, addr, ir_ty);
+                    self.push_inst(load, expr.span);
                     Some(load.result(&self.ctx).unwrap())
                 }
             }
-            ExprKind::Coercion(_) => {
-                // TODO: Implement coercion generation
-                todo!("implement coercion");
+            // Coercions -> convert the value to the target type
+            ExprKind::Coercion(operand) => {
+                let val = self.gen_local_expr(operand).unwrap();
+                Some(self.gen_coercion(val, operand.ty(), expr.ty(), expr.span))
+            }
+            // Function calls -> generate the arguments and call the function
+            ExprKind::FuncCall(FuncCall { ident, args }) => {
+                let func_ty = self.symtable.lookup(ident).unwrap().ty.clone();
+                let num_params = func_ty.unwrap_func().0.len();
+                let args = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| {
+                        let val = self.gen_local_expr(arg).unwrap();
+                        // Variadic float arguments are promoted to double, as in C
+                        if i >= num_params && arg.ty().is_float() {
+                            let f64 = Ty::f64(&mut self.ctx);
+                            let fpext = Inst::cast(&mut self.ctx, CastOp::Fpext, val, f64);
+                            self.push_inst(fpext, arg.span);
+                            fpext.result(&self.ctx).unwrap()
+                        } else {
+                            val
+                        }
+                    })
+                    .collect();
+
+                let ptr = Ty::ptr(&mut self.ctx);
+                let callee = Value::global_ref(&mut self.ctx, ident.clone(), ptr);
+                let ret_ty = self.gen_type(expr.ty());
+
+                let call = Inst::call(&mut self.ctx, callee, args, ret_ty);
+                self.push_inst(call, expr.span);
+                call.result(&self.ctx)
             }
-            ExprKind::FuncCall(FuncCall { .. }) => {
-                // TODO: Implement function call generation
-                todo!("implement call");
+            ExprKind::InitList(_) => {
+                unreachable!("initializer lists are lowered by gen_local_init/gen_global_init")
+            }
+            // Strings -> get the address of the global string
+            ExprKind::Str(value) => {
+                let global = self.gen_string(value);
+                let name = global.name(&self.ctx).to_string();
+                let value_ty = global.ty(&self.ctx);
+                Some(Value::global_ref(&mut self.ctx, name, value_ty))
             }
         }
     }
 
-    fn gen_sysylib(&mut self) {}
+    // Generate the address of a left value, and get the type of the value at
+    // the address.
+    //
+    // Pointer parameters are not stored in slots, so the address of a pointer
+    // parameter without indices is the parameter itself, of pointer type.
+    fn gen_lval_addr(&mut self, lval: &LVal, span: Span) -> (Value, Type) {
+        let entry = self.symtable.lookup(&lval.ident).unwrap();
+        let mut ty = entry.ty.clone();
+
+        let base = match entry.ir_value.unwrap() {
+            IrGenResult::Global(slot) => {
+                // If the value is a global, get the global reference
+                let name = slot.name(&self.ctx).to_string();
+                let value_ty = slot.ty(&self.ctx);
+                Value::global_ref(&mut self.ctx, name, value_ty)
+            }
+            IrGenResult::Value(slot) => slot,
+        };
+
+        if lval.indices.is_empty() {
+            return (base, ty);
+        }
+
+        // Pointers are indexed from the pointed element, and arrays from the
+        // start of the array.
+        let mut indices = Vec::new();
+        let bound_ty = match ty.kind() {
+            Tk::Ptr(elem) => self.gen_type(elem),
+            _ => {
+                indices.push(Value::i32(&mut self.ctx, 0));
+                self.gen_type(&ty)
+            }
+        };
+        for index in lval.indices.iter() {
+            indices.push(self.gen_local_expr(index).unwrap());
+            ty = ty.indexed().unwrap().clone();
+        }
+
+        let gep = Inst::getelementptr(&mut self.ctx, bound_ty, base, indices);
+        self.push_inst(gep, span);
+        (gep.result(&self.ctx).unwrap(), ty)
+    }
+
+    // Generate the constant initializer of a global from the type checked init
+    // expression.
+    fn gen_global_init(&mut self, init: &Expr) -> ConstantValue {
+        let comptime = init
+            .try_fold(&self.symtable)
+            .expect("global def expected to have constant initializer");
+        self.gen_global_comptime(&comptime)
+    }
+
+    // Initialize a local slot with the type checked init expression.
+    //
+    // Arrays not fully initialized with non-zero values are zero-filled with
+    // `memset` first, and then only the non-zero elements are stored.
+    fn gen_local_init(&mut self, slot: Value, init: &Expr, span: Span) {
+        if !init.ty().is_array() {
+            let val = self.gen_local_expr(init).unwrap();
+            let store = Inst::store(&mut self.ctx, val, slot);
+            self.push_inst(store, span);
+            return;
+        }
+
+        let mut scalars = Vec::new();
+        Self::flatten_init_list(init, &mut Vec::new(), &mut scalars);
+
+        // Positive zeros can be skipped after zero-filling, but not `-0.0`.
+        let is_zero = |expr: &Expr| match &expr.kind {
+            ExprKind::Const(Cv::Float(val)) => val.to_bits() == 0,
+            ExprKind::Const(val) => val.is_zero(),
+            _ => false,
+        };
+
+        let (_, dims) = init.ty().split_dims();
+        let len: usize = dims.iter().product();
+        let zero_filled = scalars.len() < len || scalars.iter().any(|(_, elem)| is_zero(elem));
+        if zero_filled {
+            let ir_ty = self.gen_type(init.ty());
+            let size = ir_ty.bitwidth(&self.ctx) / 8;
+            self.gen_memset(slot, size, span);
+        }
+
+        let ir_ty = self.gen_type(init.ty());
+        for (indices, elem) in scalars {
+            if zero_filled && is_zero(elem) {
+                continue;
+            }
+            let mut idx_values = vec![Value::i32(&mut self.ctx, 0)];
+            for index in indices {
+                idx_values.push(Value::i32(&mut self.ctx, index as i32));
+            }
+            let gep = Inst::getelementptr(&mut self.ctx, ir_ty, slot, idx_values);
+            self.push_inst(gep, span);
+            let addr = gep.result(&self.ctx).unwrap();
+
+            let val = self.gen_local_expr(elem).unwrap();
+            let store = Inst::store(&mut self.ctx, val, addr);
+            self.push_inst(store, span);
+        }
+    }
+
+    // Collect the scalars in a (normalized) init list with their indices.
+    fn flatten_init_list<'a>(
+        init: &'a Expr,
+        indices: &mut Vec<usize>,
+        scalars: &mut Vec<(Vec<usize>, &'a Expr)>,
+    ) {
+        match &init.kind {
+            ExprKind::InitList(elems) => {
+                for (i, elem) in elems.iter().enumerate() {
+                    indices.push(i);
+                    Self::flatten_init_list(elem, indices, scalars);
+                    indices.pop();
+                }
+            }
+            _ => scalars.push((indices.clone(), init)),
+        }
+    }
+
+    // Fill `size` bytes at `ptr` with zeros by calling `memset`, which is
+    // declared on the first use.
+    fn gen_memset(&mut self, ptr: Value, size: usize, span: Span) {
+        let memset = match self.memset {
+            Some(memset) => memset,
+            None => {
+                let void = Ty::void(&mut self.ctx);
+                let memset = Func::declare(&mut self.ctx, "memset".to_string(), void, false);
+                let ptr = Ty::ptr(&mut self.ctx);
+                let i32 = Ty::i32(&mut self.ctx);
+                memset.add_param(&mut self.ctx, ptr);
+                memset.add_param(&mut self.ctx, i32);
+                memset.add_param(&mut self.ctx, i32);
+                self.memset = Some(memset);
+                memset
+            }
+        };
+
+        let name = memset.name(&self.ctx).to_string();
+        let ptr_ty = Ty::ptr(&mut self.ctx);
+        let callee = Value::global_ref(&mut self.ctx, name, ptr_ty);
+        let zero = Value::i32(&mut self.ctx, 0);
+        let size = Value::i32(&mut self.ctx, size as i32);
+        let void = Ty::void(&mut self.ctx);
+        let call = Inst::call(&mut self.ctx, callee, vec![ptr, zero, size], void);
+        self.push_inst(call, span);
+    }
+
+    // Convert a scalar value from type `from` to type `to`. Non-zero values
+    // are converted to true, and true is converted to 1.
+    fn gen_coercion(&mut self, val: Value, from: &Type, to: &Type, span: Span) -> Value {
+        let ctx = &mut self.ctx;
+        let inst = match (from.kind(), to.kind()) {
+            (Tk::Bool, Tk::Int) => {
+                let i32 = Ty::i32(ctx);
+                Inst::cast(ctx, CastOp::Zext, val, i32)
+            }
+            (Tk::Bool, Tk::Float) => {
+                let int = self.gen_coercion(val, from, &Type::int(), span);
+                return self.gen_coercion(int, &Type::int(), to, span);
+            }
+            (Tk::Int, Tk::Bool) => {
+                let zero = Value::i32(ctx, 0);
+                Inst::icmp(ctx, IntCmpCond::Ne, val, zero)
+            }
+            (Tk::Float, Tk::Bool) => {
+                let zero = Value::f32(ctx, 0.0);
+                // A NaN is also true
+                Inst::fcmp(ctx, FloatCmpCond::Une, val, zero)
+            }
+            (Tk::Int, Tk::Float) => {
+                let f32 = Ty::f32(ctx);
+                Inst::cast(ctx, CastOp::Sitofp, val, f32)
+            }
+            (Tk::Float, Tk::Int) => {
+                let i32 = Ty::i32(ctx);
+                Inst::cast(ctx, CastOp::Fptosi, val, i32)
+            }
+            // The address of an array is also the address of its first element
+            (Tk::Array(..), Tk::Ptr(_)) => return val,
+            _ => unreachable!("invalid coercion from {} to {}", from, to),
+        };
+        self.push_inst(inst, span);
+        inst.result(&self.ctx).unwrap()
+    }
+
+    // Generate a condition, jumping to `then_block` if it is true, and to
+    // `else_block` otherwise. Logical operations are generated as branch
+    // chains, so the rhs is only evaluated if needed.
+    fn gen_cond(&mut self, cond: &Expr, then_block: Block, else_block: Block) {
+        match &cond.kind {
+            ExprKind::Unary(UnaryOp::Not, operand) => {
+                self.gen_cond(operand, else_block, then_block);
+            }
+            ExprKind::Binary(BinaryOp::LogicalAnd, lhs, rhs) => {
+                let rhs_block = Block::new(&mut self.ctx);
+                self.gen_cond(lhs, rhs_block, else_block);
+                self.append_block(rhs_block);
+                self.gen_cond(rhs, then_block, else_block);
+            }
+            ExprKind::Binary(BinaryOp::LogicalOr, lhs, rhs) => {
+                let rhs_block = Block::new(&mut self.ctx);
+                self.gen_cond(lhs, then_block, rhs_block);
+                self.append_block(rhs_block);
+                self.gen_cond(rhs, then_block, else_block);
+            }
+            _ => {
+                let cond_val = self.gen_local_expr(cond).unwrap();
+                let br = Inst::cond_br(&mut self.ctx, cond_val, then_block, else_block);
+                self.push_inst(br, cond.span);
+            }
+        }
+    }
+
+    // Generate a binary operation on float operands.
+    fn gen_float_binary(&mut self, op: BinaryOp, lhs: Value, rhs: Value) -> Inst {
+        use BinaryOp as Bo;
+        use FloatCmpCond as Fc;
+
+        let ctx = &mut self.ctx;
+        match op {
+            Bo::Add => Inst::fadd(ctx, lhs, rhs),
+            Bo::Sub => Inst::fsub(ctx, lhs, rhs),
+            Bo::Mul => Inst::fmul(ctx, lhs, rhs),
+            Bo::Div => Inst::fdiv(ctx, lhs, rhs),
+            Bo::Lt => Inst::fcmp(ctx, Fc::Olt, lhs, rhs),
+            Bo::Gt => Inst::fcmp(ctx, Fc::Ogt, lhs, rhs),
+            Bo::Le => Inst::fcmp(ctx, Fc::Ole, lhs, rhs),
+            Bo::Ge => Inst::fcmp(ctx, Fc::Oge, lhs, rhs),
+            Bo::Eq => Inst::fcmp(ctx, Fc::Oeq, lhs, rhs),
+            Bo::Ne => Inst::fcmp(ctx, Fc::Une, lhs, rhs),
+            Bo::Mod | Bo::LogicalAnd | Bo::LogicalOr => {
+                unreachable!("{:?} on float operands", op)
+            }
+        }
+    }
+
+    // Generate a private constant global of a NUL-terminated string literal.
+    fn gen_string(&mut self, value: &[u8]) -> Global {
+        if let Some(global) = self.strings.get(value) {
+            return *global;
+        }
+
+        let elems = value
+            .iter()
+            .chain(std::iter::once(&0))
+            .map(|&byte| ConstantValue::i8(&mut self.ctx, byte as i8))
+            .collect();
+        let i8 = Ty::i8(&mut self.ctx);
+        let constant = ConstantValue::array(&mut self.ctx, i8, elems);
+
+        let name = format!("__STR_{}", self.strings.len());
+        let global = Global::new_private_constant(&mut self.ctx, name, constant);
+        self.strings.insert(value.to_vec(), global);
+        global
+    }
+
+    // Declare the SysY library functions as external functions, and register
+    // them to the symbol table.
+    fn gen_sysylib(&mut self) {
+        for (name, ty) in sysylib() {
+            let (param_tys, ret_ty) = ty.unwrap_func();
+            let ir_ret_ty = self.gen_type(ret_ty);
+            let func = Func::declare(&mut self.ctx, name.to_string(), ir_ret_ty, ty.is_variadic());
+            for param_ty in param_tys {
+                let ir_ty = self.gen_type(param_ty);
+                func.add_param(&mut self.ctx, ir_ty);
+            }
+
+            self.symtable.insert(
+                name,
+                SymbolEntry {
+                    ty,
+                    comptime: None,
+                    is_const: false,
+                    span: None,
+                    ir_value: None,
+                },
+            );
+        }
+    }
 }
 
 pub trait IrGen {
@@ -249,10 +680,8 @@ impl IrGen for Item {
             Item::Decl(decl) => match decl {
                 Decl::ConstDecl(ConstDecl { defs, .. }) => {
                     for ConstDef { ident, init, .. } in defs {
-                        let comptime = init
-                            .try_fold(&irgen.symtable)
-                            .expect("global def expected to have constant initializer");
-                        let constant = irgen.gen_global_comptime(&comptime);
+                        let comptime = init.try_fold(&irgen.symtable);
+                        let constant = irgen.gen_global_init(init);
                         let slot = Global::new(
                             &mut irgen.ctx,
                             format!("__GLOBAL_CONST_{}", ident),
@@ -262,20 +691,25 @@ impl IrGen for Item {
                             ident.clone(),
                             SymbolEntry {
                                 ty: init.ty().clone(),
-                                comptime: Some(comptime),
+                                comptime,
+                                is_const: true,
+                                span: None,
                                 ir_value: Some(IrGenResult::Global(slot)),
                             },
                         );
                     }
                 }
-                Decl::VarDecl(VarDecl { defs, .. }) => {
-                    for VarDef { ident, init, .. } in defs {
-                        let comptime = init
-                            .as_ref()
-                            .unwrap() // None should have been assigned with undef
-                            .try_fold(&irgen.symtable)
-                            .expect("global def expected to have constant initializer");
-                        let constant = irgen.gen_global_comptime(&comptime);
+                Decl::VarDecl(VarDecl { ty, defs, .. }) => {
+                    for def @ VarDef { ident, init, .. } in defs {
+                        let ty = def.ty(ty);
+                        // Globals without initializers are zero-initialized
+                        let constant = match init {
+                            Some(init) => irgen.gen_global_init(init),
+                            None => {
+                                let ir_ty = irgen.gen_type(&ty);
+                                ConstantValue::zero(&irgen.ctx, ir_ty)
+                            }
+                        };
                         let slot = Global::new(
                             &mut irgen.ctx,
                             format!("__GLOBAL_VAR_{}", ident),
@@ -284,8 +718,10 @@ impl IrGen for Item {
                         irgen.symtable.insert(
                             ident.clone(),
                             SymbolEntry {
-                                ty: init.as_ref().unwrap().ty().clone(),
-                                comptime: Some(comptime),
+                                ty,
+                                comptime: None,
+                                is_const: false,
+                                span: None,
                                 ir_value: Some(IrGenResult::Global(slot)),
                             },
                         );
@@ -316,6 +752,8 @@ impl IrGen for FuncDef {
             SymbolEntry {
                 ty: func_ty,
                 comptime: None,
+                is_const: false,
+                span: None,
                 ir_value: None,
             },
             1,
@@ -338,16 +776,19 @@ impl IrGen for FuncDef {
                 SymbolEntry {
                     ty: ty.clone(),
                     comptime: None,
+                    is_const: false,
+                    span: None,
                     ir_value: Some(IrGenResult::Value(param)),
                 },
             );
         }
 
-        // create slots for pass-by-value params
-        for (FuncFParam { ident, .. }, ty) in self.params.iter().zip(param_tys.iter()) {
-            if ty.is_int() {
+        // create slots for pass-by-value params, arrays are passed by pointers
+        for (FuncFParam { ident, span, .. }, ty) in self.params.iter().zip(param_tys.iter()) {
+            if ty.is_scalar() {
                 let ir_ty = irgen.gen_type(ty);
                 let slot = Inst::alloca(&mut irgen.ctx, ir_ty);
+                slot.set_span(&mut irgen.ctx, *span);
 
                 block.push_front(&mut irgen.ctx, slot).unwrap();
                 let slot = slot.result(&irgen.ctx).unwrap();
@@ -363,8 +804,7 @@ impl IrGen for FuncDef {
 
                 // store
                 let store = Inst::store(&mut irgen.ctx, param, slot);
-
-                block.push_back(&mut irgen.ctx, store).unwrap();
+                irgen.push_inst(store, *span);
 
                 // set new entry
                 irgen.symtable.insert(
@@ -372,6 +812,8 @@ impl IrGen for FuncDef {
                     SymbolEntry {
                         ty: ty.clone(),
                         comptime: None,
+                        is_const: false,
+                        span: None,
                         ir_value: Some(IrGenResult::Value(slot)),
                     },
                 );
@@ -390,8 +832,10 @@ impl IrGen for FuncDef {
             irgen.curr_ret_slot = Some(ret_slot.result(&irgen.ctx).unwrap());
         }
 
-        // generate body
+        // generate body, and fall through to the return block at the end
         self.body.irgen(irgen);
+        let jump = Inst::br(&mut irgen.ctx, ret_block);
+        irgen.push_inst(jump, self.span);
 
         // append return block
         func.push_back(&mut irgen.ctx, ret_block).unwrap();
@@ -426,56 +870,65 @@ impl IrGen for FuncDef {
 impl IrGen for Decl {
     fn irgen(&self, irgen: &mut IrGenContext) {
         let entry_block = irgen.curr_func.unwrap().head(&irgen.ctx).unwrap();
-        let curr_block = irgen.curr_block.unwrap();
         match self {
             Decl::ConstDecl(ConstDecl { defs, .. }) => {
-                for ConstDef { ident, init, .. } in defs {
-                    let comptime = init
-                        .try_fold(&irgen.symtable)
-                        .expect("global def expected to have constant initializer");
+                for ConstDef {
+                    ident, init, span, ..
+                } in defs
+                {
+                    let comptime = init.try_fold(&irgen.symtable);
 
                     let ir_ty = irgen.gen_type(init.ty());
                     let stack_slot = Inst::alloca(&mut irgen.ctx, ir_ty);
+                    stack_slot.set_span(&mut irgen.ctx, *span);
 
                     entry_block.push_front(&mut irgen.ctx, stack_slot).unwrap();
                     irgen.symtable.insert(
                         ident,
                         SymbolEntry {
                             ty: init.ty().clone(),
-                            comptime: Some(comptime),
+                            comptime,
+                            is_const: true,
+                            span: None,
                             ir_value: Some(IrGenResult::Value(
                                 stack_slot.result(&irgen.ctx).unwrap(),
                             )),
                         },
                     );
-                    let init = irgen.gen_local_expr(init).unwrap();
                     let slot = stack_slot.result(&irgen.ctx).unwrap();
-                    let store = Inst::store(&mut irgen.ctx, init, slot);
-                    curr_block.push_back(&mut irgen.ctx, store).unwrap();
+                    irgen.gen_local_init(slot, init, *span);
                 }
             }
-            Decl::VarDecl(VarDecl { defs, .. }) => {
-                for VarDef { ident, init, .. } in defs {
-                    let init = init.as_ref().unwrap();
-                    let ir_ty = irgen.gen_type(init.ty());
+            Decl::VarDecl(VarDecl { ty, defs, .. }) => {
+                for def @ VarDef {
+                    ident, init, span, ..
+                } in defs
+                {
+                    let ty = def.ty(ty);
+                    let ir_ty = irgen.gen_type(&ty);
                     let stack_slot = Inst::alloca(&mut irgen.ctx, ir_ty);
+                    stack_slot.set_span(&mut irgen.ctx, *span);
 
                     entry_block.push_front(&mut irgen.ctx, stack_slot).unwrap();
                     irgen.symtable.insert(
                         ident,
                         SymbolEntry {
-                            ty: init.ty().clone(),
+                            ty,
                             comptime: None,
+                            is_const: false,
+                            span: None,
                             ir_value: Some(IrGenResult::Value(
                                 stack_slot.result(&irgen.ctx).unwrap(),
                             )),
                         },
                     );
 
-                    let init = irgen.gen_local_expr(init).unwrap();
-                    let slot = stack_slot.result(&irgen.ctx).unwrap();
-                    let store = Inst::store(&mut irgen.ctx, init, slot);
-                    curr_block.push_back(&mut irgen.ctx, store).unwrap();
+                    // Locals without initializers are left undefined, i.e., the
+                    // loads before any store read `undef`.
+                    if let Some(init) = init {
+                        let slot = stack_slot.result(&irgen.ctx).unwrap();
+                        irgen.gen_local_init(slot, init, *span);
+                    }
                 }
             }
         }
@@ -484,64 +937,85 @@ impl IrGen for Decl {
 
 impl IrGen for Stmt {
     fn irgen(&self, irgen: &mut IrGenContext) {
-        let curr_block = irgen.curr_block.unwrap();
-
-        match self {
-            Stmt::Assign(LVal { ident }, expr) => {
-                let entry = irgen.symtable.lookup(ident).unwrap();
-                let ir_value = entry.ir_value.unwrap();
-
-                let slot = if let IrGenResult::Global(slot) = ir_value {
-                    let name = slot.name(&irgen.ctx).to_string();
-                    let value_ty = slot.ty(&irgen.ctx);
-                    Value::global_ref(&mut irgen.ctx, name, value_ty)
-                } else if let IrGenResult::Value(slot) = ir_value {
-                    slot
-                } else {
-                    unreachable!()
-                };
-
-                let store_dst = slot;
-
+        match &self.kind {
+            StmtKind::Assign(lval, expr) => {
+                let (store_dst, _) = irgen.gen_lval_addr(lval, self.span);
                 let val = irgen.gen_local_expr(expr).unwrap();
                 let store = Inst::store(&mut irgen.ctx, val, store_dst);
-                curr_block.push_back(&mut irgen.ctx, store).unwrap();
+                irgen.push_inst(store, self.span);
             }
-            Stmt::Expr(ExprStmt { expr }) => {
+            StmtKind::Expr(ExprStmt { expr }) => {
                 if let Some(ref expr) = expr {
                     irgen.gen_local_expr(expr);
                 }
             }
-            Stmt::Block(block) => block.irgen(irgen),
-            Stmt::If(..) => {
-                todo!("implement if statement");
+            StmtKind::Block(block) => block.irgen(irgen),
+            StmtKind::If(cond, then_stmt, else_stmt) => {
+                let then_block = Block::new(&mut irgen.ctx);
+                let merge_block = Block::new(&mut irgen.ctx);
+                // Without an else branch, jump to the merge block directly.
+                let else_block = match else_stmt {
+                    Some(_) => Block::new(&mut irgen.ctx),
+                    None => merge_block,
+                };
+
+                irgen.gen_cond(cond, then_block, else_block);
+
+                irgen.append_block(then_block);
+                then_stmt.irgen(irgen);
+                let jump = Inst::br(&mut irgen.ctx, merge_block);
+                irgen.push_inst(jump, self.span);
+
+                if let Some(else_stmt) = else_stmt {
+                    irgen.append_block(else_block);
+                    else_stmt.irgen(irgen);
+                    let jump = Inst::br(&mut irgen.ctx, merge_block);
+                    irgen.push_inst(jump, self.span);
+                }
+
+                irgen.append_block(merge_block);
             }
-            Stmt::While(..) => {
-                todo!("implement while statement");
+            StmtKind::While(cond, body) => {
+                let cond_block = Block::new(&mut irgen.ctx);
+                let body_block = Block::new(&mut irgen.ctx);
+                let exit_block = Block::new(&mut irgen.ctx);
+
+                let jump = Inst::br(&mut irgen.ctx, cond_block);
+                irgen.push_inst(jump, self.span);
+
+                irgen.append_block(cond_block);
+                irgen.gen_cond(cond, body_block, exit_block);
+
+                // `continue` re-evaluates the condition, and `break` exits.
+                irgen.loop_entry_stack.push(cond_block);
+                irgen.loop_exit_stack.push(exit_block);
+
+                irgen.append_block(body_block);
+                body.irgen(irgen);
+                let jump = Inst::br(&mut irgen.ctx, cond_block);
+                irgen.push_inst(jump, self.span);
+
+                irgen.loop_entry_stack.pop();
+                irgen.loop_exit_stack.pop();
+
+                irgen.append_block(exit_block);
             }
-            Stmt::Break => {
-                todo!("implement break statement");
+            StmtKind::Break => {
+                let exit = *irgen.loop_exit_stack.last().unwrap();
+                irgen.gen_jump(exit, self.span);
             }
-            Stmt::Continue => {
-                todo!("implement continue statement");
+            StmtKind::Continue => {
+                let entry = *irgen.loop_entry_stack.last().unwrap();
+                irgen.gen_jump(entry, self.span);
             }
-            Stmt::Return(ReturnStmt { expr }) => {
+            StmtKind::Return(ReturnStmt { expr }) => {
                 if let Some(expr) = expr {
                     let val = irgen.gen_local_expr(expr).unwrap();
                     let store = Inst::store(&mut irgen.ctx, val, irgen.curr_ret_slot.unwrap());
-                    irgen
-                        .curr_block
-                        .unwrap()
-                        .push_back(&mut irgen.ctx, store)
-                        .unwrap();
+                    irgen.push_inst(store, self.span);
                 }
 
-                let jump = Inst::br(&mut irgen.ctx, irgen.curr_ret_block.unwrap());
-                irgen
-                    .curr_block
-                    .unwrap()
-                    .push_back(&mut irgen.ctx, jump)
-                    .unwrap();
+                irgen.gen_jump(irgen.curr_ret_block.unwrap(), self.span);
             }
         }
     }
@@ -559,3 +1033,299 @@ impl IrGen for ast::Block {
         irgen.symtable.leave_scope();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{InstKind, Usable, verify};
+
+    fn func(ctx: &Context, name: &str) -> Func {
+        ctx.funcs().find(|func| func.name(ctx) == name).unwrap()
+    }
+
+    #[test]
+    fn test_irgen_control_flow() {
+        let ctx = irgen_src(
+            r#"
+            int f(int n) {
+                int s = 0;
+                int i = 0;
+                while (i < n) {
+                    i = i + 1;
+                    if (i == 3) continue;
+                    if (i > 10) break;
+                    else s = s + i;
+                    while (s > 100) {
+                        if (s == 200) { return s; }
+                        s = s - 1;
+                    }
+                }
+                return s;
+            }
+            int main() { if (f(5) > 0) putint(1); return 0; }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        // One conditional branch for each `if` and `while`.
+        let count_cond_br = |func: Func| {
+            func.iter(&ctx)
+                .filter_map(|block| block.tail(&ctx))
+                .filter(|inst| matches!(inst.kind(&ctx), InstKind::CondBr))
+                .count()
+        };
+        assert_eq!(count_cond_br(func(&ctx, "f")), 5);
+        assert_eq!(count_cond_br(func(&ctx, "main")), 1);
+    }
+
+    #[test]
+    fn test_irgen_unreachable_after_jump() {
+        let ctx = irgen_src(
+            r#"
+            int main() {
+                while (1) {
+                    break;
+                    putint(1);
+                }
+                return 0;
+                putint(2);
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        // The calls after `break` and `return` are in blocks without
+        // predecessors, instead of after the terminators.
+        let main = func(&ctx, "main");
+        let call_blocks: Vec<_> = main
+            .iter(&ctx)
+            .filter(|block| {
+                block
+                    .iter(&ctx)
+                    .any(|inst| matches!(inst.kind(&ctx), InstKind::Call))
+            })
+            .collect();
+        assert_eq!(call_blocks.len(), 2);
+        for block in call_blocks {
+            assert!(block.users(&ctx).into_iter().next().is_none());
+        }
+    }
+
+    #[test]
+    fn test_irgen_short_circuit() {
+        let ctx = irgen_src(
+            r#"
+            int f() { putint(1); return 1; }
+            int main() {
+                int a = 0;
+                if (a > 0 && f() > 0 || a < 0) putint(2);
+                while (a < 0 || f() < 0) {}
+                if ((a > 0 && f() > 0) == (a < 0)) putint(3);
+                return 0;
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        // The calls of `f` are never in the same block as the evaluation of
+        // the lhs, i.e., the entry or the loop condition.
+        let main = func(&ctx, "main");
+        let calls_f = |block: Block| {
+            block.iter(&ctx).any(|inst| {
+                matches!(inst.kind(&ctx), InstKind::Call)
+                    && inst.operand(&ctx, 0).display(&ctx, false).to_string() == "@f"
+            })
+        };
+        let blocks: Vec<_> = main.iter(&ctx).filter(|&block| calls_f(block)).collect();
+        assert_eq!(blocks.len(), 3);
+        for block in blocks {
+            assert_eq!(block.users(&ctx).into_iter().count(), 1);
+            assert!(!block.iter(&ctx).any(|inst| matches!(inst.kind(&ctx), InstKind::Load)));
+        }
+
+        // Only the value of the logical operation in `==` is materialized.
+        let phis = main
+            .iter(&ctx)
+            .flat_map(|block| block.iter(&ctx))
+            .filter(|inst| inst.is_phi(&ctx))
+            .count();
+        assert_eq!(phis, 1);
+    }
+
+    #[test]
+    fn test_irgen_unary_and_coercion() {
+        let ctx = irgen_src(
+            r#"
+            int main() {
+                int a = getint();
+                float b = -a;
+                int c = !a + -b;
+                if (a) putfloat(b);
+                while (!(b && c)) { b = b + 1; }
+                if (!(a > 0)) c = c + (a < 1);
+                return c;
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        let main = func(&ctx, "main");
+        let text = main.display(&ctx).to_string();
+        for expected in [
+            "sub i32 0, ",
+            "sitofp i32 ",
+            "fneg f32 ",
+            "icmp ne i32 ",
+            "xor i1 ",
+            "zext i1 ",
+            "fptosi f32 ",
+            "fcmp une f32 ",
+        ] {
+            assert!(text.contains(expected), "missing `{}` in\n{}", expected, text);
+        }
+        // `!` in conditions swaps the branch targets instead of computing `xor`
+        assert_eq!(text.matches("xor").count(), 1);
+    }
+
+    #[test]
+    fn test_irgen_float_truthiness() {
+        // A NaN is truthy in C, so the comparison against zero is unordered
+        let ctx = irgen_src(
+            r#"
+            int main() {
+                float x = 0.0 / 0.0;
+                if (x) return 1;
+                return 0;
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        let text = func(&ctx, "main").display(&ctx).to_string();
+        assert!(text.contains("fcmp une f32 "), "{}", text);
+        assert!(!text.contains("fcmp one"), "{}", text);
+        assert!(FloatCmpCond::Une.eval(f32::NAN, 0.0));
+    }
+
+    #[test]
+    fn test_irgen_variadic_float() {
+        let ctx = irgen_src(
+            r#"
+            int main() {
+                float x = getfloat();
+                putf("%d %f %d\n", 1 > 0, x, 2);
+                putfloat(x);
+                return 0;
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        // Only the float is promoted, bool is promoted to int in type checking
+        let text = func(&ctx, "main").display(&ctx).to_string();
+        assert_eq!(text.matches("fpext f32 ").count(), 1, "{}", text);
+        let call = text
+            .lines()
+            .find(|line| line.contains("call void @putf("))
+            .unwrap();
+        assert!(call.contains(", i32 %v") && call.contains(", f64 %v"), "{}", call);
+        assert!(call.ends_with(", i32 2)"), "{}", call);
+        assert!(text.contains("call void @putfloat(f32 %v"), "{}", text);
+    }
+
+    #[test]
+    fn test_irgen_float_ne() {
+        // `NaN != NaN` is true in C, so `!=` is an unordered comparison
+        let ctx = irgen_src(
+            r#"
+            int main() {
+                float a = getfloat();
+                return a != a;
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        let text = func(&ctx, "main").display(&ctx).to_string();
+        assert!(text.contains("fcmp une f32 "), "{}", text);
+        assert!(!text.contains("fcmp one"), "{}", text);
+    }
+
+    #[test]
+    fn test_irgen_default_init() {
+        let ctx = irgen_src(
+            r#"
+            int g;
+            float h[2][3];
+            int main() {
+                int a;
+                float b[4];
+                a = g;
+                return a;
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        // Globals are zero-initialized
+        let text = ctx.to_string();
+        assert!(text.contains("@__GLOBAL_VAR_g = global i32 0"));
+        assert!(text.contains("@__GLOBAL_VAR_h = global [2 x [3 x f32]] zeroinitializer"));
+
+        // Locals are left undefined, so the only stores are the assignment and
+        // the return value
+        let main = func(&ctx, "main");
+        let stores: Vec<_> = main
+            .iter(&ctx)
+            .flat_map(|block| block.iter(&ctx))
+            .filter(|inst| matches!(inst.kind(&ctx), InstKind::Store))
+            .collect();
+        assert_eq!(stores.len(), 2);
+    }
+
+    #[test]
+    fn test_irgen_arrays() {
+        let ctx = irgen_src(
+            r#"
+            const int N[2] = {1, 2};
+            int g[2][3] = {1, {2}, 3};
+            float z[4] = {};
+            int sum(int a[][3], int n) { return a[n][0] + a[1][n]; }
+            int main() {
+                int a[2][3] = {1, 2, 3, 4};
+                int b[2] = {getint(), 5};
+                a[1][2] = b[0] + N[1];
+                putarray(3, a[1]);
+                return sum(a, 1) + sum(g, 0) + z[1];
+            }
+            "#,
+        );
+        assert!(verify(&ctx).is_ok());
+
+        let text = ctx.to_string();
+        for expected in [
+            "@__GLOBAL_CONST_N = global [2 x i32] [i32 1, i32 2]",
+            "@__GLOBAL_VAR_g = global [2 x [3 x i32]] \
+             [[3 x i32] [i32 1, i32 2, i32 3], [3 x i32] zeroinitializer]",
+            "@__GLOBAL_VAR_z = global [4 x f32] zeroinitializer",
+            "declare void @memset(ptr, i32, i32)",
+        ] {
+            assert!(text.contains(expected), "missing `{}` in\n{}", expected, text);
+        }
+
+        // Pointers are indexed from the pointed element
+        let sum = func(&ctx, "sum").display(&ctx).to_string();
+        assert!(sum.contains("getelementptr [3 x i32], ptr %v"));
+
+        // Only the partially initialized array is zero-filled, and the arrays
+        // are passed by pointers without loads
+        let main = func(&ctx, "main").display(&ctx).to_string();
+        assert_eq!(main.matches("call void @memset(ptr %v").count(), 1);
+        assert!(main.contains("i32 0, i32 24)"));
+        // 4 for `a`, 2 for `b`, 1 for the assignment and 1 for the return value
+        assert_eq!(main.matches("store i32 ").count(), 8);
+        assert!(main.contains("call void @putarray(i32 3, ptr %v"));
+        assert!(main.contains("call i32 @sum(ptr @__GLOBAL_VAR_g, i32 0)"));
+        assert!(!main.contains("load [2 x"));
+    }
+}
@@ -1,7 +1,7 @@
 //! Parser for SysY language.
 //! This module guides LALRPOP to generate the parser for SysY language.
 use hexponent::FloatLiteral;
-use lalrpop_util::lalrpop_mod;
+use lalrpop_util::{lalrpop_mod, ParseError};
 
 // Define a module named `parser` and generate the parser according to the
 // grammar in `sysy.lalrpop` to `src/frontend/sysy.rs`.
@@ -15,6 +15,111 @@ pub fn parse_hexadecimal_float(s: &str) -> f32 {
     result.inner() as f32
 }
 
+/// Parse the digits of an integer literal in the given radix into an i32.
+///
+/// Literals up to `u32::MAX` wrap around, and larger ones are reported as a
+/// parse error instead of overflowing.
+pub fn parse_int<T>(s: &str, radix: u32) -> Result<i32, ParseError<usize, T, &'static str>> {
+    u32::from_str_radix(s, radix)
+        .map(|value| value as i32)
+        .map_err(|_| ParseError::User {
+            error: "integer literal is too large",
+        })
+}
+
+/// Decode the escape sequences in the content of a string literal (without
+/// the quotes) into bytes.
+///
+/// The simple escape sequences in C, octal escapes like `\0` and hexadecimal
+/// escapes like `\x1b` are supported. An unknown escape sequence is kept as
+/// the character after the backslash.
+pub fn decode_string(s: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        let Some(c) = chars.next() else {
+            bytes.push(b'\\');
+            break;
+        };
+        let byte = match c {
+            'n' => b'\n',
+            't' => b'\t',
+            'r' => b'\r',
+            'a' => 0x07,
+            'b' => 0x08,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            '0'..='7' => {
+                // At most 3 octal digits
+                let mut value = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                value as u8
+            }
+            'x' => {
+                // Out-of-range values are truncated to a byte
+                let mut value = 0u8;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) {
+                    value = value.wrapping_mul(16).wrapping_add(digit as u8);
+                    chars.next();
+                }
+                value
+            }
+            // `\\`, `\"`, `\'`, `\?` and unknown escapes
+            c => {
+                let mut buf = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                continue;
+            }
+        };
+        bytes.push(byte);
+    }
+    bytes
+}
+
 // Make top-level parser public.
 pub use parser::NumberParser;
 pub use parser::SysYParser;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_string() {
+        assert_eq!(decode_string(r"%d\n"), b"%d\n");
+        assert_eq!(decode_string(r#"\"a\"\\\t"#), b"\"a\"\\\t");
+        assert_eq!(decode_string(r"\0\101\x41\x7e\q"), b"\0AA~q");
+        assert_eq!(decode_string("é"), "é".as_bytes());
+    }
+
+    #[test]
+    fn test_int_literal_range() {
+        let parser = SysYParser::new();
+        assert!(parser.parse("int a = 4294967295;").is_ok());
+        assert!(parser.parse("int a = 0xffffffff;").is_ok());
+        assert!(parser.parse("int a = -2147483648;").is_ok());
+
+        for src in ["int a = 4294967296;", "int a = 0x100000000;", "int a = 040000000000;"] {
+            let err = parser.parse(src).unwrap_err();
+            assert!(matches!(
+                err,
+                ParseError::User {
+                    error: "integer literal is too large"
+                }
+            ));
+        }
+    }
+}
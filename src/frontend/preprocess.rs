@@ -6,8 +6,192 @@
 //!
 //! Additionally, the `__LINE__` is replaced by the line number of the source
 //! file.
+//!
+//! The replacement is token-aware: nothing is replaced inside comments and
+//! string literals, and identifiers like `mystarttime` are left untouched.
+
+use crate::infra::span::{LineIndex, Span};
 
 /// Preprocess the source code of SysY.
+pub fn preprocess(src: &str) -> String { preprocess_with_map(src).0 }
+
+/// Preprocess the source code of SysY, and get a [`SourceMap`] from the
+/// preprocessed source back to the original source.
+pub fn preprocess_with_map(src: &str) -> (String, SourceMap) {
+    let index = LineIndex::new(src);
+    let bytes = src.as_bytes();
+
+    let mut out = String::with_capacity(src.len());
+    let mut map = SourceMap::default();
+    // The start of the source not copied to the output yet.
+    let mut copied = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let rest = &src[i..];
+        if rest.starts_with("//") {
+            i += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            i += comment.find("*/").map_or(rest.len(), |end| end + 4);
+        } else if bytes[i] == b'"' {
+            i += string_len(rest);
+        } else if bytes[i].is_ascii_digit() {
+            // Skip the whole number, e.g., `0x1f`, so that its tail is not taken as an
+            // identifier
+            i += rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.')
+                .unwrap_or(rest.len());
+        } else if bytes[i].is_ascii_alphabetic() || bytes[i] == b'_' {
+            let ident_len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let ident = &rest[..ident_len];
+            let line = index.line_col(i).line;
+
+            let replacement = match ident {
+                "__LINE__" => Some((line.to_string(), ident_len)),
+                "starttime" | "stoptime" => empty_call_len(&rest[ident_len..])
+                    .map(|len| (format!("_sysy_{}({})", ident, line), ident_len + len)),
+                _ => None,
+            };
+
+            match replacement {
+                Some((text, len)) => {
+                    out += &src[copied..i];
+                    map.edits.push(Edit {
+                        output: Span::new(out.len(), out.len() + text.len()),
+                        input: Span::new(i, i + len),
+                    });
+                    out += &text;
+                    i += len;
+                    copied = i;
+                }
+                None => i += ident_len,
+            }
+        } else {
+            i += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    out += &src[copied..];
+
+    (out, map)
+}
+
+/// Get the length of the string literal at the start of `src`, including the
+/// quotes.
+fn string_len(src: &str) -> usize {
+    let mut escaped = false;
+    for (i, c) in src.char_indices().skip(1) {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return i + 1,
+            // An unterminated string ends at the end of the line
+            '\n' => return i,
+            _ => escaped = false,
+        }
+    }
+    src.len()
+}
+
+/// Get the length of `()` (with optional whitespaces) at the start of `src`.
+fn empty_call_len(src: &str) -> Option<usize> {
+    let lparen = src.len() - src.trim_start().len();
+    let after_lparen = src[lparen..].strip_prefix('(')?;
+    let rparen = after_lparen.len() - after_lparen.trim_start().len();
+    after_lparen[rparen..].strip_prefix(')')?;
+    Some(lparen + rparen + 2)
+}
+
+/// A replacement made by the preprocessor.
+#[derive(Debug, Clone, Copy)]
+struct Edit {
+    /// The span of the replacement in the preprocessed source.
+    output: Span,
+    /// The span of the replaced text in the original source.
+    input: Span,
+}
+
+/// A map from offsets in the preprocessed source to offsets in the original
+/// source.
 ///
-/// TODO: Implement the macro replacement.
-pub fn preprocess(src: &str) -> String { src.to_string() }
+/// The replacements may remove line breaks (e.g., in `starttime(\n)`), and
+/// always change the columns after them. Spans of the preprocessed source
+/// should be mapped back before reporting diagnostics with the original
+/// source.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    /// The replacements, sorted by their offsets.
+    edits: Vec<Edit>,
+}
+
+impl SourceMap {
+    /// Map an offset in the preprocessed source to the original source.
+    ///
+    /// Offsets inside a replacement are mapped into the replaced text.
+    pub fn map_offset(&self, offset: usize) -> usize {
+        let idx = self
+            .edits
+            .partition_point(|edit| edit.output.start <= offset);
+        if idx == 0 {
+            return offset;
+        }
+        let Edit { output, input } = self.edits[idx - 1];
+        if offset < output.end {
+            input.start + (offset - output.start).min(input.len())
+        } else {
+            input.end + (offset - output.end)
+        }
+    }
+
+    /// Map a span in the preprocessed source to the original source.
+    pub fn map_span(&self, span: Span) -> Span {
+        Span::new(self.map_offset(span.start), self.map_offset(span.end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preprocess() {
+        let src = r#"int main() {
+    starttime();
+    int mystarttime = __LINE__;
+    // starttime();
+    /* stoptime(); */ putf("stoptime()");
+    stoptime (
+    );
+    starttime(1);
+    return 0x1__LINE__;
+}"#;
+        let expected = r#"int main() {
+    _sysy_starttime(2);
+    int mystarttime = 3;
+    // starttime();
+    /* stoptime(); */ putf("stoptime()");
+    _sysy_stoptime(6);
+    starttime(1);
+    return 0x1__LINE__;
+}"#;
+        assert_eq!(preprocess(src), expected);
+    }
+
+    #[test]
+    fn test_source_map() {
+        let src = "int a = __LINE__;\nstarttime(\n);\nint b = c;";
+        let (out, map) = preprocess_with_map(src);
+        assert_eq!(out, "int a = 1;\n_sysy_starttime(2);\nint b = c;");
+
+        // Before and after the replacements
+        assert_eq!(map.map_offset(4), 4);
+        let c = out.find('c').unwrap();
+        assert_eq!(map.map_offset(c), src.find('c').unwrap());
+
+        // The whole replacement is mapped to the replaced text
+        let start = out.find("_sysy").unwrap();
+        let span = Span::new(start, start + "_sysy_starttime(2)".len());
+        let start = src.find("starttime").unwrap();
+        assert_eq!(map.map_span(span), Span::new(start, start + "starttime(\n)".len()));
+    }
+}
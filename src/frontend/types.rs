@@ -19,8 +19,21 @@ pub enum TypeKind {
     Int,
     /// The float type
     Float,
-    /// The function type, with params and return type.
-    Func(Vec<Type>, Type),
+    /// The array type, with element type and length.
+    Array(Type, usize),
+    /// The pointer type.
+    ///
+    /// SysY has no pointer, but array parameters like `int a[][3]` are decayed
+    /// into pointers to their element type.
+    Ptr(Type),
+    /// The string type.
+    ///
+    /// SysY has no string, but string literals can be passed as the format of
+    /// the library function `putf`.
+    Str,
+    /// The function type, with params, return type, and whether it takes
+    /// variadic arguments after the params.
+    Func(Vec<Type>, Type, bool),
 }
 
 // The type in AST
@@ -48,16 +61,24 @@ impl fmt::Display for Type {
             TypeKind::Bool => write!(f, "bool"),
             TypeKind::Int => write!(f, "int"),
             TypeKind::Float => write!(f, "float"),
-            TypeKind::Func(params, ret) => write!(
-                f,
-                "{}({})",
-                ret,
-                params
-                    .iter()
-                    .map(|t| t.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            ),
+            TypeKind::Array(..) => {
+                // Display multi-dimensional arrays like `int[2][3]`.
+                let (base, dims) = self.split_dims();
+                write!(f, "{}", base)?;
+                for dim in dims {
+                    write!(f, "[{}]", dim)?;
+                }
+                Ok(())
+            }
+            TypeKind::Ptr(elem) => write!(f, "{}*", elem),
+            TypeKind::Str => write!(f, "str"),
+            TypeKind::Func(params, ret, variadic) => {
+                let mut params = params.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+                if *variadic {
+                    params.push("...".to_string());
+                }
+                write!(f, "{}({})", ret, params.join(", "))
+            }
         }
     }
 }
@@ -102,8 +123,32 @@ impl Type {
     /// Create a new float type.
     pub fn float() -> Self { Self::make(TypeKind::Float) }
 
+    /// Create a new array type.
+    pub fn array(elem: Type, len: usize) -> Self { Self::make(TypeKind::Array(elem, len)) }
+
+    /// Create a new pointer type.
+    pub fn ptr(elem: Type) -> Self { Self::make(TypeKind::Ptr(elem)) }
+
+    /// Create a (possibly multi-dimensional) array type from the base type and
+    /// the dimensions, e.g., `int` and `[2, 3]` make `int[2][3]`.
+    pub fn array_of(base: Type, dims: &[usize]) -> Self {
+        dims.iter()
+            .rev()
+            .fold(base, |elem, &len| Self::array(elem, len))
+    }
+
+    /// Create a new string type.
+    pub fn str() -> Self { Self::make(TypeKind::Str) }
+
     /// Create a new function type.
-    pub fn func(params: Vec<Type>, ret: Type) -> Self { Self::make(TypeKind::Func(params, ret)) }
+    pub fn func(params: Vec<Type>, ret: Type) -> Self {
+        Self::make(TypeKind::Func(params, ret, false))
+    }
+
+    /// Create a new function type taking variadic arguments after `params`.
+    pub fn variadic_func(params: Vec<Type>, ret: Type) -> Self {
+        Self::make(TypeKind::Func(params, ret, true))
+    }
 
     /// Check if the type is a int type.
     pub fn is_int(&self) -> bool { matches!(self.kind(), TypeKind::Int) }
@@ -114,16 +159,49 @@ impl Type {
     /// Check if the type is a void type.
     pub fn is_void(&self) -> bool { matches!(self.kind(), TypeKind::Void) }
 
-    /// Check if the type is a void type.
+    /// Check if the type is a float type.
     pub fn is_float(&self) -> bool { matches!(self.kind(), TypeKind::Float) }
 
+    /// Check if the type is an array type.
+    pub fn is_array(&self) -> bool { matches!(self.kind(), TypeKind::Array(..)) }
+
+    /// Check if the type is a pointer type.
+    pub fn is_ptr(&self) -> bool { matches!(self.kind(), TypeKind::Ptr(..)) }
+
+    /// Check if the type is a function type taking variadic arguments.
+    pub fn is_variadic(&self) -> bool { matches!(self.kind(), TypeKind::Func(_, _, true)) }
+
+    /// Check if the type is a scalar type, i.e., bool, int or float.
+    pub fn is_scalar(&self) -> bool { self.is_bool() || self.is_int() || self.is_float() }
+
+    /// Get the type of the element when indexing into an array or a pointer.
+    pub fn indexed(&self) -> Option<&Type> {
+        match self.kind() {
+            TypeKind::Array(elem, _) | TypeKind::Ptr(elem) => Some(elem),
+            _ => None,
+        }
+    }
+
+    /// Split an array type into the base type and the dimensions.
+    ///
+    /// For non-array types, the dimensions are empty.
+    pub fn split_dims(&self) -> (&Type, Vec<usize>) {
+        let mut ty = self;
+        let mut dims = Vec::new();
+        while let TypeKind::Array(elem, len) = ty.kind() {
+            dims.push(*len);
+            ty = elem;
+        }
+        (ty, dims)
+    }
+
     /// Get the parameters and return type of a function type.
     ///
     /// # Panics
     ///
     /// - Panics if the type is not a function type.
     pub fn unwrap_func(&self) -> (&[Type], &Type) {
-        if let TypeKind::Func(params, ret) = self.kind() {
+        if let TypeKind::Func(params, ret, _) = self.kind() {
             (params, ret)
         } else {
             panic!("unwrap_func: not a function type: {}", self);
@@ -137,7 +215,11 @@ impl Type {
             TypeKind::Bool => 1,
             TypeKind::Int => 4,
             TypeKind::Float => 4,
-            TypeKind::Func(_, _) => unreachable!(),
+            TypeKind::Array(elem, len) => elem.bytewidth() * len,
+            TypeKind::Ptr(_) | TypeKind::Str => {
+                unreachable!("pointer size depends on the target")
+            }
+            TypeKind::Func(..) => unreachable!(),
         }
     }
 }
@@ -153,4 +235,32 @@ mod tests {
         assert_eq!(Type::int().to_string(), "int");
         assert_eq!(Type::float().to_string(), "float");
     }
+
+    #[test]
+    fn test_array_type() {
+        let arr = Type::array_of(Type::int(), &[2, 3]);
+        assert_eq!(arr, Type::array(Type::array(Type::int(), 3), 2));
+        assert_eq!(arr.to_string(), "int[2][3]");
+        assert_eq!(arr.bytewidth(), 24);
+        assert_eq!(arr.split_dims(), (&Type::int(), vec![2, 3]));
+        assert_eq!(arr.indexed(), Some(&Type::array(Type::int(), 3)));
+
+        let ptr = Type::ptr(Type::array(Type::float(), 3));
+        assert_eq!(ptr.to_string(), "float[3]*");
+        assert_eq!(ptr.indexed(), Some(&Type::array(Type::float(), 3)));
+        assert_eq!(Type::int().indexed(), None);
+    }
+
+    #[test]
+    fn test_func_type() {
+        let func = Type::func(vec![Type::int(), Type::ptr(Type::float())], Type::void());
+        assert_eq!(func.to_string(), "void(int, float*)");
+        assert!(!func.is_variadic());
+
+        let putf = Type::variadic_func(vec![Type::str()], Type::void());
+        assert_eq!(putf.to_string(), "void(str, ...)");
+        assert!(putf.is_variadic());
+        assert_ne!(putf, Type::func(vec![Type::str()], Type::void()));
+        assert_eq!(putf.unwrap_func(), (&[Type::str()][..], &Type::void()));
+    }
 }
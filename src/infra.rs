@@ -1,2 +1,3 @@
 pub mod linked_list;
+pub mod span;
 pub mod storage;
@@ -0,0 +1,132 @@
+//! Source Locations
+//!
+//! This module provides [`Span`] to record where a piece of syntax comes from,
+//! and [`LineIndex`] to translate the byte offsets in spans into line and
+//! column numbers.
+//!
+//! Spans are attached to AST nodes by the parser, and carried into the IR so
+//! that instructions can be attributed back to source lines.
+
+use std::fmt;
+
+/// A half-open range of byte offsets `[start, end)` in the source.
+///
+/// The default span is empty at offset 0, which is used for nodes synthesized
+/// by the compiler that have no counterpart in the source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        debug_assert!(start <= end, "invalid span: {}..{}", start, end);
+        Self { start, end }
+    }
+
+    /// Get the smallest span covering both `self` and `other`.
+    pub fn merge(self, other: Span) -> Self {
+        Self {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        }
+    }
+
+    pub fn len(&self) -> usize { self.end - self.start }
+
+    pub fn is_empty(&self) -> bool { self.start == self.end }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A line and column position in the source, both 1-based.
+///
+/// The column is counted in characters, not bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for LineCol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}:{}", self.line, self.col) }
+}
+
+/// An index of line starts of a source, used to look up the line and column
+/// of byte offsets.
+pub struct LineIndex<'a> {
+    src: &'a str,
+    /// Byte offsets of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { src, line_starts }
+    }
+
+    /// Get the line and column of the byte offset.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the offset is out of the source or not on a char boundary.
+    pub fn line_col(&self, offset: usize) -> LineCol {
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let col = self.src[self.line_starts[line]..offset].chars().count();
+        LineCol {
+            line: line + 1,
+            col: col + 1,
+        }
+    }
+
+    /// Get the text of the 1-based line, without the line terminator.
+    pub fn line(&self, line: usize) -> &'a str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.src.len());
+        self.src[start..end].trim_end_matches(['\n', '\r'])
+    }
+
+    /// Get the number of lines in the source.
+    pub fn num_lines(&self) -> usize { self.line_starts.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let src = "int a;\r\nint main() {\n  return 0;\n}";
+        let index = LineIndex::new(src);
+
+        assert_eq!(index.num_lines(), 4);
+        assert_eq!(index.line_col(0), LineCol { line: 1, col: 1 });
+        assert_eq!(index.line_col(4), LineCol { line: 1, col: 5 });
+        assert_eq!(index.line_col(8), LineCol { line: 2, col: 1 });
+        assert_eq!(index.line_col(src.find("return").unwrap()), LineCol { line: 3, col: 3 });
+        assert_eq!(index.line_col(src.len()), LineCol { line: 4, col: 2 });
+
+        assert_eq!(index.line(1), "int a;");
+        assert_eq!(index.line(3), "  return 0;");
+        assert_eq!(index.line(4), "}");
+    }
+
+    #[test]
+    fn test_span_merge() {
+        let span = Span::new(3, 5).merge(Span::new(8, 10));
+        assert_eq!(span, Span::new(3, 10));
+        assert_eq!(span.len(), 7);
+    }
+}
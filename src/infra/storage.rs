@@ -327,7 +327,8 @@ impl<Data> Arena<GenericPtr<Data>> for GenericArena<Data> {
 
     fn try_dealloc(&mut self, ptr: GenericPtr<Data>) -> Option<Data> {
         let index = ptr.index();
-        if index >= self.entries.len() {
+        // A vacant entry is already in the free list, it should not be pushed twice.
+        if !matches!(self.entries.get(index)?, GenericEntry::Occupied(_)) {
             return None;
         }
         let old_entry = mem::replace(
@@ -338,7 +339,7 @@ impl<Data> Arena<GenericPtr<Data>> for GenericArena<Data> {
         );
         self.free_head = Some(index);
         match old_entry {
-            GenericEntry::Vacant { .. } => None,
+            GenericEntry::Vacant { .. } => unreachable!(),
             GenericEntry::Occupied(data) => Some(data),
         }
     }
@@ -543,6 +544,12 @@ mod tests {
         let ptr1 = arena.alloc(1);
         assert_eq!(arena.try_dealloc(ptr1), Some(1));
         assert_eq!(arena.try_dealloc(ptr1), None); // double free
+        // the free list is not corrupted by the double free
+        let ptr2 = arena.alloc(2);
+        let ptr3 = arena.alloc(3);
+        assert_ne!(ptr2, ptr3);
+        assert_eq!(ptr2.try_deref(&arena), Some(&2));
+        assert_eq!(ptr3.try_deref(&arena), Some(&3));
     }
 
     #[test]
@@ -1,11 +1,74 @@
+//! The intermediate representation (IR).
+//!
+//! The IR is in SSA form, stored in a [`Context`] with arenas of types,
+//! values, instructions, blocks, functions and globals. Whether a context is
+//! well-formed can be checked with [`verify()`].
+//!
+//! # Textual Format
+//!
+//! A [`Context`] can be printed in a textual format similar to LLVM IR. The
+//! output is deterministic for the same context, so it can be used in
+//! golden-file tests.
+//!
+//! ```text
+//! @g = global i32 0
+//! @a = global [2 x i32] [i32 1, i32 2]
+//! @z = global [4 x f32] zeroinitializer
+//! @__STR_0 = private constant [3 x i8] [i8 104, i8 105, i8 0]
+//!
+//! declare i32 @getint()
+//! declare void @putf(ptr, ...)
+//!
+//! define i32 @main(i32 %v0, ptr %v1) {
+//! bb_0:
+//!     %v2 = alloca i32
+//!     store i32 %v0, ptr %v2
+//!     %v3 = load i32, ptr %v2
+//!     %v4 = getelementptr [2 x i32], ptr @a, i32 0, i32 1
+//!     %v5 = icmp slt i32 %v3, 10
+//!     br i1 %v5, label %bb_1, label %bb_2
+//! bb_1:
+//!     %v6 = call i32 @getint()
+//!     br label %bb_2
+//! bb_2:
+//!     %v7 = phi i32 [%v3, %bb_0], [%v6, %bb_1]
+//!     %v8 = sitofp i32 %v7 to f32
+//!     %v9 = fmul f32 %v8, 0.5
+//!     ret i32 %v7
+//! }
+//! ```
+//!
+//! - Types are `void`, `i1`, `i8`, `i32`, `f32`, `f64`, `ptr` and arrays
+//!   like `[2 x i32]`. `f64` is only used for float arguments to variadic
+//!   functions, which are promoted by `fpext` as in C.
+//! - Values are named by their indices in the arena, e.g., `%v3`, and blocks
+//!   are named like `bb_0` (and referred as `%bb_0`). Globals and functions are
+//!   referred by their names, e.g., `@a`.
+//! - Constants are printed as is, e.g., `1`, `true`, `undef`, and floats always
+//!   have a decimal point or an exponent, e.g., `0.5`, `1e-10`.
+//! - Operands are printed with their types, except for the second operand of
+//!   binary operations, which has the same type as the first one.
+//! - Phi incomings are printed in the order they are inserted.
+//! - The callee of `call` is the first operand of the instruction, followed by
+//!   the arguments.
+//! - Instructions are indented with a tab.
+//!
+//! The textual format can be read back with [`parse()`], e.g., to write tests
+//! of passes directly in IR. Comments starting with `;` are also allowed.
+
+pub mod analysis;
+pub mod passes;
+
 mod block;
 mod context;
 mod def_use;
 mod func;
 mod global;
 mod inst;
+mod parse;
 mod ty;
 mod value;
+mod verify;
 
 pub use block::*;
 pub use context::*;
@@ -13,5 +76,7 @@ pub use def_use::*;
 pub use func::*;
 pub use global::*;
 pub use inst::*;
+pub use parse::*;
 pub use ty::*;
 pub use value::*;
+pub use verify::*;
@@ -0,0 +1,17 @@
+//! Analyses of the IR.
+//!
+//! Analyses of a function are computed on demand and cached in the function,
+//! e.g., [`Func::cfg`](super::Func::cfg). The cache is invalidated whenever the
+//! function is mutated in a way that may change the result, so a cached
+//! analysis is always up to date. The pass manager also drops the analyses a
+//! pass does not report as preserved, see [`PreservedAnalyses`].
+
+mod cfg;
+mod dominance;
+mod loops;
+mod preserved;
+
+pub use cfg::*;
+pub use dominance::*;
+pub use loops::*;
+pub use preserved::*;
@@ -0,0 +1,297 @@
+//! Control flow graph (CFG) of a function.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::infra::linked_list::{LinkedListContainer, LinkedListNode};
+use crate::ir::{Block, Context, Func, Inst};
+
+/// The control flow graph of a function.
+///
+/// The edges are given by the successors of the terminators. Multiple edges
+/// between the same pair of blocks, e.g., `br i1 %c, label %bb_1, label
+/// %bb_1`, are merged into one.
+#[derive(Debug, Clone, Default)]
+pub struct Cfg {
+    /// The entry block, `None` for declarations.
+    entry: Option<Block>,
+    /// All the blocks in the layout order.
+    blocks: Vec<Block>,
+    /// Successors of each block, in the order of the terminator operands.
+    succs: HashMap<Block, Vec<Block>>,
+    /// Predecessors of each block, in the layout order.
+    preds: HashMap<Block, Vec<Block>>,
+    /// Blocks reachable from the entry, in reverse postorder.
+    rpo: Vec<Block>,
+    /// The index of each reachable block in `rpo`.
+    rpo_index: HashMap<Block, usize>,
+}
+
+impl Cfg {
+    /// Compute the control flow graph of `func`.
+    pub fn new(ctx: &Context, func: Func) -> Self {
+        let mut cfg = Cfg {
+            entry: func.head(ctx),
+            ..Default::default()
+        };
+
+        for block in func.iter(ctx) {
+            cfg.blocks.push(block);
+            cfg.preds.entry(block).or_default();
+            let mut succs: Vec<Block> = Vec::new();
+            if let Some(terminator) = block.terminator(ctx) {
+                for succ in terminator.successor_iter(ctx) {
+                    if !succs.contains(&succ) {
+                        succs.push(succ);
+                    }
+                }
+            }
+            for &succ in succs.iter() {
+                cfg.preds.entry(succ).or_default().push(block);
+            }
+            cfg.succs.insert(block, succs);
+        }
+
+        // Iterative DFS from the entry, a block is finished after all its successors.
+        let mut postorder = Vec::new();
+        let mut visited = HashSet::new();
+        if let Some(entry) = cfg.entry {
+            let mut stack = vec![(entry, 0)];
+            visited.insert(entry);
+            while let Some((block, next)) = stack.pop() {
+                match cfg.succs[&block].get(next).copied() {
+                    Some(succ) => {
+                        stack.push((block, next + 1));
+                        if visited.insert(succ) {
+                            stack.push((succ, 0));
+                        }
+                    }
+                    None => postorder.push(block),
+                }
+            }
+        }
+        postorder.reverse();
+        cfg.rpo = postorder;
+        cfg.rpo_index = cfg.rpo.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+
+        cfg
+    }
+
+    /// Get the entry block, or `None` if the function has no blocks.
+    pub fn entry(&self) -> Option<Block> { self.entry }
+
+    /// Get all the blocks in the layout order.
+    pub fn blocks(&self) -> &[Block] { &self.blocks }
+
+    /// Get the successors of the block.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the block is not in the function.
+    pub fn succs(&self, block: Block) -> &[Block] { &self.succs[&block] }
+
+    /// Get the predecessors of the block, including the unreachable ones.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the block is not in the function.
+    pub fn preds(&self, block: Block) -> &[Block] { &self.preds[&block] }
+
+    /// Get the blocks reachable from the entry in reverse postorder, i.e., a
+    /// block comes before its successors unless the edge is a back edge.
+    pub fn reverse_postorder(&self) -> &[Block] { &self.rpo }
+
+    /// Iterate over the blocks reachable from the entry in postorder.
+    pub fn postorder(&self) -> impl Iterator<Item = Block> + '_ { self.rpo.iter().rev().copied() }
+
+    /// Get the index of a reachable block in the reverse postorder.
+    pub fn rpo_index(&self, block: Block) -> Option<usize> { self.rpo_index.get(&block).copied() }
+
+    /// Check if the block is reachable from the entry.
+    pub fn is_reachable(&self, block: Block) -> bool { self.rpo_index.contains_key(&block) }
+
+    /// Iterate over the blocks not reachable from the entry, in the layout
+    /// order.
+    pub fn unreachable_blocks(&self) -> impl Iterator<Item = Block> + '_ {
+        self.blocks
+            .iter()
+            .copied()
+            .filter(|&block| !self.is_reachable(block))
+    }
+
+    /// Check if the edge from `from` to `to` is critical, i.e., `from` has
+    /// multiple successors and `to` has multiple predecessors.
+    pub fn is_critical_edge(&self, from: Block, to: Block) -> bool {
+        self.succs(from).len() > 1 && self.preds(to).len() > 1
+    }
+}
+
+/// Split all the critical edges in the function, and return the new blocks.
+///
+/// A critical edge cannot have instructions placed on it without affecting
+/// other paths, e.g., the copies when leaving SSA. The edge is split by a new
+/// block that branches to the original destination, placed after the source
+/// block. Phi nodes in the destination take the incoming values from
+/// the new block instead.
+pub fn split_critical_edges(ctx: &mut Context, func: Func) -> Vec<Block> {
+    let cfg = func.cfg(ctx).clone();
+    let mut new_blocks = Vec::new();
+
+    for &from in cfg.blocks() {
+        // The new blocks are placed after the source in the order of the successors.
+        let mut pos = from;
+        for &to in cfg.succs(from) {
+            if !cfg.is_critical_edge(from, to) {
+                continue;
+            }
+
+            let edge = Block::new(ctx);
+            let br = Inst::br(ctx, to);
+            edge.push_back(ctx, br).unwrap();
+            pos.insert_after(ctx, edge).unwrap();
+            pos = edge;
+
+            let terminator = from.terminator(ctx).unwrap();
+            let indices: Vec<usize> = terminator
+                .indexed_successor_iter(ctx)
+                .filter(|&(_, succ)| succ == to)
+                .map(|(idx, _)| idx)
+                .collect();
+            for idx in indices {
+                terminator.set_successor(ctx, idx, edge);
+            }
+
+            let phis: Vec<Inst> = to.iter(ctx).take_while(|inst| inst.is_phi(ctx)).collect();
+            for phi in phis {
+                let value = phi.incoming(ctx, from);
+                phi.remove_incoming(ctx, from);
+                phi.insert_incoming(ctx, edge, value);
+            }
+
+            new_blocks.push(edge);
+        }
+    }
+
+    new_blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{parse, verify};
+
+    const LOOP: &str = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr label %bb_1
+bb_1:
+\t%v1 = phi i32 [0, %bb_0], [%v2, %bb_2]
+\tbr i1 %v0, label %bb_2, label %bb_3
+bb_2:
+\t%v2 = add i32 %v1, 1
+\tbr label %bb_1
+bb_4:
+\tbr label %bb_3
+bb_3:
+\tret i32 %v1
+}
+";
+
+    fn blocks(ctx: &Context, func: Func) -> Vec<Block> { func.iter(ctx).collect() }
+
+    #[test]
+    fn test_cfg() {
+        let ctx = parse(LOOP).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let [bb0, bb1, bb2, bb4, bb3] = blocks(&ctx, func)[..] else {
+            panic!("expected 5 blocks");
+        };
+        let cfg = func.cfg(&ctx);
+
+        assert_eq!(cfg.entry(), Some(bb0));
+        assert_eq!(cfg.succs(bb1), [bb2, bb3]);
+        assert_eq!(cfg.preds(bb1), [bb0, bb2]);
+        assert_eq!(cfg.preds(bb3), [bb1, bb4]);
+        assert!(cfg.preds(bb4).is_empty());
+
+        assert_eq!(cfg.reverse_postorder(), [bb0, bb1, bb3, bb2]);
+        assert_eq!(cfg.postorder().collect::<Vec<_>>(), [bb2, bb3, bb1, bb0]);
+        assert_eq!(cfg.rpo_index(bb3), Some(2));
+        assert!(!cfg.is_reachable(bb4));
+        assert_eq!(cfg.unreachable_blocks().collect::<Vec<_>>(), [bb4]);
+
+        assert!(cfg.is_critical_edge(bb1, bb3));
+        assert!(!cfg.is_critical_edge(bb1, bb2));
+    }
+
+    #[test]
+    fn test_cfg_invalidated() {
+        let mut ctx = parse(LOOP).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let [_, bb1, bb2, bb4, bb3] = blocks(&ctx, func)[..] else {
+            panic!("expected 5 blocks");
+        };
+        assert_eq!(func.cfg(&ctx).succs(bb1), [bb2, bb3]);
+
+        // Redirect the exit of the loop through `bb_4`
+        let terminator = bb1.terminator(&ctx).unwrap();
+        terminator.set_successor(&mut ctx, 1, bb4);
+        assert_eq!(func.cfg(&ctx).succs(bb1), [bb2, bb4]);
+        assert!(func.cfg(&ctx).is_reachable(bb4));
+
+        bb2.terminator(&ctx).unwrap().erase(&mut ctx);
+        assert!(func.cfg(&ctx).succs(bb2).is_empty());
+        assert_eq!(func.cfg(&ctx).preds(bb1).len(), 1);
+
+        let bb5 = Block::new(&mut ctx);
+        func.push_back(&mut ctx, bb5).unwrap();
+        assert_eq!(func.cfg(&ctx).unreachable_blocks().collect::<Vec<_>>(), [bb5]);
+    }
+
+    #[test]
+    fn test_split_critical_edges() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tbr i1 %v0, label %bb_2, label %bb_1
+bb_2:
+\t%v1 = phi i32 [0, %bb_0], [1, %bb_1]
+\tret i32 %v1
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let new_blocks = split_critical_edges(&mut ctx, func);
+        assert_eq!(new_blocks.len(), 4);
+        assert!(verify(&ctx).is_ok());
+
+        let cfg = func.cfg(&ctx);
+        for &block in cfg.blocks() {
+            for &succ in cfg.succs(block) {
+                assert!(!cfg.is_critical_edge(block, succ));
+            }
+        }
+        assert_eq!(
+            func.display(&ctx).to_string(),
+            "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_3, label %bb_4
+bb_3:
+\tbr label %bb_1
+bb_4:
+\tbr label %bb_2
+bb_1:
+\tbr i1 %v0, label %bb_5, label %bb_6
+bb_5:
+\tbr label %bb_2
+bb_6:
+\tbr label %bb_1
+bb_2:
+\t%v1 = phi i32 [0, %bb_4], [1, %bb_5]
+\tret i32 %v1
+}"
+        );
+    }
+}
@@ -0,0 +1,558 @@
+//! Dominator trees and dominance frontiers.
+//!
+//! Block `a` dominates block `b` if every path from the entry to `b` goes
+//! through `a`, and post-dominates `b` if every path from `b` to an exit goes
+//! through `a`. Both relations are reflexive.
+//!
+//! The immediate dominators are computed with the iterative algorithm in
+//! Cooper, Harvey and Kennedy, "A Simple, Fast Dominance Algorithm", which
+//! handles irreducible control flow without special treatment.
+
+use std::collections::{HashMap, HashSet};
+
+use super::Cfg;
+use crate::infra::linked_list::LinkedListNode;
+use crate::ir::{Block, Context, Inst};
+
+/// A dominator tree, or a post-dominator tree, of a function.
+///
+/// Only the blocks reachable from the entry are in the dominator tree. In the
+/// post-dominator tree, only the blocks that can reach an exit are, and every
+/// exit, i.e., a block without successors, is a root.
+#[derive(Debug, Clone, Default)]
+pub struct DomTree {
+    /// Whether this is a post-dominator tree.
+    post: bool,
+    /// The roots of the tree, i.e., the entry or the exits.
+    roots: Vec<Block>,
+    /// The immediate dominator of each block, `None` for the roots.
+    idoms: HashMap<Block, Option<Block>>,
+    /// The children of each block in the tree.
+    children: HashMap<Block, Vec<Block>>,
+    /// The preorder and postorder numbers of each block in the tree, to answer
+    /// dominance queries in constant time.
+    order: HashMap<Block, (usize, usize)>,
+}
+
+impl DomTree {
+    /// Compute the dominator tree from the CFG.
+    pub fn new(cfg: &Cfg) -> Self {
+        let nodes = cfg.reverse_postorder();
+        let preds = nodes
+            .iter()
+            .map(|&block| {
+                cfg.preds(block)
+                    .iter()
+                    .filter_map(|&pred| cfg.rpo_index(pred))
+                    .collect()
+            })
+            .collect();
+        Self::build(nodes, preds)
+    }
+
+    /// Compute the post-dominator tree from the CFG.
+    ///
+    /// This is the dominator tree of the reversed CFG, starting from a virtual
+    /// root that all the exits branch to, which is then removed.
+    pub fn new_post(cfg: &Cfg) -> Self {
+        let exits: Vec<Block> = cfg
+            .blocks()
+            .iter()
+            .copied()
+            .filter(|&block| cfg.succs(block).is_empty())
+            .collect();
+
+        // Postorder of the reversed CFG from the virtual root, which is not included.
+        let mut postorder = Vec::new();
+        let mut visited = HashSet::new();
+        for &exit in exits.iter() {
+            visited.insert(exit);
+            let mut stack = vec![(exit, 0)];
+            while let Some((block, next)) = stack.pop() {
+                match cfg.preds(block).get(next).copied() {
+                    Some(pred) => {
+                        stack.push((block, next + 1));
+                        if visited.insert(pred) {
+                            stack.push((pred, 0));
+                        }
+                    }
+                    None => postorder.push(block),
+                }
+            }
+        }
+
+        let mut rpo: Vec<Option<Block>> = vec![None];
+        rpo.extend(postorder.into_iter().rev().map(Some));
+        let index: HashMap<Block, usize> = rpo
+            .iter()
+            .enumerate()
+            .filter_map(|(i, block)| Some(((*block)?, i)))
+            .collect();
+
+        let preds: Vec<Vec<usize>> = rpo
+            .iter()
+            .map(|&block| match block {
+                None => Vec::new(),
+                Some(block) if cfg.succs(block).is_empty() => vec![0],
+                // Successors that never reach an exit are not in the tree.
+                Some(block) => cfg
+                    .succs(block)
+                    .iter()
+                    .filter_map(|succ| index.get(succ).copied())
+                    .collect(),
+            })
+            .collect();
+        let idoms = compute_idoms(&preds);
+
+        // Only the exits have the virtual root as their immediate dominator.
+        let mut tree = DomTree {
+            post: true,
+            roots: exits,
+            ..Default::default()
+        };
+        for (i, &block) in rpo.iter().enumerate().skip(1) {
+            let block = block.unwrap();
+            let idom = rpo[idoms[i]];
+            tree.idoms.insert(block, idom);
+            tree.children.entry(block).or_default();
+            if let Some(idom) = idom {
+                tree.children.entry(idom).or_default().push(block);
+            }
+        }
+        tree.number();
+        tree
+    }
+
+    /// Build the tree of `nodes` in reverse postorder, where the first node is
+    /// the root, and `preds` are the indices of the predecessors of each node.
+    fn build(nodes: &[Block], preds: Vec<Vec<usize>>) -> Self {
+        let mut tree = DomTree::default();
+        if nodes.is_empty() {
+            return tree;
+        }
+        let idoms = compute_idoms(&preds);
+        tree.roots.push(nodes[0]);
+        for (i, &block) in nodes.iter().enumerate() {
+            tree.children.entry(block).or_default();
+            if i == 0 {
+                tree.idoms.insert(block, None);
+            } else {
+                let idom = nodes[idoms[i]];
+                tree.idoms.insert(block, Some(idom));
+                tree.children.entry(idom).or_default().push(block);
+            }
+        }
+        tree.number();
+        tree
+    }
+
+    /// Number the blocks in preorder and postorder of the tree.
+    fn number(&mut self) {
+        let mut counter = 0;
+        for &root in self.roots.iter() {
+            let mut stack = vec![(root, 0)];
+            self.order.insert(root, (counter, 0));
+            counter += 1;
+            while let Some((block, next)) = stack.pop() {
+                match self.children[&block].get(next).copied() {
+                    Some(child) => {
+                        stack.push((block, next + 1));
+                        stack.push((child, 0));
+                        self.order.insert(child, (counter, 0));
+                        counter += 1;
+                    }
+                    None => {
+                        self.order.get_mut(&block).unwrap().1 = counter;
+                        counter += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Check if this is a post-dominator tree.
+    pub fn is_post(&self) -> bool { self.post }
+
+    /// Get the roots of the tree.
+    pub fn roots(&self) -> &[Block] { &self.roots }
+
+    /// Check if the block is in the tree.
+    pub fn contains(&self, block: Block) -> bool { self.idoms.contains_key(&block) }
+
+    /// Get the immediate dominator of the block, or `None` if the block is a
+    /// root or not in the tree.
+    pub fn idom(&self, block: Block) -> Option<Block> { self.idoms.get(&block).copied().flatten() }
+
+    /// Get the children of the block in the tree, i.e., the blocks it
+    /// immediately dominates.
+    pub fn children(&self, block: Block) -> &[Block] {
+        self.children.get(&block).map_or(&[], Vec::as_slice)
+    }
+
+    /// Check if block `a` dominates block `b`. Blocks not in the tree neither
+    /// dominate nor are dominated by any block.
+    pub fn dominates(&self, a: Block, b: Block) -> bool {
+        match (self.order.get(&a), self.order.get(&b)) {
+            (Some(&(a_pre, a_post)), Some(&(b_pre, b_post))) => a_pre <= b_pre && b_post <= a_post,
+            _ => false,
+        }
+    }
+
+    /// Check if block `a` strictly dominates block `b`, i.e., `a` dominates
+    /// `b` and they are different.
+    pub fn strictly_dominates(&self, a: Block, b: Block) -> bool { a != b && self.dominates(a, b) }
+
+    /// Check if instruction `a` dominates instruction `b`, i.e., `a` comes
+    /// first if they are in the same block, or the block of `a` strictly
+    /// dominates the block of `b`.
+    ///
+    /// For the post-dominator tree, `a` post-dominates `b` if `a` comes after
+    /// `b` in the same block instead.
+    ///
+    /// Note that the uses in a phi node happen at the end of the incoming
+    /// blocks, which should be checked with the incoming blocks instead.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if any of the instructions is not in a block.
+    pub fn dominates_inst(&self, ctx: &Context, a: Inst, b: Inst) -> bool {
+        let a_block = a.container(ctx).expect("instruction not in a block");
+        let b_block = b.container(ctx).expect("instruction not in a block");
+        if a_block != b_block {
+            return self.strictly_dominates(a_block, b_block);
+        }
+        if !self.contains(a_block) {
+            return false;
+        }
+        // In the post-dominator tree the order in the block is reversed.
+        let (first, second) = if self.post { (b, a) } else { (a, b) };
+        let mut curr = Some(first);
+        while let Some(inst) = curr {
+            if inst == second {
+                return true;
+            }
+            curr = inst.next(ctx);
+        }
+        false
+    }
+}
+
+/// The dominance frontiers of the blocks in a dominator tree.
+///
+/// The dominance frontier of block `a` is the set of blocks `b` such that `a`
+/// dominates a predecessor of `b` but does not strictly dominate `b`, i.e., the
+/// blocks where the dominance of `a` ends. This is where phi nodes are needed
+/// for a variable defined in `a`.
+#[derive(Debug, Clone, Default)]
+pub struct DomFrontier {
+    /// The frontier of each block in the tree, in the reverse postorder.
+    frontiers: HashMap<Block, Vec<Block>>,
+}
+
+impl DomFrontier {
+    /// Compute the dominance frontiers from the CFG and its dominator tree.
+    pub fn new(cfg: &Cfg, dom: &DomTree) -> Self {
+        let mut frontiers: HashMap<Block, Vec<Block>> = HashMap::new();
+        for &block in cfg.reverse_postorder() {
+            frontiers.entry(block).or_default();
+            let preds = cfg.preds(block);
+            if preds.len() < 2 {
+                continue;
+            }
+            // Walk up from each predecessor until the immediate dominator of the
+            // join point, all the blocks on the way have it in their frontiers.
+            let idom = dom.idom(block);
+            for &pred in preds.iter().filter(|&&pred| dom.contains(pred)) {
+                let mut runner = Some(pred);
+                while runner != idom {
+                    let Some(curr) = runner else { break };
+                    let frontier = frontiers.entry(curr).or_default();
+                    if !frontier.contains(&block) {
+                        frontier.push(block);
+                    }
+                    runner = dom.idom(curr);
+                }
+            }
+        }
+        for frontier in frontiers.values_mut() {
+            frontier.sort_by_key(|&block| cfg.rpo_index(block));
+        }
+        Self { frontiers }
+    }
+
+    /// Get the dominance frontier of the block, empty if the block is not in
+    /// the tree.
+    pub fn frontier(&self, block: Block) -> &[Block] {
+        self.frontiers.get(&block).map_or(&[], Vec::as_slice)
+    }
+
+    /// Compute the iterated dominance frontier of the blocks, i.e., the limit
+    /// of `DF(S)`, `DF(S ∪ DF(S))`, and so on.
+    ///
+    /// For a variable defined in the blocks, this is where the phi nodes are
+    /// placed in the minimal SSA form. The result is in no particular order.
+    pub fn iterated(&self, blocks: impl IntoIterator<Item = Block>) -> HashSet<Block> {
+        let mut result = HashSet::new();
+        let mut worklist: Vec<Block> = blocks.into_iter().collect();
+        let mut visited: HashSet<Block> = worklist.iter().copied().collect();
+        while let Some(block) = worklist.pop() {
+            for &df in self.frontier(block) {
+                result.insert(df);
+                if visited.insert(df) {
+                    worklist.push(df);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Compute the immediate dominators with the Cooper-Harvey-Kennedy algorithm.
+///
+/// The nodes are numbered in reverse postorder, and node 0 is the root.
+/// `preds[i]` are the predecessors of node `i`. Returns the immediate
+/// dominator of each node, where the root is its own immediate dominator.
+fn compute_idoms(preds: &[Vec<usize>]) -> Vec<usize> {
+    let mut idoms: Vec<Option<usize>> = vec![None; preds.len()];
+    idoms[0] = Some(0);
+
+    // Walk up from two nodes to their nearest common dominator. Dominators have
+    // smaller numbers in reverse postorder.
+    let intersect = |idoms: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while a > b {
+                a = idoms[a].unwrap();
+            }
+            while b > a {
+                b = idoms[b].unwrap();
+            }
+        }
+        a
+    };
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for node in 1..preds.len() {
+            let mut new_idom = None;
+            for &pred in preds[node].iter() {
+                // Only the processed predecessors are considered.
+                if idoms[pred].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    None => pred,
+                    Some(other) => intersect(&idoms, pred, other),
+                });
+            }
+            if new_idom != idoms[node] {
+                idoms[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+
+    idoms.into_iter().map(Option::unwrap).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::linked_list::LinkedListContainer;
+    use crate::ir::{parse, Func};
+
+    fn blocks(ctx: &Context, func: Func) -> Vec<Block> { func.iter(ctx).collect() }
+
+    fn sorted(set: HashSet<Block>) -> Vec<Block> {
+        let mut blocks: Vec<Block> = set.into_iter().collect();
+        blocks.sort();
+        blocks
+    }
+
+    #[test]
+    fn test_dom_tree() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\t%v1 = add i32 1, 2
+\t%v2 = add i32 %v1, 3
+\tbr label %bb_3
+bb_2:
+\tbr label %bb_3
+bb_3:
+\tbr i1 %v0, label %bb_4, label %bb_5
+bb_4:
+\tbr label %bb_3
+bb_5:
+\tret i32 %v2
+}
+";
+        let ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let [bb0, bb1, bb2, bb3, bb4, bb5] = blocks(&ctx, func)[..] else {
+            panic!("expected 6 blocks");
+        };
+        let dom = func.dom_tree(&ctx);
+
+        assert!(!dom.is_post());
+        assert_eq!(dom.roots(), [bb0]);
+        assert_eq!(dom.idom(bb0), None);
+        assert_eq!(dom.idom(bb1), Some(bb0));
+        assert_eq!(dom.idom(bb2), Some(bb0));
+        assert_eq!(dom.idom(bb3), Some(bb0));
+        assert_eq!(dom.idom(bb4), Some(bb3));
+        assert_eq!(dom.idom(bb5), Some(bb3));
+        assert_eq!(dom.children(bb3), [bb5, bb4]);
+
+        assert!(dom.dominates(bb0, bb4));
+        assert!(dom.dominates(bb3, bb3));
+        assert!(!dom.strictly_dominates(bb3, bb3));
+        assert!(dom.strictly_dominates(bb3, bb4));
+        assert!(!dom.dominates(bb1, bb3));
+        assert!(!dom.dominates(bb4, bb3));
+        assert!(!dom.dominates(bb4, bb5));
+
+        let [i1, i2, br1] = bb1.iter(&ctx).collect::<Vec<_>>()[..] else {
+            panic!("expected 3 instructions");
+        };
+        let ret = bb5.terminator(&ctx).unwrap();
+        let br0 = bb0.terminator(&ctx).unwrap();
+        assert!(dom.dominates_inst(&ctx, i1, i1));
+        assert!(dom.dominates_inst(&ctx, i1, i2));
+        assert!(!dom.dominates_inst(&ctx, br1, i2));
+        assert!(dom.dominates_inst(&ctx, br0, ret));
+        // `%v2` is used in `bb_5` but does not dominate it, the IR is not verified.
+        assert!(!dom.dominates_inst(&ctx, i2, ret));
+
+        let df = DomFrontier::new(func.cfg(&ctx), dom);
+        assert!(df.frontier(bb0).is_empty());
+        assert_eq!(df.frontier(bb1), [bb3]);
+        assert_eq!(df.frontier(bb2), [bb3]);
+        assert_eq!(df.frontier(bb3), [bb3]);
+        assert_eq!(df.frontier(bb4), [bb3]);
+        assert!(df.frontier(bb5).is_empty());
+        assert_eq!(sorted(df.iterated([bb1])), [bb3]);
+        assert!(df.iterated([bb0, bb5]).is_empty());
+
+        let post_dom = func.post_dom_tree(&ctx);
+        assert!(post_dom.is_post());
+        assert_eq!(post_dom.roots(), [bb5]);
+        assert_eq!(post_dom.idom(bb5), None);
+        assert_eq!(post_dom.idom(bb3), Some(bb5));
+        assert_eq!(post_dom.idom(bb4), Some(bb3));
+        assert_eq!(post_dom.idom(bb1), Some(bb3));
+        assert_eq!(post_dom.idom(bb2), Some(bb3));
+        assert_eq!(post_dom.idom(bb0), Some(bb3));
+        assert!(post_dom.dominates(bb3, bb0));
+        assert!(!post_dom.dominates(bb1, bb0));
+        assert!(post_dom.dominates_inst(&ctx, i2, i1));
+        assert!(!post_dom.dominates_inst(&ctx, i1, i2));
+        assert!(post_dom.dominates_inst(&ctx, ret, br0));
+    }
+
+    #[test]
+    fn test_irreducible() {
+        // The loop of `bb_1` and `bb_2` has two entries.
+        let src = "\
+define void @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tbr i1 %v0, label %bb_2, label %bb_3
+bb_2:
+\tbr i1 %v0, label %bb_1, label %bb_3
+bb_3:
+\tret void
+}
+";
+        let ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let [bb0, bb1, bb2, bb3] = blocks(&ctx, func)[..] else {
+            panic!("expected 4 blocks");
+        };
+        let dom = func.dom_tree(&ctx);
+
+        for block in [bb1, bb2, bb3] {
+            assert_eq!(dom.idom(block), Some(bb0));
+        }
+        assert!(!dom.dominates(bb1, bb2));
+        assert!(!dom.dominates(bb2, bb1));
+
+        let df = DomFrontier::new(func.cfg(&ctx), dom);
+        assert_eq!(df.frontier(bb1).len(), 2);
+        assert!(df.frontier(bb1).contains(&bb2));
+        assert!(df.frontier(bb1).contains(&bb3));
+        assert_eq!(sorted(df.iterated([bb1])), sorted(HashSet::from([bb1, bb2, bb3])));
+
+        let post_dom = func.post_dom_tree(&ctx);
+        assert_eq!(post_dom.roots(), [bb3]);
+        for block in [bb0, bb1, bb2] {
+            assert_eq!(post_dom.idom(block), Some(bb3));
+        }
+    }
+
+    #[test]
+    fn test_unreachable_and_infinite() {
+        let src = "\
+define void @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tbr label %bb_1
+bb_3:
+\tbr label %bb_2
+bb_2:
+\tret void
+bb_4:
+\tret void
+}
+";
+        let ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let [bb0, bb1, bb3, bb2, bb4] = blocks(&ctx, func)[..] else {
+            panic!("expected 5 blocks");
+        };
+
+        let dom = func.dom_tree(&ctx);
+        assert!(!dom.contains(bb3));
+        assert!(!dom.contains(bb4));
+        assert!(!dom.dominates(bb0, bb3));
+        assert!(dom.dominates(bb0, bb1));
+        assert_eq!(dom.idom(bb3), None);
+
+        // The infinite loop never reaches an exit, and there are two exits.
+        let post_dom = func.post_dom_tree(&ctx);
+        assert_eq!(post_dom.roots(), [bb2, bb4]);
+        assert!(!post_dom.contains(bb1));
+        assert_eq!(post_dom.idom(bb0), Some(bb2));
+        assert_eq!(post_dom.idom(bb3), Some(bb2));
+        assert!(!post_dom.dominates(bb2, bb4));
+    }
+
+    #[test]
+    fn test_dom_tree_invalidated() {
+        let src = "\
+define void @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tbr label %bb_2
+bb_2:
+\tret void
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let [bb0, bb1, bb2] = blocks(&ctx, func)[..] else {
+            panic!("expected 3 blocks");
+        };
+        assert_eq!(func.dom_tree(&ctx).idom(bb2), Some(bb0));
+
+        // Make `bb_1` the only way to `bb_2`.
+        bb0.terminator(&ctx).unwrap().set_successor(&mut ctx, 1, bb1);
+        assert_eq!(func.dom_tree(&ctx).idom(bb2), Some(bb1));
+        assert_eq!(func.post_dom_tree(&ctx).idom(bb0), Some(bb1));
+    }
+}
@@ -0,0 +1,213 @@
+//! Natural loops of a function.
+//!
+//! A back edge is an edge from a latch to a header that dominates it, and the
+//! natural loop of the header consists of the blocks that can reach a latch
+//! without going through the header. Loops with the same header are merged,
+//! and the loops form a forest by containment.
+//!
+//! Cycles in irreducible control flow have no header dominating the other
+//! blocks, so they are not recognized as loops.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Cfg, DomTree};
+use crate::ir::Block;
+
+/// A loop in a [`LoopInfo`], identified by its index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Loop(usize);
+
+#[derive(Debug, Clone)]
+struct LoopData {
+    header: Block,
+    /// The sources of the back edges.
+    latches: Vec<Block>,
+    /// All the blocks in the loop, including the nested loops, in the reverse
+    /// postorder. The header is always the first.
+    blocks: Vec<Block>,
+    /// The innermost loop containing this loop.
+    parent: Option<Loop>,
+    /// The loops directly nested in this loop.
+    children: Vec<Loop>,
+    /// The nesting depth, 1 for the outermost loops.
+    depth: usize,
+}
+
+/// The natural loops of a function.
+#[derive(Debug, Clone, Default)]
+pub struct LoopInfo {
+    /// The loops, outer loops come before the loops nested in them.
+    loops: Vec<LoopData>,
+    /// The innermost loop containing each block.
+    innermost: HashMap<Block, Loop>,
+}
+
+impl LoopInfo {
+    /// Find the natural loops with the CFG and its dominator tree.
+    pub fn new(cfg: &Cfg, dom: &DomTree) -> Self {
+        let mut info = LoopInfo::default();
+
+        // The headers in the reverse postorder, so an outer loop is found before
+        // the loops nested in it.
+        for &header in cfg.reverse_postorder() {
+            let latches: Vec<Block> = cfg
+                .preds(header)
+                .iter()
+                .copied()
+                .filter(|&pred| dom.dominates(header, pred))
+                .collect();
+            if latches.is_empty() {
+                continue;
+            }
+
+            let mut body = HashSet::from([header]);
+            let mut worklist = latches.clone();
+            while let Some(block) = worklist.pop() {
+                if body.insert(block) {
+                    worklist.extend(cfg.preds(block).iter().filter(|&&p| cfg.is_reachable(p)));
+                }
+            }
+            let mut blocks: Vec<Block> = body.into_iter().collect();
+            blocks.sort_by_key(|&block| cfg.rpo_index(block));
+
+            // The header of the parent loop dominates this header, so the parent
+            // is already found and contains this header.
+            let parent = info.innermost.get(&header).copied();
+            let depth = parent.map_or(1, |parent| info.loops[parent.0].depth + 1);
+            let lp = Loop(info.loops.len());
+            if let Some(parent) = parent {
+                info.loops[parent.0].children.push(lp);
+            }
+            for &block in blocks.iter() {
+                info.innermost.insert(block, lp);
+            }
+            info.loops.push(LoopData {
+                header,
+                latches,
+                blocks,
+                parent,
+                children: Vec::new(),
+                depth,
+            });
+        }
+
+        info
+    }
+
+    /// Iterate over all the loops, outer loops come before the nested ones.
+    pub fn loops(&self) -> impl Iterator<Item = Loop> + '_ { (0..self.loops.len()).map(Loop) }
+
+    /// Iterate over the outermost loops.
+    pub fn top_level(&self) -> impl Iterator<Item = Loop> + '_ {
+        self.loops().filter(|&lp| self.parent(lp).is_none())
+    }
+
+    /// Get the header of the loop.
+    pub fn header(&self, lp: Loop) -> Block { self.loops[lp.0].header }
+
+    /// Get the latches of the loop, i.e., the blocks branching back to the
+    /// header.
+    pub fn latches(&self, lp: Loop) -> &[Block] { &self.loops[lp.0].latches }
+
+    /// Get all the blocks in the loop in the reverse postorder, starting with
+    /// the header.
+    pub fn blocks(&self, lp: Loop) -> &[Block] { &self.loops[lp.0].blocks }
+
+    /// Get the innermost loop containing the loop.
+    pub fn parent(&self, lp: Loop) -> Option<Loop> { self.loops[lp.0].parent }
+
+    /// Get the loops directly nested in the loop.
+    pub fn children(&self, lp: Loop) -> &[Loop] { &self.loops[lp.0].children }
+
+    /// Get the nesting depth of the loop, 1 for the outermost loops.
+    pub fn depth(&self, lp: Loop) -> usize { self.loops[lp.0].depth }
+
+    /// Check if the block is in the loop, including the nested loops.
+    pub fn contains(&self, lp: Loop, block: Block) -> bool {
+        let mut curr = self.loop_of(block);
+        while let Some(inner) = curr {
+            if inner == lp {
+                return true;
+            }
+            curr = self.parent(inner);
+        }
+        false
+    }
+
+    /// Get the innermost loop containing the block, if any.
+    pub fn loop_of(&self, block: Block) -> Option<Loop> { self.innermost.get(&block).copied() }
+
+    /// Get the nesting depth of the block, 0 if it is not in any loop.
+    pub fn loop_depth(&self, block: Block) -> usize {
+        self.loop_of(block).map_or(0, |lp| self.depth(lp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::linked_list::LinkedListContainer;
+    use crate::ir::parse;
+
+    #[test]
+    fn test_loops() {
+        // An outer loop of `bb_1`, with an inner loop of `bb_2` and `bb_3`
+        // which has two latches, and an irreducible cycle of `bb_6` and `bb_7`.
+        let src = "\
+define void @f(i1 %v0) {
+bb_0:
+\tbr label %bb_1
+bb_1:
+\tbr i1 %v0, label %bb_2, label %bb_5
+bb_2:
+\tbr i1 %v0, label %bb_3, label %bb_2
+bb_3:
+\tbr i1 %v0, label %bb_2, label %bb_4
+bb_4:
+\tbr label %bb_1
+bb_5:
+\tbr i1 %v0, label %bb_6, label %bb_7
+bb_6:
+\tbr label %bb_7
+bb_7:
+\tbr i1 %v0, label %bb_6, label %bb_8
+bb_8:
+\tret void
+}
+";
+        let ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let blocks: Vec<Block> = func.iter(&ctx).collect();
+        let [bb0, bb1, bb2, bb3, bb4, bb5, bb6, bb7, bb8] = blocks[..] else {
+            panic!("expected 9 blocks");
+        };
+        let info = func.loops(&ctx);
+
+        assert_eq!(info.loops().count(), 2);
+        let outer = info.loop_of(bb1).unwrap();
+        let inner = info.loop_of(bb2).unwrap();
+        assert_eq!(info.top_level().collect::<Vec<_>>(), [outer]);
+        assert_eq!(info.header(outer), bb1);
+        assert_eq!(info.latches(outer), [bb4]);
+        assert_eq!(info.blocks(outer).len(), 4);
+        assert_eq!(info.blocks(outer)[0], bb1);
+        assert_eq!(info.children(outer), [inner]);
+        assert_eq!(info.depth(outer), 1);
+
+        assert_eq!(info.header(inner), bb2);
+        assert_eq!(info.latches(inner), [bb2, bb3]);
+        assert_eq!(info.blocks(inner), [bb2, bb3]);
+        assert_eq!(info.parent(inner), Some(outer));
+        assert_eq!(info.depth(inner), 2);
+        assert_eq!(info.loop_of(bb3), Some(inner));
+        assert_eq!(info.loop_of(bb4), Some(outer));
+
+        assert!(info.contains(outer, bb3));
+        assert!(!info.contains(inner, bb4));
+        assert_eq!(info.loop_depth(bb0), 0);
+        assert_eq!(info.loop_depth(bb3), 2);
+        for block in [bb5, bb6, bb7, bb8] {
+            assert_eq!(info.loop_of(block), None);
+        }
+    }
+}
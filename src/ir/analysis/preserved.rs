@@ -0,0 +1,120 @@
+//! The analyses preserved by a pass.
+
+use std::collections::HashSet;
+
+/// The kinds of analyses cached in a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnalysisKind {
+    /// The control flow graph, see [`Cfg`](super::Cfg).
+    Cfg,
+    /// The dominator tree, see [`DomTree`](super::DomTree).
+    DomTree,
+    /// The post-dominator tree, see [`DomTree`](super::DomTree).
+    PostDomTree,
+    /// The natural loops, see [`LoopInfo`](super::LoopInfo).
+    Loops,
+}
+
+impl AnalysisKind {
+    /// Get the analyses this analysis is computed from, which invalidate it
+    /// if they are not preserved.
+    pub fn dependencies(self) -> &'static [AnalysisKind] {
+        match self {
+            AnalysisKind::Cfg => &[],
+            AnalysisKind::DomTree | AnalysisKind::PostDomTree => &[AnalysisKind::Cfg],
+            AnalysisKind::Loops => &[AnalysisKind::Cfg, AnalysisKind::DomTree],
+        }
+    }
+}
+
+/// The set of analyses that are still valid after a pass.
+///
+/// An analysis is only preserved if all its dependencies are preserved as
+/// well, e.g., preserving the dominator tree without the CFG preserves nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreservedAnalyses {
+    /// Whether all the analyses are preserved, i.e., the pass changed nothing
+    /// or only made changes that no analysis depends on.
+    all: bool,
+    preserved: HashSet<AnalysisKind>,
+}
+
+impl PreservedAnalyses {
+    /// All the analyses are preserved.
+    pub fn all() -> Self {
+        Self {
+            all: true,
+            preserved: HashSet::new(),
+        }
+    }
+
+    /// No analysis is preserved.
+    pub fn none() -> Self { Self::default() }
+
+    /// The analyses computed from the CFG are preserved, i.e., the pass changes
+    /// instructions but not the blocks or the successors of the terminators.
+    pub fn cfg_analyses() -> Self {
+        Self::none()
+            .preserve(AnalysisKind::Cfg)
+            .preserve(AnalysisKind::DomTree)
+            .preserve(AnalysisKind::PostDomTree)
+            .preserve(AnalysisKind::Loops)
+    }
+
+    /// Mark the analysis as preserved.
+    pub fn preserve(mut self, kind: AnalysisKind) -> Self {
+        self.preserved.insert(kind);
+        self
+    }
+
+    /// Check if all the analyses are preserved.
+    pub fn preserves_all(&self) -> bool { self.all }
+
+    /// Check if the analysis is preserved, together with its dependencies.
+    pub fn is_preserved(&self, kind: AnalysisKind) -> bool {
+        self.all
+            || (self.preserved.contains(&kind)
+                && kind
+                    .dependencies()
+                    .iter()
+                    .all(|&dep| self.is_preserved(dep)))
+    }
+
+    /// Keep only the analyses preserved by both, e.g., after running two
+    /// passes in a row.
+    pub fn intersect(&mut self, other: &PreservedAnalyses) {
+        match (self.all, other.all) {
+            (_, true) => {}
+            (true, false) => *self = other.clone(),
+            (false, false) => self.preserved.retain(|kind| other.preserved.contains(kind)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preserved_analyses() {
+        let all = PreservedAnalyses::all();
+        assert!(all.preserves_all());
+        assert!(all.is_preserved(AnalysisKind::Loops));
+        assert!(!PreservedAnalyses::none().is_preserved(AnalysisKind::Cfg));
+
+        // The dominator tree is invalid without the CFG.
+        let dom = PreservedAnalyses::none().preserve(AnalysisKind::DomTree);
+        assert!(!dom.is_preserved(AnalysisKind::DomTree));
+        let dom = dom.preserve(AnalysisKind::Cfg);
+        assert!(dom.is_preserved(AnalysisKind::DomTree));
+        assert!(!dom.is_preserved(AnalysisKind::Loops));
+
+        let mut preserved = PreservedAnalyses::all();
+        preserved.intersect(&PreservedAnalyses::cfg_analyses());
+        assert!(!preserved.preserves_all());
+        assert!(preserved.is_preserved(AnalysisKind::Loops));
+        preserved.intersect(&dom);
+        assert!(preserved.is_preserved(AnalysisKind::DomTree));
+        assert!(!preserved.is_preserved(AnalysisKind::PostDomTree));
+    }
+}
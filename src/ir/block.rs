@@ -54,7 +54,62 @@ impl Block {
         format!("%bb_{}", self.0.index())
     }
 
-    pub fn display(self, ctx: &Context) -> DisplayBlock { DisplayBlock { ctx, block: self } }
+    pub fn display(self, ctx: &Context) -> DisplayBlock<'_> { DisplayBlock { ctx, block: self } }
+
+    /// Get the terminator of the block, if any.
+    pub fn terminator(self, ctx: &Context) -> Option<Inst> {
+        self.tail(ctx).filter(|inst| inst.is_terminator(ctx))
+    }
+
+    /// Erase the block, i.e., unlink it from its function and deallocate it
+    /// together with all its instructions.
+    ///
+    /// The incoming values from this block are removed from the phi nodes in
+    /// its successors.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the block is still used, i.e., branched to from other
+    ///   blocks.
+    /// - Panics if the result of any instruction in the block is still used by
+    ///   other blocks.
+    pub fn erase(self, ctx: &mut Context) {
+        // Branches in the block itself (i.e., a self loop) are erased together.
+        assert!(
+            self.users(ctx)
+                .into_iter()
+                .all(|user| user.inst().container(ctx) == Some(self)),
+            "erasing a block that is still used"
+        );
+
+        let mut succs: Vec<Block> = match self.terminator(ctx) {
+            Some(terminator) => terminator.successor_iter(ctx).collect(),
+            None => Vec::new(),
+        };
+        succs.sort();
+        succs.dedup();
+        for succ in succs.into_iter().filter(|&succ| succ != self) {
+            let phis: Vec<Inst> = succ.iter(ctx).take_while(|inst| inst.is_phi(ctx)).collect();
+            for phi in phis {
+                if phi.incoming_iter(ctx).any(|(block, _)| block == self) {
+                    phi.remove_incoming(ctx, self);
+                }
+            }
+        }
+
+        // Drop all the operands first, so that the instructions in the block can use
+        // each other in any order.
+        let insts: Vec<Inst> = self.iter(ctx).collect();
+        for &inst in insts.iter() {
+            inst.drop_operands(ctx);
+        }
+        for inst in insts {
+            inst.erase(ctx);
+        }
+
+        self.unlink(ctx);
+        ctx.try_dealloc(self).unwrap();
+    }
 }
 
 impl fmt::Display for DisplayBlock<'_> {
@@ -136,6 +191,10 @@ impl LinkedListNode for Block {
     }
 
     fn set_container(self, ctx: &mut Self::Ctx, container: Option<Self::Container>) {
+        // The block is added to or removed from a function
+        for func in [self.container(ctx), container].into_iter().flatten() {
+            func.invalidate_cfg(ctx);
+        }
         self.try_deref_mut(ctx).expect("invalid pointer").container = container;
     }
 }
@@ -153,3 +212,71 @@ impl Usable for Block {
         self.try_deref_mut(arena).unwrap().users.remove(&user);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{parse, verify};
+
+    #[test]
+    fn test_erase_block() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\t%v1 = add i32 1, 2
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_2:
+\t%v2 = phi i32 [0, %bb_0], [%v1, %bb_1]
+\tret i32 %v2
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let blocks: Vec<_> = func.iter(&ctx).collect();
+        let [bb0, bb1, bb2] = blocks[..] else {
+            panic!("expected 3 blocks");
+        };
+
+        // Branch to `bb_2` directly, so that `bb_1` is only used by itself.
+        let cond_br = bb0.terminator(&ctx).unwrap();
+        let br = Inst::br(&mut ctx, bb2);
+        cond_br.insert_after(&mut ctx, br).unwrap();
+        cond_br.erase(&mut ctx);
+
+        // The phi in `bb_2` no longer has an incoming value from `bb_1`.
+        bb1.erase(&mut ctx);
+        assert!(verify(&ctx).is_ok());
+        assert_eq!(
+            func.display(&ctx).to_string(),
+            "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr label %bb_2
+bb_2:
+\t%v1 = phi i32 [0, %bb_0]
+\tret i32 %v1
+}"
+        );
+        let cond = func.params(&ctx)[0];
+        assert_eq!(cond.users(&ctx).into_iter().count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "erasing a block that is still used")]
+    fn test_erase_used_block() {
+        let src = "\
+define void @f() {
+bb_0:
+\tbr label %bb_1
+bb_1:
+\tret void
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let bb1 = func.tail(&ctx).unwrap();
+        bb1.erase(&mut ctx);
+    }
+}
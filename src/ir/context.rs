@@ -6,7 +6,7 @@ use super::global::GlobalData;
 use super::inst::InstData;
 use super::ty::TyData;
 use super::value::ValueData;
-use super::Func;
+use super::{Func, Global};
 use crate::infra::storage::{GenericArena, UniqueArena};
 
 pub struct TargetInfo {
@@ -54,21 +54,151 @@ impl Context {
     pub fn funcs(&self) -> impl Iterator<Item = Func> + '_ {
         self.funcs.iter().map(|data| data.self_ptr)
     }
+
+    pub fn globals(&self) -> impl Iterator<Item = Global> + '_ {
+        self.globals.iter().map(|data| data.self_ptr)
+    }
 }
 
 impl fmt::Display for Context {
+    /// Print the globals, then the functions, in the order they are created.
+    ///
+    /// The groups of globals and declarations are separated from the function
+    /// definitions by empty lines.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = false;
         for GlobalData {
             self_ptr: global, ..
         } in self.globals.iter()
         {
             writeln!(f, "{}", global.display(self))?;
+            sep = true;
         }
 
+        let mut last_declaration = false;
         for FuncData { self_ptr: func, .. } in self.funcs.iter() {
+            let declaration = func.is_declaration(self);
+            if sep && !(declaration && last_declaration) {
+                writeln!(f)?;
+            }
             writeln!(f, "{}", func.display(self))?;
+            sep = true;
+            last_declaration = declaration;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::linked_list::LinkedListContainer;
+    use crate::ir::{Block, CastOp, ConstantValue, Inst, IntCmpCond, Ty, Value};
+
+    #[test]
+    fn test_display_context() {
+        let mut ctx = Context::default();
+        let i8 = Ty::i8(&mut ctx);
+        let i32 = Ty::i32(&mut ctx);
+        let f32 = Ty::f32(&mut ctx);
+        let ptr = Ty::ptr(&mut ctx);
+        let arr = Ty::array(&mut ctx, i32, 2);
+        let zero_arr = Ty::array(&mut ctx, f32, 4);
+
+        let zero = ConstantValue::i32(&mut ctx, 0);
+        Global::new(&mut ctx, "g".to_string(), zero);
+        let elems = vec![ConstantValue::i32(&mut ctx, 1), ConstantValue::i32(&mut ctx, 2)];
+        let init = ConstantValue::array(&mut ctx, i32, elems);
+        Global::new(&mut ctx, "a".to_string(), init);
+        let init = ConstantValue::AggregateZero { ty: zero_arr };
+        Global::new(&mut ctx, "z".to_string(), init);
+        let elems = vec![ConstantValue::i8(&mut ctx, 104), ConstantValue::i8(&mut ctx, 0)];
+        let init = ConstantValue::array(&mut ctx, i8, elems);
+        Global::new_private_constant(&mut ctx, "s".to_string(), init);
+
+        Func::declare(&mut ctx, "getint".to_string(), i32, false);
+        let void = Ty::void(&mut ctx);
+        let putf = Func::declare(&mut ctx, "putf".to_string(), void, true);
+        putf.add_param(&mut ctx, ptr);
+
+        let main = Func::new(&mut ctx, "main".to_string(), i32);
+        let n = main.add_param(&mut ctx, i32);
+        main.add_param(&mut ctx, ptr);
+        let bb0 = Block::new(&mut ctx);
+        let bb1 = Block::new(&mut ctx);
+        let bb2 = Block::new(&mut ctx);
+        for block in [bb0, bb1, bb2] {
+            main.push_back(&mut ctx, block).unwrap();
+        }
+
+        let slot = Inst::alloca(&mut ctx, i32);
+        let slot_val = slot.result(&ctx).unwrap();
+        let store = Inst::store(&mut ctx, n, slot_val);
+        let load = Inst::load(&mut ctx, slot_val, i32);
+        let loaded = load.result(&ctx).unwrap();
+        let a = Value::global_ref(&mut ctx, "a".to_string(), arr);
+        let idx0 = Value::i32(&mut ctx, 0);
+        let idx1 = Value::i32(&mut ctx, 1);
+        let gep = Inst::getelementptr(&mut ctx, arr, a, vec![idx0, idx1]);
+        let ten = Value::i32(&mut ctx, 10);
+        let cmp = Inst::icmp(&mut ctx, IntCmpCond::Slt, loaded, ten);
+        let cond = cmp.result(&ctx).unwrap();
+        let br = Inst::cond_br(&mut ctx, cond, bb1, bb2);
+        for inst in [slot, store, load, gep, cmp, br] {
+            bb0.push_back(&mut ctx, inst).unwrap();
+        }
+
+        let one = Value::i32(&mut ctx, 1);
+        let add = Inst::add(&mut ctx, loaded, one, i32);
+        let added = add.result(&ctx).unwrap();
+        let br = Inst::br(&mut ctx, bb2);
+        bb1.push_back(&mut ctx, add).unwrap();
+        bb1.push_back(&mut ctx, br).unwrap();
+
+        let phi = Inst::phi(&mut ctx, i32);
+        phi.insert_incoming(&mut ctx, bb0, loaded);
+        phi.insert_incoming(&mut ctx, bb1, added);
+        let phi_val = phi.result(&ctx).unwrap();
+        let sitofp = Inst::cast(&mut ctx, CastOp::Sitofp, phi_val, f32);
+        let half = Value::f32(&mut ctx, 0.5);
+        let float = sitofp.result(&ctx).unwrap();
+        let fmul = Inst::fmul(&mut ctx, float, half);
+        let product = fmul.result(&ctx).unwrap();
+        let fneg = Inst::fneg(&mut ctx, product);
+        let ret = Inst::ret(&mut ctx, Some(phi_val));
+        for inst in [phi, sitofp, fmul, fneg, ret] {
+            bb2.push_back(&mut ctx, inst).unwrap();
+        }
+
+        let expected = "\
+@g = global i32 0
+@a = global [2 x i32] [i32 1, i32 2]
+@z = global [4 x f32] zeroinitializer
+@s = private constant [2 x i8] [i8 104, i8 0]
+
+declare i32 @getint()
+declare void @putf(ptr, ...)
+
+define i32 @main(i32 %v1, ptr %v2) {
+bb_0:
+\t%v3 = alloca i32
+\tstore i32 %v1, ptr %v3
+\t%v4 = load i32, ptr %v3
+\t%v8 = getelementptr [2 x i32], ptr @a, i32 0, i32 1
+\t%v10 = icmp slt i32 %v4, 10
+\tbr i1 %v10, label %bb_1, label %bb_2
+bb_1:
+\t%v12 = add i32 %v4, 1
+\tbr label %bb_2
+bb_2:
+\t%v13 = phi i32 [%v4, %bb_0], [%v12, %bb_1]
+\t%v14 = sitofp i32 %v13 to f32
+\t%v16 = fmul f32 %v14, 0.5
+\t%v17 = fneg f32 %v16
+\tret i32 %v13
+}
+";
+        assert_eq!(ctx.to_string(), expected);
+    }
+}
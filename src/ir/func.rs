@@ -1,5 +1,7 @@
+use std::cell::OnceCell;
 use std::fmt;
 
+use super::analysis::{AnalysisKind, Cfg, DomTree, LoopInfo, PreservedAnalyses};
 use super::block::Block;
 use super::context::Context;
 use super::ty::Ty;
@@ -12,10 +14,22 @@ pub struct FuncData {
     name: String,
     params: Vec<Value>,
     ret_ty: Ty,
+    /// Whether the function takes variadic arguments after the params.
+    variadic: bool,
+    /// Whether the function is only declared, i.e., defined externally.
+    declaration: bool,
 
     head: Option<Block>,
     tail: Option<Block>,
-    // TODO: Distinguish `define` and `declare`.
+
+    /// The cached control flow graph, cleared when the CFG is mutated.
+    cfg: OnceCell<Cfg>,
+    /// The cached dominator tree, cleared with the CFG.
+    dom_tree: OnceCell<DomTree>,
+    /// The cached post-dominator tree, cleared with the CFG.
+    post_dom_tree: OnceCell<DomTree>,
+    /// The cached natural loops, cleared with the CFG.
+    loops: OnceCell<LoopInfo>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -33,8 +47,32 @@ impl Func {
             name,
             params: Vec::new(),
             ret_ty,
+            variadic: false,
+            declaration: false,
+            head: None,
+            tail: None,
+            cfg: OnceCell::new(),
+            dom_tree: OnceCell::new(),
+            post_dom_tree: OnceCell::new(),
+            loops: OnceCell::new(),
+        })
+    }
+
+    /// Create an external function declaration, which has no blocks.
+    pub fn declare(ctx: &mut Context, name: String, ret_ty: Ty, variadic: bool) -> Self {
+        ctx.alloc_with(|self_ptr| FuncData {
+            self_ptr,
+            name,
+            params: Vec::new(),
+            ret_ty,
+            variadic,
+            declaration: true,
             head: None,
             tail: None,
+            cfg: OnceCell::new(),
+            dom_tree: OnceCell::new(),
+            post_dom_tree: OnceCell::new(),
+            loops: OnceCell::new(),
         })
     }
 
@@ -51,23 +89,99 @@ impl Func {
 
     pub fn ret_ty(self, ctx: &Context) -> Ty { self.deref(ctx).ret_ty }
 
-    pub fn display(self, ctx: &Context) -> DisplayFunc { DisplayFunc { ctx, func: self } }
+    pub fn is_variadic(self, ctx: &Context) -> bool { self.deref(ctx).variadic }
+
+    pub fn is_declaration(self, ctx: &Context) -> bool { self.deref(ctx).declaration }
+
+    pub fn display(self, ctx: &Context) -> DisplayFunc<'_> { DisplayFunc { ctx, func: self } }
+
+    /// Get the control flow graph of the function, computed on the first call
+    /// after the function is mutated.
+    pub fn cfg(self, ctx: &Context) -> &Cfg {
+        self.deref(ctx).cfg.get_or_init(|| Cfg::new(ctx, self))
+    }
+
+    /// Get the dominator tree of the function, computed on the first call after
+    /// the CFG is mutated.
+    pub fn dom_tree(self, ctx: &Context) -> &DomTree {
+        self.deref(ctx)
+            .dom_tree
+            .get_or_init(|| DomTree::new(self.cfg(ctx)))
+    }
+
+    /// Get the post-dominator tree of the function, computed on the first call
+    /// after the CFG is mutated.
+    pub fn post_dom_tree(self, ctx: &Context) -> &DomTree {
+        self.deref(ctx)
+            .post_dom_tree
+            .get_or_init(|| DomTree::new_post(self.cfg(ctx)))
+    }
+
+    /// Get the natural loops of the function, computed on the first call after
+    /// the CFG is mutated.
+    pub fn loops(self, ctx: &Context) -> &LoopInfo {
+        self.deref(ctx)
+            .loops
+            .get_or_init(|| LoopInfo::new(self.cfg(ctx), self.dom_tree(ctx)))
+    }
+
+    /// Invalidate the cached analyses after the CFG is mutated, e.g., blocks
+    /// are added or removed, or the successors of a terminator are changed.
+    pub(super) fn invalidate_cfg(self, ctx: &mut Context) {
+        self.invalidate_analyses(ctx, &PreservedAnalyses::none());
+    }
+
+    /// Invalidate the cached analyses that are not preserved, e.g., after a
+    /// pass.
+    pub(super) fn invalidate_analyses(self, ctx: &mut Context, preserved: &PreservedAnalyses) {
+        let data = self.deref_mut(ctx);
+        if !preserved.is_preserved(AnalysisKind::Cfg) {
+            data.cfg.take();
+        }
+        if !preserved.is_preserved(AnalysisKind::DomTree) {
+            data.dom_tree.take();
+        }
+        if !preserved.is_preserved(AnalysisKind::PostDomTree) {
+            data.post_dom_tree.take();
+        }
+        if !preserved.is_preserved(AnalysisKind::Loops) {
+            data.loops.take();
+        }
+    }
 }
 
 impl fmt::Display for DisplayFunc<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let declaration = self.func.is_declaration(self.ctx);
         write!(
             f,
-            "define {} @{}(",
+            "{} {} @{}(",
+            if declaration { "declare" } else { "define" },
             self.func.ret_ty(self.ctx).display(self.ctx),
             self.func.name(self.ctx)
         )?;
 
+        // Params are named in definitions, so that they can be used in the body.
         for (i, param) in self.func.params(self.ctx).iter().enumerate() {
             if i != 0 {
                 write!(f, ", ")?;
             }
-            write!(f, "{}", param.ty(self.ctx).display(self.ctx))?;
+            if declaration {
+                write!(f, "{}", param.ty(self.ctx).display(self.ctx))?;
+            } else {
+                write!(f, "{}", param.display(self.ctx, true))?;
+            }
+        }
+
+        if self.func.is_variadic(self.ctx) {
+            if !self.func.params(self.ctx).is_empty() {
+                write!(f, ", ")?;
+            }
+            write!(f, "...")?;
+        }
+
+        if declaration {
+            return write!(f, ")");
         }
 
         write!(f, ") {{")?;
@@ -112,6 +226,8 @@ impl LinkedListContainer<Block> for Func {
     fn tail(self, ctx: &Self::Ctx) -> Option<Block> { self.try_deref(ctx).unwrap().tail }
 
     fn set_head(self, ctx: &mut Self::Ctx, head: Option<Block>) {
+        // The entry block may be changed
+        self.invalidate_cfg(ctx);
         self.try_deref_mut(ctx).unwrap().head = head;
     }
 
@@ -7,6 +7,10 @@ pub struct GlobalData {
     pub(super) self_ptr: Global,
     name: String,
     value: ConstantValue,
+    /// Whether the global is only visible in the current module.
+    private: bool,
+    /// Whether the global is read-only.
+    constant: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -18,6 +22,19 @@ impl Global {
             self_ptr,
             name,
             value,
+            private: false,
+            constant: false,
+        })
+    }
+
+    /// Create a private read-only global, e.g., for string literals.
+    pub fn new_private_constant(ctx: &mut Context, name: String, value: ConstantValue) -> Self {
+        ctx.alloc_with(|self_ptr| GlobalData {
+            self_ptr,
+            name,
+            value,
+            private: true,
+            constant: true,
         })
     }
 
@@ -26,6 +43,10 @@ impl Global {
     pub fn value(self, ctx: &Context) -> &ConstantValue { &self.deref(ctx).value }
 
     pub fn ty(self, ctx: &Context) -> Ty { self.value(ctx).ty() }
+
+    pub fn is_private(self, ctx: &Context) -> bool { self.deref(ctx).private }
+
+    pub fn is_constant(self, ctx: &Context) -> bool { self.deref(ctx).constant }
 }
 
 pub struct DisplayGlobal<'ctx> {
@@ -34,15 +55,17 @@ pub struct DisplayGlobal<'ctx> {
 }
 
 impl Global {
-    pub fn display(self, ctx: &Context) -> DisplayGlobal { DisplayGlobal { ctx, global: self } }
+    pub fn display(self, ctx: &Context) -> DisplayGlobal<'_> { DisplayGlobal { ctx, global: self } }
 }
 
 impl fmt::Display for DisplayGlobal<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "@{} = global {}",
+            "@{} = {}{} {}",
             self.global.name(self.ctx),
+            if self.global.is_private(self.ctx) { "private " } else { "" },
+            if self.global.is_constant(self.ctx) { "constant" } else { "global" },
             self.global.value(self.ctx).to_string(self.ctx, true)
         )
     }
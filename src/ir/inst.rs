@@ -7,14 +7,46 @@ use super::def_use::{Operand, Usable};
 use super::ty::Ty;
 use super::value::Value;
 use crate::infra::linked_list::LinkedListNode;
+use crate::infra::span::Span;
 use crate::infra::storage::{Arena, ArenaPtr, GenericPtr};
 
-#[derive(Debug)]
+/// Conditions of integer comparisons.
+///
+/// The `S` prefix means signed comparison, and `U` means unsigned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IntCmpCond {
     Eq,
     Ne,
     Slt,
     Sle,
+    Sgt,
+    Sge,
+    Ult,
+    Ule,
+    Ugt,
+    Uge,
+}
+
+impl IntCmpCond {
+    /// Evaluate the comparison on constant operands.
+    ///
+    /// Narrower integers should be sign-extended to `i32`, which keeps the
+    /// results of both signed and unsigned comparisons.
+    pub fn eval(self, lhs: i32, rhs: i32) -> bool {
+        let (ulhs, urhs) = (lhs as u32, rhs as u32);
+        match self {
+            IntCmpCond::Eq => lhs == rhs,
+            IntCmpCond::Ne => lhs != rhs,
+            IntCmpCond::Slt => lhs < rhs,
+            IntCmpCond::Sle => lhs <= rhs,
+            IntCmpCond::Sgt => lhs > rhs,
+            IntCmpCond::Sge => lhs >= rhs,
+            IntCmpCond::Ult => ulhs < urhs,
+            IntCmpCond::Ule => ulhs <= urhs,
+            IntCmpCond::Ugt => ulhs > urhs,
+            IntCmpCond::Uge => ulhs >= urhs,
+        }
+    }
 }
 
 impl fmt::Display for IntCmpCond {
@@ -24,6 +56,12 @@ impl fmt::Display for IntCmpCond {
             IntCmpCond::Ne => write!(f, "ne"),
             IntCmpCond::Slt => write!(f, "slt"),
             IntCmpCond::Sle => write!(f, "sle"),
+            IntCmpCond::Sgt => write!(f, "sgt"),
+            IntCmpCond::Sge => write!(f, "sge"),
+            IntCmpCond::Ult => write!(f, "ult"),
+            IntCmpCond::Ule => write!(f, "ule"),
+            IntCmpCond::Ugt => write!(f, "ugt"),
+            IntCmpCond::Uge => write!(f, "uge"),
         }
     }
 }
@@ -67,11 +105,132 @@ impl fmt::Display for IntBinaryOp {
     }
 }
 
+impl IntBinaryOp {
+    /// Evaluate the operation on constant `i32` operands.
+    ///
+    /// Arithmetic wraps around on overflow. Returns `None` if the result is
+    /// undefined, i.e., division by zero, `i32::MIN / -1`, or shifting by
+    /// at least 32 bits. Comparisons evaluate to 0 or 1.
+    pub fn eval(&self, lhs: i32, rhs: i32) -> Option<i32> {
+        let (ulhs, urhs) = (lhs as u32, rhs as u32);
+        match self {
+            IntBinaryOp::Add => Some(lhs.wrapping_add(rhs)),
+            IntBinaryOp::Sub => Some(lhs.wrapping_sub(rhs)),
+            IntBinaryOp::Mul => Some(lhs.wrapping_mul(rhs)),
+            IntBinaryOp::SDiv => lhs.checked_div(rhs),
+            IntBinaryOp::UDiv => ulhs.checked_div(urhs).map(|v| v as i32),
+            IntBinaryOp::SRem => lhs.checked_rem(rhs),
+            IntBinaryOp::URem => ulhs.checked_rem(urhs).map(|v| v as i32),
+            IntBinaryOp::Shl => lhs.checked_shl(urhs),
+            IntBinaryOp::LShr => ulhs.checked_shr(urhs).map(|v| v as i32),
+            IntBinaryOp::AShr => lhs.checked_shr(urhs),
+            IntBinaryOp::And => Some(lhs & rhs),
+            IntBinaryOp::Or => Some(lhs | rhs),
+            IntBinaryOp::Xor => Some(lhs ^ rhs),
+            IntBinaryOp::ICmp { cond } => Some(cond.eval(lhs, rhs) as i32),
+        }
+    }
+}
+
+/// Conditions of float comparisons.
+///
+/// Ordered conditions (`o*`) are false if any of the operands is NaN, and the
+/// unordered `une` is true in that case. The comparison operators in C are
+/// ordered, except for `!=`, which is `une`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatCmpCond {
+    Oeq,
+    One,
+    Olt,
+    Ole,
+    Ogt,
+    Oge,
+    /// Unordered or not equal.
+    Une,
+}
+
+impl FloatCmpCond {
+    /// Evaluate the comparison on constant operands.
+    pub fn eval(self, lhs: f32, rhs: f32) -> bool {
+        let ordered = !lhs.is_nan() && !rhs.is_nan();
+        match self {
+            FloatCmpCond::Oeq => ordered && lhs == rhs,
+            FloatCmpCond::One => ordered && lhs != rhs,
+            FloatCmpCond::Olt => ordered && lhs < rhs,
+            FloatCmpCond::Ole => ordered && lhs <= rhs,
+            FloatCmpCond::Ogt => ordered && lhs > rhs,
+            FloatCmpCond::Oge => ordered && lhs >= rhs,
+            FloatCmpCond::Une => !ordered || lhs != rhs,
+        }
+    }
+}
+
+impl fmt::Display for FloatCmpCond {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FloatCmpCond::Oeq => write!(f, "oeq"),
+            FloatCmpCond::One => write!(f, "one"),
+            FloatCmpCond::Olt => write!(f, "olt"),
+            FloatCmpCond::Ole => write!(f, "ole"),
+            FloatCmpCond::Ogt => write!(f, "ogt"),
+            FloatCmpCond::Oge => write!(f, "oge"),
+            FloatCmpCond::Une => write!(f, "une"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FloatBinaryOp {
+    Fadd,
+    Fsub,
+    Fmul,
+    Fdiv,
+    FCmp { cond: FloatCmpCond },
+}
+
+impl fmt::Display for FloatBinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FloatBinaryOp::Fadd => write!(f, "fadd"),
+            FloatBinaryOp::Fsub => write!(f, "fsub"),
+            FloatBinaryOp::Fmul => write!(f, "fmul"),
+            FloatBinaryOp::Fdiv => write!(f, "fdiv"),
+            FloatBinaryOp::FCmp { cond } => write!(f, "fcmp {}", cond),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum CastOp {
     Zext,
     Sext,
     Trunc,
+    /// Signed integer to float.
+    Sitofp,
+    /// Float to signed integer, rounding towards zero.
+    Fptosi,
+    /// `f32` to `f64`.
+    Fpext,
+    /// Reinterpret the bits as another type of the same bit width.
+    Bitcast,
+}
+
+impl CastOp {
+    /// Check if the cast from type `from` to type `to` is valid.
+    pub fn is_valid(&self, ctx: &Context, from: Ty, to: Ty) -> bool {
+        match self {
+            CastOp::Zext | CastOp::Sext => {
+                from.is_int(ctx) && to.is_int(ctx) && from.bitwidth(ctx) < to.bitwidth(ctx)
+            }
+            CastOp::Trunc => {
+                from.is_int(ctx) && to.is_int(ctx) && from.bitwidth(ctx) > to.bitwidth(ctx)
+            }
+            CastOp::Sitofp => from.is_int(ctx) && to.is_float(ctx),
+            CastOp::Fptosi => from.is_float(ctx) && to.is_int(ctx),
+            CastOp::Fpext => from.is_float(ctx) && to.is_f64(ctx),
+            CastOp::Bitcast => from.bitwidth(ctx) == to.bitwidth(ctx),
+        }
+    }
 }
 
 impl fmt::Display for CastOp {
@@ -80,6 +239,10 @@ impl fmt::Display for CastOp {
             CastOp::Zext => write!(f, "zext"),
             CastOp::Sext => write!(f, "sext"),
             CastOp::Trunc => write!(f, "trunc"),
+            CastOp::Sitofp => write!(f, "sitofp"),
+            CastOp::Fptosi => write!(f, "fptosi"),
+            CastOp::Fpext => write!(f, "fpext"),
+            CastOp::Bitcast => write!(f, "bitcast"),
         }
     }
 }
@@ -103,6 +266,10 @@ pub enum InstKind {
     IntBinary {
         op: IntBinaryOp,
     },
+    FloatBinary {
+        op: FloatBinaryOp,
+    },
+    FNeg,
     Cast {
         op: CastOp,
     },
@@ -213,6 +380,26 @@ impl<T: Usable> OperandList<T> {
         }
     }
 
+    /// Replace the operand at the given index, and return the old one.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no operand at the given index.
+    fn set(&mut self, idx: usize, operand: Operand<T>) -> Operand<T> {
+        match &mut self.operands[idx] {
+            OperandEntry::Occupied { operand: old } => std::mem::replace(old, operand),
+            _ => panic!("invalid operand index"),
+        }
+    }
+
+    /// Consume the list and iterate over the operands.
+    fn into_operands(self) -> impl Iterator<Item = Operand<T>> {
+        self.operands.into_iter().filter_map(|entry| match entry {
+            OperandEntry::Occupied { operand } => Some(operand),
+            _ => None,
+        })
+    }
+
     /// Get the operand at the given index.
     ///
     /// # Panics
@@ -264,6 +451,8 @@ pub struct InstData {
     phi_node: HashMap<Block, usize>,
     /// The result of the instruction.
     result: Option<Value>,
+    /// The source location this instruction is generated from, if any.
+    span: Option<Span>,
     // Linked list pointers.
     next: Option<Inst>,
     prev: Option<Inst>,
@@ -282,7 +471,7 @@ impl Inst {
     /// - `ctx`: The context to create the instruction.
     /// - `kind`: The kind of the instruction.
     /// - `ty`: The type of the instruction result.
-    fn new(ctx: &mut Context, kind: InstKind, ty: Ty) -> Self {
+    pub(super) fn new(ctx: &mut Context, kind: InstKind, ty: Ty) -> Self {
         let inst = ctx.alloc_with(|self_ptr| InstData {
             _self_ptr: self_ptr,
             kind,
@@ -290,6 +479,7 @@ impl Inst {
             phi_node: HashMap::default(),
             successors: OperandList::default(),
             result: None,
+            span: None,
             next: None,
             prev: None,
             container: None,
@@ -342,200 +532,100 @@ impl Inst {
         inst
     }
 
-    /// Create a new `add` instruction.
-    pub fn add(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::Add,
-            },
-            ty,
-        );
+    /// Create a new integer binary instruction, with the result of type `ty`.
+    pub(super) fn int_binary(
+        ctx: &mut Context,
+        op: IntBinaryOp,
+        lhs: Value,
+        rhs: Value,
+        ty: Ty,
+    ) -> Self {
+        let inst = Self::new(ctx, InstKind::IntBinary { op }, ty);
         inst.add_operand(ctx, lhs);
         inst.add_operand(ctx, rhs);
         inst
     }
 
+    /// Create a new `add` instruction.
+    pub fn add(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::Add, lhs, rhs, ty)
+    }
+
     /// Create a new `sub` instruction.
     pub fn sub(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::Sub,
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+        Self::int_binary(ctx, IntBinaryOp::Sub, lhs, rhs, ty)
     }
 
     /// Create a new `mul` instruction.
     pub fn mul(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::Mul,
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+        Self::int_binary(ctx, IntBinaryOp::Mul, lhs, rhs, ty)
     }
 
-    /// Create a new `SDiv` instruction.
-    pub fn SDiv(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::SDiv,
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `sdiv` instruction.
+    pub fn sdiv(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::SDiv, lhs, rhs, ty)
     }
 
-    /// Create a new `mod` instruction.
-    pub fn SRem(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::SRem,
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `udiv` instruction.
+    pub fn udiv(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::UDiv, lhs, rhs, ty)
     }
 
-    /// Create a new `Lt` instruction.
-    pub fn Lt(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::ICmp {
-                    cond: (IntCmpCond::Slt),
-                },
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `srem` instruction.
+    pub fn srem(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::SRem, lhs, rhs, ty)
     }
 
-    /// Create a new `Gt` instruction.
-    pub fn Gt(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::ICmp {
-                    cond: (IntCmpCond::Slt),
-                },
-            },
-            ty,
-        );
-        // Reverse lhs and rhs because we use slt not sgt
-        inst.add_operand(ctx, rhs);
-        inst.add_operand(ctx, lhs);
-        inst
+    /// Create a new `urem` instruction.
+    pub fn urem(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::URem, lhs, rhs, ty)
     }
 
-    /// Create a new `Lt` instruction.
-    pub fn Le(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::ICmp {
-                    cond: (IntCmpCond::Sle),
-                },
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `shl` instruction.
+    pub fn shl(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::Shl, lhs, rhs, ty)
     }
 
-    /// Create a new `Ge` instruction.
-    pub fn Ge(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::ICmp {
-                    cond: (IntCmpCond::Sle),
-                },
-            },
-            ty,
-        );
-        // Reverse lhs and rhs because we use sle not sge
-        inst.add_operand(ctx, rhs);
-        inst.add_operand(ctx, lhs);
-        inst
+    /// Create a new `lshr` instruction.
+    pub fn lshr(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::LShr, lhs, rhs, ty)
     }
 
-    /// Create a new `Eq` instruction.
-    pub fn Eq(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::ICmp {
-                    cond: (IntCmpCond::Eq),
-                },
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `ashr` instruction.
+    pub fn ashr(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::AShr, lhs, rhs, ty)
     }
 
-    /// Create a new `Ne` instruction.
-    pub fn Ne(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::ICmp {
-                    cond: (IntCmpCond::Ne),
-                },
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `and` instruction.
+    pub fn and(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::And, lhs, rhs, ty)
     }
 
-    /// Create a new `LogicalAnd` instruction.
-    pub fn LogicalAnd(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::And,
-            },
-            ty,
-        );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+    /// Create a new `or` instruction.
+    pub fn or(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::Or, lhs, rhs, ty)
     }
 
-    /// Create a new `LogicalOr` instruction.
-    pub fn LogicalOr(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
-        let inst = Self::new(
-            ctx,
-            InstKind::IntBinary {
-                op: IntBinaryOp::Or,
-            },
-            ty,
+    /// Create a new `xor` instruction.
+    pub fn xor(ctx: &mut Context, lhs: Value, rhs: Value, ty: Ty) -> Self {
+        Self::int_binary(ctx, IntBinaryOp::Xor, lhs, rhs, ty)
+    }
+
+    /// Create a new `icmp` instruction, the result is `i1`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the operands are not of the same integer type.
+    pub fn icmp(ctx: &mut Context, cond: IntCmpCond, lhs: Value, rhs: Value) -> Self {
+        let ty = lhs.ty(ctx);
+        assert!(
+            (ty.is_int(ctx) || ty.is_ptr(ctx)) && ty == rhs.ty(ctx),
+            "invalid operand types of icmp: {} and {}",
+            ty.display(ctx),
+            rhs.ty(ctx).display(ctx)
         );
-        inst.add_operand(ctx, lhs);
-        inst.add_operand(ctx, rhs);
-        inst
+        let i1 = Ty::i1(ctx);
+        Self::int_binary(ctx, IntBinaryOp::ICmp { cond }, lhs, rhs, i1)
     }
 
     /// Create a new `ret` instruction.
@@ -566,10 +656,113 @@ impl Inst {
         inst
     }
 
+    /// Create a new float binary instruction, with the operands of the same
+    /// float type.
+    ///
+    /// The result is `i1` for `fcmp`, otherwise the type of the operands.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the operands are not of the same float type.
+    pub(super) fn float_binary(
+        ctx: &mut Context,
+        op: FloatBinaryOp,
+        lhs: Value,
+        rhs: Value,
+    ) -> Self {
+        let ty = lhs.ty(ctx);
+        assert!(
+            ty.is_float(ctx) && ty == rhs.ty(ctx),
+            "invalid operand types of {}: {} and {}",
+            op,
+            ty.display(ctx),
+            rhs.ty(ctx).display(ctx)
+        );
+        let ty = match op {
+            FloatBinaryOp::FCmp { .. } => Ty::i1(ctx),
+            _ => ty,
+        };
+        let inst = Self::new(ctx, InstKind::FloatBinary { op }, ty);
+        inst.add_operand(ctx, lhs);
+        inst.add_operand(ctx, rhs);
+        inst
+    }
+
+    /// Create a new `fadd` instruction.
+    pub fn fadd(ctx: &mut Context, lhs: Value, rhs: Value) -> Self {
+        Self::float_binary(ctx, FloatBinaryOp::Fadd, lhs, rhs)
+    }
+
+    /// Create a new `fsub` instruction.
+    pub fn fsub(ctx: &mut Context, lhs: Value, rhs: Value) -> Self {
+        Self::float_binary(ctx, FloatBinaryOp::Fsub, lhs, rhs)
+    }
+
+    /// Create a new `fmul` instruction.
+    pub fn fmul(ctx: &mut Context, lhs: Value, rhs: Value) -> Self {
+        Self::float_binary(ctx, FloatBinaryOp::Fmul, lhs, rhs)
+    }
+
+    /// Create a new `fdiv` instruction.
+    pub fn fdiv(ctx: &mut Context, lhs: Value, rhs: Value) -> Self {
+        Self::float_binary(ctx, FloatBinaryOp::Fdiv, lhs, rhs)
+    }
+
+    /// Create a new `fcmp` instruction, the result is `i1`.
+    pub fn fcmp(ctx: &mut Context, cond: FloatCmpCond, lhs: Value, rhs: Value) -> Self {
+        Self::float_binary(ctx, FloatBinaryOp::FCmp { cond }, lhs, rhs)
+    }
+
+    /// Create a new `fneg` instruction.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the operand is not a float.
+    pub fn fneg(ctx: &mut Context, val: Value) -> Self {
+        let ty = val.ty(ctx);
+        assert!(ty.is_float(ctx), "invalid operand type of fneg: {}", ty.display(ctx));
+        let inst = Self::new(ctx, InstKind::FNeg, ty);
+        inst.add_operand(ctx, val);
+        inst
+    }
+
+    /// Create a new cast instruction, converting `val` to type `ty`.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the cast is invalid between the types, e.g., `sitofp` from a
+    ///   float, or `zext` to a narrower integer.
+    pub fn cast(ctx: &mut Context, op: CastOp, val: Value, ty: Ty) -> Self {
+        let from = val.ty(ctx);
+        assert!(
+            op.is_valid(ctx, from, ty),
+            "invalid cast: {} from {} to {}",
+            op,
+            from.display(ctx),
+            ty.display(ctx)
+        );
+        let inst = Self::new(ctx, InstKind::Cast { op }, ty);
+        inst.add_operand(ctx, val);
+        inst
+    }
+
+    /// Create a new `call` instruction, with the result of type `ret_ty`.
+    ///
+    /// The `callee` is a global reference to the function, which is kept as
+    /// the first operand, followed by the arguments.
+    pub fn call(ctx: &mut Context, callee: Value, args: Vec<Value>, ret_ty: Ty) -> Self {
+        let inst = Self::new(ctx, InstKind::Call, ret_ty);
+        inst.add_operand(ctx, callee);
+        for arg in args {
+            inst.add_operand(ctx, arg);
+        }
+        inst
+    }
+
     // TODO: Implement constructors for other instructions.
 
     /// Create an operand and add it to the operand list.
-    fn add_operand(self, ctx: &mut Context, operand: Value) {
+    pub(super) fn add_operand(self, ctx: &mut Context, operand: Value) {
         let next_idx = self.deref_mut(ctx).operands.next_idx();
         let operand = Operand::new(ctx, operand, self, next_idx);
         self.try_deref_mut(ctx)
@@ -597,6 +790,44 @@ impl Inst {
         self.deref(ctx).operands.get(idx).used()
     }
 
+    /// Get the operand at the given index, or `None` if the slot is vacant or
+    /// out of bounds.
+    pub(super) fn try_operand(self, ctx: &Context, idx: usize) -> Option<Value> {
+        match self.deref(ctx).operands.operands.get(idx) {
+            Some(OperandEntry::Occupied { operand }) => Some(operand.used()),
+            _ => None,
+        }
+    }
+
+    /// Iterate over operands with their indices in the operand list.
+    pub(super) fn indexed_operand_iter(
+        self,
+        ctx: &Context,
+    ) -> impl Iterator<Item = (usize, Value)> + '_ {
+        self.deref(ctx)
+            .operands
+            .iter()
+            .map(|op| (op.idx(), op.used()))
+    }
+
+    /// Replace the operand at the given index with `value`, and update the
+    /// users of both the old and the new value.
+    ///
+    /// This also works for phi nodes, the incoming block of the operand is
+    /// kept.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no operand at the given index.
+    pub fn set_operand(self, ctx: &mut Context, idx: usize, value: Value) {
+        let operand = Operand::new(ctx, value, self, idx);
+        let old = self.deref_mut(ctx).operands.set(idx, operand);
+        // The user is shared if the value is not changed, and should be kept.
+        if old.used() != value {
+            old.drop(ctx);
+        }
+    }
+
     /// Iterate over operands
     ///
     /// # Panics
@@ -622,7 +853,8 @@ impl Inst {
             .unwrap()
     }
 
-    /// Iterate over incoming block and values
+    /// Iterate over incoming block and values, in the order they are inserted
+    /// (if no incoming is removed in between).
     ///
     /// # Panics
     ///
@@ -630,10 +862,16 @@ impl Inst {
     pub fn incoming_iter(self, ctx: &Context) -> impl Iterator<Item = (Block, Value)> + '_ {
         assert!(self.is_phi(ctx), "not a phi node");
 
-        self.deref(ctx)
+        let mut incomings: Vec<_> = self
+            .deref(ctx)
             .phi_node
             .iter()
-            .map(move |(&block, &idx)| (block, self.operand(ctx, idx)))
+            .map(|(&block, &idx)| (idx, block))
+            .collect();
+        incomings.sort_unstable_by_key(|&(idx, _)| idx);
+        incomings
+            .into_iter()
+            .map(move |(idx, block)| (block, self.operand(ctx, idx)))
     }
 
     /// Add an incoming value to the phi node.
@@ -685,16 +923,111 @@ impl Inst {
         self.deref(ctx).successors.get(idx).used()
     }
 
+    /// Replace the successor at the given index with `block`, and update the
+    /// users of both blocks.
+    ///
+    /// Phi nodes in the blocks are not changed, the caller should update the
+    /// incoming values accordingly.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if there is no successor at the given index.
+    pub fn set_successor(self, ctx: &mut Context, idx: usize, block: Block) {
+        let operand = Operand::new(ctx, block, self, idx);
+        let old = self.deref_mut(ctx).successors.set(idx, operand);
+        if old.used() != block {
+            old.drop(ctx);
+        }
+        self.invalidate_cfg(ctx);
+    }
+
     /// Iterate over successors
     pub fn successor_iter(self, ctx: &Context) -> impl Iterator<Item = Block> + '_ {
         self.deref(ctx).successors.iter().map(|op| op.used())
     }
 
+    /// Get the successor at the given index, or `None` if the slot is vacant or
+    /// out of bounds.
+    pub(super) fn try_successor(self, ctx: &Context, idx: usize) -> Option<Block> {
+        match self.deref(ctx).successors.operands.get(idx) {
+            Some(OperandEntry::Occupied { operand }) => Some(operand.used()),
+            _ => None,
+        }
+    }
+
+    /// Iterate over successors with their indices in the successor list.
+    pub(super) fn indexed_successor_iter(
+        self,
+        ctx: &Context,
+    ) -> impl Iterator<Item = (usize, Block)> + '_ {
+        self.deref(ctx)
+            .successors
+            .iter()
+            .map(|op| (op.idx(), op.used()))
+    }
+
+    /// Drop all the operands and successors of the instruction, so that it is
+    /// no longer a user of them.
+    ///
+    /// The instruction is left without operands, and should be erased
+    /// afterwards.
+    pub(super) fn drop_operands(self, ctx: &mut Context) {
+        let data = self.deref_mut(ctx);
+        let operands = std::mem::take(&mut data.operands);
+        let successors = std::mem::take(&mut data.successors);
+        data.phi_node.clear();
+        for operand in operands.into_operands() {
+            operand.drop(ctx);
+        }
+        for successor in successors.into_operands() {
+            successor.drop(ctx);
+        }
+        self.invalidate_cfg(ctx);
+    }
+
+    /// Invalidate the cached CFG of the function containing this instruction
+    /// after its successors are changed.
+    fn invalidate_cfg(self, ctx: &mut Context) {
+        if let Some(func) = self.container(ctx).and_then(|block| block.container(ctx)) {
+            func.invalidate_cfg(ctx);
+        }
+    }
+
+    /// Erase the instruction, i.e., unlink it from its block, drop its
+    /// operands and deallocate it together with its result.
+    ///
+    /// # Panics
+    ///
+    /// - Panics if the result of the instruction is still used, except by the
+    ///   instruction itself (e.g., a phi node in a loop).
+    pub fn erase(self, ctx: &mut Context) {
+        self.unlink(ctx);
+        self.drop_operands(ctx);
+        let data = ctx.try_dealloc(self).unwrap();
+        if let Some(result) = data.result {
+            assert!(
+                result.users(ctx).into_iter().next().is_none(),
+                "erasing an instruction whose result is still used"
+            );
+            ctx.try_dealloc(result).unwrap();
+        }
+    }
+
     /// Get a displayable instance of the instruction.
-    pub fn display(self, ctx: &Context) -> DisplayInst {
+    pub fn display(self, ctx: &Context) -> DisplayInst<'_> {
         DisplayInst { ctx, inst: self }
     }
 
+    /// Get the source location of the instruction.
+    pub fn span(self, ctx: &Context) -> Option<Span> {
+        self.deref(ctx).span
+    }
+
+    /// Set the source location of the instruction.
+    pub fn set_span(self, ctx: &mut Context, span: Span) {
+        self.deref_mut(ctx).span = Some(span);
+    }
+
     /// Get the result of the instruction.
     pub fn result(self, ctx: &Context) -> Option<Value> {
         self.deref(ctx).result
@@ -709,6 +1042,14 @@ impl Inst {
     pub fn is_phi(self, ctx: &Context) -> bool {
         matches!(self.deref(ctx).kind, InstKind::Phi)
     }
+
+    /// Check if this is a terminator, i.e., `br` or `ret`.
+    pub fn is_terminator(self, ctx: &Context) -> bool {
+        matches!(
+            self.deref(ctx).kind,
+            InstKind::Br | InstKind::CondBr | InstKind::Ret
+        )
+    }
 }
 
 pub struct DisplayInst<'ctx> {
@@ -734,9 +1075,7 @@ impl fmt::Display for DisplayInst<'_> {
                 write!(f, "phi {}", ty.display(self.ctx))?;
                 let mut first = true;
                 for (block, value) in self.inst.incoming_iter(self.ctx) {
-                    if !first {
-                        write!(f, ", ")?;
-                    }
+                    write!(f, "{}", if first { " " } else { ", " })?;
                     first = false;
                     write!(
                         f,
@@ -772,6 +1111,32 @@ impl fmt::Display for DisplayInst<'_> {
                     self.inst.operand(self.ctx, 1).display(self.ctx, false)
                 )?;
             }
+            InstKind::FloatBinary { op } => {
+                write!(
+                    f,
+                    "{} {}, {}",
+                    op,
+                    self.inst.operand(self.ctx, 0).display(self.ctx, true),
+                    self.inst.operand(self.ctx, 1).display(self.ctx, false)
+                )?;
+            }
+            InstKind::FNeg => {
+                write!(
+                    f,
+                    "fneg {}",
+                    self.inst.operand(self.ctx, 0).display(self.ctx, true)
+                )?;
+            }
+            InstKind::Cast { op } => {
+                let ty = self.inst.result(self.ctx).unwrap().ty(self.ctx);
+                write!(
+                    f,
+                    "{} {} to {}",
+                    op,
+                    self.inst.operand(self.ctx, 0).display(self.ctx, true),
+                    ty.display(self.ctx)
+                )?;
+            }
             InstKind::Ret => {
                 if let Some(val) = self.inst.operand_iter(self.ctx).next() {
                     write!(f, "ret {}", val.display(self.ctx, true))?;
@@ -786,9 +1151,37 @@ impl fmt::Display for DisplayInst<'_> {
                     self.inst.successor(self.ctx, 0).name(self.ctx)
                 )?;
             }
-            _ => {
-                dbg!(self.inst.kind(self.ctx));
-                todo!("implement the display for other instructions");
+            InstKind::CondBr => {
+                write!(
+                    f,
+                    "br {}, label {}, label {}",
+                    self.inst.operand(self.ctx, 0).display(self.ctx, true),
+                    self.inst.successor(self.ctx, 0).name(self.ctx),
+                    self.inst.successor(self.ctx, 1).name(self.ctx)
+                )?;
+            }
+            InstKind::GetElementPtr { bound_ty } => {
+                write!(f, "getelementptr {}", bound_ty.display(self.ctx))?;
+                for operand in self.inst.operand_iter(self.ctx) {
+                    write!(f, ", {}", operand.display(self.ctx, true))?;
+                }
+            }
+            InstKind::Call => {
+                // The first operand is the callee, and the rest are the arguments.
+                let ret_ty = match self.inst.result(self.ctx) {
+                    Some(result) => result.ty(self.ctx).display(self.ctx).to_string(),
+                    None => "void".to_string(),
+                };
+                let mut operands = self.inst.operand_iter(self.ctx);
+                let callee = operands.next().unwrap();
+                write!(f, "call {} {}(", ret_ty, callee.display(self.ctx, false))?;
+                for (i, arg) in operands.enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg.display(self.ctx, true))?;
+                }
+                write!(f, ")")?;
             }
         }
 
@@ -847,6 +1240,272 @@ impl LinkedListNode for Inst {
     }
 
     fn set_container(self, ctx: &mut Self::Ctx, container: Option<Self::Container>) {
+        // A terminator is added to or removed from a block, so are the edges.
+        if self.is_terminator(ctx) {
+            for block in [self.container(ctx), container].into_iter().flatten() {
+                if let Some(func) = block.container(ctx) {
+                    func.invalidate_cfg(ctx);
+                }
+            }
+        }
         self.deref_mut(ctx).container = container;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infra::linked_list::LinkedListContainer;
+    use crate::ir::{parse, verify, Func};
+
+    #[test]
+    fn test_float_insts() {
+        let mut ctx = Context::default();
+        let f32 = Ty::f32(&mut ctx);
+        let i32 = Ty::i32(&mut ctx);
+        let i1 = Ty::i1(&mut ctx);
+        let func = Func::new(&mut ctx, "f".to_string(), f32);
+        let a = func.add_param(&mut ctx, f32);
+        let b = func.add_param(&mut ctx, f32);
+        let n = func.add_param(&mut ctx, i32);
+
+        let fadd = Inst::fadd(&mut ctx, a, b);
+        let result = fadd.result(&ctx).unwrap();
+        assert_eq!(result.ty(&ctx), f32);
+        assert_eq!(
+            fadd.display(&ctx).to_string(),
+            format!(
+                "{} = fadd f32 {}, {}",
+                result.display(&ctx, false),
+                a.display(&ctx, false),
+                b.display(&ctx, false)
+            )
+        );
+
+        let fcmp = Inst::fcmp(&mut ctx, FloatCmpCond::Olt, a, b);
+        let result = fcmp.result(&ctx).unwrap();
+        assert_eq!(result.ty(&ctx), i1);
+        assert_eq!(
+            fcmp.display(&ctx).to_string(),
+            format!(
+                "{} = fcmp olt f32 {}, {}",
+                result.display(&ctx, false),
+                a.display(&ctx, false),
+                b.display(&ctx, false)
+            )
+        );
+
+        let fneg = Inst::fneg(&mut ctx, a);
+        assert_eq!(fneg.result(&ctx).unwrap().ty(&ctx), f32);
+
+        let sitofp = Inst::cast(&mut ctx, CastOp::Sitofp, n, f32);
+        let result = sitofp.result(&ctx).unwrap();
+        assert_eq!(result.ty(&ctx), f32);
+        assert_eq!(
+            sitofp.display(&ctx).to_string(),
+            format!(
+                "{} = sitofp i32 {} to f32",
+                result.display(&ctx, false),
+                n.display(&ctx, false)
+            )
+        );
+
+        let fptosi = Inst::cast(&mut ctx, CastOp::Fptosi, a, i32);
+        assert_eq!(fptosi.result(&ctx).unwrap().ty(&ctx), i32);
+        let bitcast = Inst::cast(&mut ctx, CastOp::Bitcast, a, i32);
+        assert_eq!(bitcast.result(&ctx).unwrap().ty(&ctx), i32);
+    }
+
+    #[test]
+    fn test_icmp_cond_eval() {
+        use IntCmpCond as Ic;
+
+        // (cond, results of -1 vs 1, 1 vs 1, 2 vs 1)
+        let cases = [
+            (Ic::Eq, [false, true, false]),
+            (Ic::Ne, [true, false, true]),
+            (Ic::Slt, [true, false, false]),
+            (Ic::Sle, [true, true, false]),
+            (Ic::Sgt, [false, false, true]),
+            (Ic::Sge, [false, true, true]),
+            // -1 is the largest unsigned value
+            (Ic::Ult, [false, false, false]),
+            (Ic::Ule, [false, true, false]),
+            (Ic::Ugt, [true, false, true]),
+            (Ic::Uge, [true, true, true]),
+        ];
+        for (cond, expected) in cases {
+            let results = [(-1, 1), (1, 1), (2, 1)].map(|(lhs, rhs)| cond.eval(lhs, rhs));
+            assert_eq!(results, expected, "{}", cond);
+        }
+    }
+
+    #[test]
+    fn test_fcmp_cond_eval() {
+        use FloatCmpCond as Fc;
+
+        // (cond, results of 1 vs 2, 1 vs 1, NaN vs NaN, NaN vs 1)
+        let cases = [
+            (Fc::Oeq, [false, true, false, false]),
+            (Fc::One, [true, false, false, false]),
+            (Fc::Olt, [true, false, false, false]),
+            (Fc::Ole, [true, true, false, false]),
+            (Fc::Ogt, [false, false, false, false]),
+            (Fc::Oge, [false, true, false, false]),
+            // `!=` in C, a NaN is not equal to anything
+            (Fc::Une, [true, false, true, true]),
+        ];
+        let operands = [(1.0, 2.0), (1.0, 1.0), (f32::NAN, f32::NAN), (f32::NAN, 1.0)];
+        for (cond, expected) in cases {
+            let results = operands.map(|(lhs, rhs)| cond.eval(lhs, rhs));
+            assert_eq!(results, expected, "{}", cond);
+        }
+    }
+
+    #[test]
+    fn test_int_binary_eval() {
+        assert_eq!(IntBinaryOp::Add.eval(i32::MAX, 1), Some(i32::MIN));
+        assert_eq!(IntBinaryOp::Mul.eval(1 << 16, 1 << 16), Some(0));
+        assert_eq!(IntBinaryOp::SDiv.eval(-7, 2), Some(-3));
+        assert_eq!(IntBinaryOp::SRem.eval(-7, 2), Some(-1));
+        assert_eq!(IntBinaryOp::UDiv.eval(-2, 2), Some(i32::MAX));
+        assert_eq!(IntBinaryOp::SDiv.eval(1, 0), None);
+        assert_eq!(IntBinaryOp::SDiv.eval(i32::MIN, -1), None);
+        assert_eq!(IntBinaryOp::LShr.eval(-1, 28), Some(0xf));
+        assert_eq!(IntBinaryOp::AShr.eval(-16, 2), Some(-4));
+        assert_eq!(IntBinaryOp::Shl.eval(1, 32), None);
+
+        let icmp = IntBinaryOp::ICmp {
+            cond: IntCmpCond::Sgt,
+        };
+        assert_eq!(icmp.eval(2, 1), Some(1));
+        assert_eq!(icmp.eval(1, 2), Some(0));
+    }
+
+    #[test]
+    fn test_icmp() {
+        let mut ctx = Context::default();
+        let i32 = Ty::i32(&mut ctx);
+        let i1 = Ty::i1(&mut ctx);
+        let func = Func::new(&mut ctx, "f".to_string(), i32);
+        let a = func.add_param(&mut ctx, i32);
+        let b = func.add_param(&mut ctx, i32);
+
+        for cond in [IntCmpCond::Sgt, IntCmpCond::Sge, IntCmpCond::Ult] {
+            let icmp = Inst::icmp(&mut ctx, cond, a, b);
+            let result = icmp.result(&ctx).unwrap();
+            assert_eq!(result.ty(&ctx), i1);
+            // The operands are kept in order
+            assert_eq!(icmp.operand(&ctx, 0), a);
+            assert_eq!(icmp.operand(&ctx, 1), b);
+            assert_eq!(
+                icmp.display(&ctx).to_string(),
+                format!(
+                    "{} = icmp {} i32 {}, {}",
+                    result.display(&ctx, false),
+                    cond,
+                    a.display(&ctx, false),
+                    b.display(&ctx, false)
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_display_call() {
+        let mut ctx = Context::default();
+        let i32 = Ty::i32(&mut ctx);
+        let void = Ty::void(&mut ctx);
+        let ptr = Ty::ptr(&mut ctx);
+
+        let callee = Value::global_ref(&mut ctx, "putint".to_string(), ptr);
+        let arg = Value::i32(&mut ctx, 1);
+        let call = Inst::call(&mut ctx, callee, vec![arg], void);
+        assert_eq!(call.display(&ctx).to_string(), "call void @putint(i32 1)");
+
+        let callee = Value::global_ref(&mut ctx, "getint".to_string(), ptr);
+        let call = Inst::call(&mut ctx, callee, Vec::new(), i32);
+        let result = call.result(&ctx).unwrap();
+        assert_eq!(
+            call.display(&ctx).to_string(),
+            format!("{} = call i32 @getint()", result.display(&ctx, false))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid operand types of fadd")]
+    fn test_fadd_int_operands() {
+        let mut ctx = Context::default();
+        let a = Value::i32(&mut ctx, 1);
+        let b = Value::i32(&mut ctx, 2);
+        Inst::fadd(&mut ctx, a, b);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid cast: sitofp from f32 to f32")]
+    fn test_invalid_cast() {
+        let mut ctx = Context::default();
+        let a = Value::f32(&mut ctx, 1.0);
+        let f32 = Ty::f32(&mut ctx);
+        Inst::cast(&mut ctx, CastOp::Sitofp, a, f32);
+    }
+
+    #[test]
+    fn test_set_operand() {
+        let src = "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v1 = add i32 %v0, 1
+\t%v2 = mul i32 %v1, %v1
+\tret i32 %v2
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let param = func.params(&ctx)[0];
+        let block = func.head(&ctx).unwrap();
+        let add = block.head(&ctx).unwrap();
+        let mul = add.next(&ctx).unwrap();
+        let sum = add.result(&ctx).unwrap();
+
+        // Setting the same value keeps the use
+        mul.set_operand(&mut ctx, 0, sum);
+        assert_eq!(sum.users(&ctx).into_iter().count(), 2);
+
+        mul.set_operand(&mut ctx, 1, param);
+        assert_eq!(sum.users(&ctx).into_iter().count(), 1);
+        assert_eq!(param.users(&ctx).into_iter().count(), 2);
+        assert!(verify(&ctx).is_ok());
+
+        sum.replace_all_uses_with(&mut ctx, param);
+        assert_eq!(sum.users(&ctx).into_iter().count(), 0);
+        assert_eq!(param.users(&ctx).into_iter().count(), 3);
+        add.erase(&mut ctx);
+        assert!(verify(&ctx).is_ok());
+        assert_eq!(
+            func.display(&ctx).to_string(),
+            "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v3 = mul i32 %v0, %v0
+\tret i32 %v3
+}"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "erasing an instruction whose result is still used")]
+    fn test_erase_used_inst() {
+        let src = "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v1 = add i32 %v0, 1
+\tret i32 %v1
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let add = func.head(&ctx).unwrap().head(&ctx).unwrap();
+        add.erase(&mut ctx);
+    }
+}
@@ -0,0 +1,1475 @@
+//! Parser of the textual IR format.
+//!
+//! [`parse`] reads the format printed by [`Context`] back into a new context,
+//! so that IR can be written by hand, e.g., in tests of optimization passes.
+//! Besides the printed format, comments starting with `;` are allowed.
+//!
+//! Globals, functions, blocks and values can be referred to before they are
+//! defined. Values are numbered by the new context as the instructions are
+//! built, so printing the parsed context gives the source text up to the
+//! renaming of values and blocks.
+//!
+//! Only the names and the types required to build the instructions are
+//! checked. Whether the IR is well-formed, e.g., whether every block ends with
+//! a terminator, is left to [`verify`](super::verify).
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use thiserror::Error;
+
+use super::{
+    Block, CastOp, ConstantValue, Context, FloatBinaryOp, FloatCmpCond, Func, Global, Inst,
+    IntBinaryOp, IntCmpCond, Ty, TyData, Value,
+};
+use crate::infra::linked_list::LinkedListContainer;
+use crate::infra::span::{LineCol, LineIndex};
+use crate::infra::storage::ArenaPtr;
+
+/// An error in the textual IR.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{pos}: {message}")]
+pub struct ParseError {
+    /// Where the error is found.
+    pub pos: LineCol,
+    pub message: String,
+}
+
+/// Parse the textual IR into a new context.
+pub fn parse(src: &str) -> Result<Context, ParseError> {
+    let result = lex(src).and_then(|tokens| {
+        let mut parser = Parser::new(tokens);
+        let items = parser.parse_items()?;
+        parser.build(items)?;
+        Ok(parser.ctx)
+    });
+    result.map_err(|(offset, message)| ParseError {
+        pos: LineIndex::new(src).line_col(offset),
+        message,
+    })
+}
+
+/// The result of parsing, the error is a message with the byte offset of
+/// where it is found.
+type PResult<T> = Result<T, (usize, String)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TokenKind<'a> {
+    /// Keywords, types, opcodes and block labels.
+    Ident(&'a str),
+    /// `@name`, referring to a global or a function.
+    Global(&'a str),
+    /// `%name`, referring to a value or a block.
+    Local(&'a str),
+    /// Integer and float literals.
+    Num(&'a str),
+    Punct(char),
+    /// `...`
+    Ellipsis,
+    Eof,
+}
+
+impl fmt::Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Ident(s) | TokenKind::Num(s) => write!(f, "`{}`", s),
+            TokenKind::Global(s) => write!(f, "`@{}`", s),
+            TokenKind::Local(s) => write!(f, "`%{}`", s),
+            TokenKind::Punct(c) => write!(f, "`{}`", c),
+            TokenKind::Ellipsis => write!(f, "`...`"),
+            TokenKind::Eof => write!(f, "end of input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    kind: TokenKind<'a>,
+    /// The byte offset of the token in the source.
+    offset: usize,
+}
+
+fn lex(src: &str) -> PResult<Vec<Token<'_>>> {
+    let bytes = src.as_bytes();
+    let is_name = |c: u8| c.is_ascii_alphanumeric() || c == b'_' || c == b'.';
+    let name_end = |mut i: usize| {
+        while i < bytes.len() && is_name(bytes[i]) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        let kind = match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                i += 1;
+                continue;
+            }
+            b';' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            c @ (b'@' | b'%') => {
+                i = name_end(i + 1);
+                if i == start + 1 {
+                    return Err((start, format!("expected a name after `{}`", c as char)));
+                }
+                let name = &src[start + 1..i];
+                if c == b'@' {
+                    TokenKind::Global(name)
+                } else {
+                    TokenKind::Local(name)
+                }
+            }
+            b'.' if src[i..].starts_with("...") => {
+                i += 3;
+                TokenKind::Ellipsis
+            }
+            b'-' | b'0'..=b'9' => {
+                i += 1;
+                // The sign of the exponent is part of the literal, e.g., `1e-10`.
+                while i < bytes.len()
+                    && (is_name(bytes[i])
+                        || matches!(bytes[i], b'+' | b'-') && matches!(bytes[i - 1], b'e' | b'E'))
+                {
+                    i += 1;
+                }
+                TokenKind::Num(&src[start..i])
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                i = name_end(i);
+                TokenKind::Ident(&src[start..i])
+            }
+            c @ (b'=' | b',' | b'(' | b')' | b'[' | b']' | b'{' | b'}' | b':') => {
+                i += 1;
+                TokenKind::Punct(c as char)
+            }
+            _ => {
+                let c = src[i..].chars().next().unwrap();
+                return Err((start, format!("unexpected character `{}`", c)));
+            }
+        };
+        tokens.push(Token {
+            kind,
+            offset: start,
+        });
+    }
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        offset: src.len(),
+    });
+
+    Ok(tokens)
+}
+
+/// A name in the source, without the sigil.
+#[derive(Debug, Clone, Copy)]
+struct Name<'a> {
+    name: &'a str,
+    offset: usize,
+}
+
+enum OperandSyntax<'a> {
+    Local(Name<'a>),
+    Global(Name<'a>),
+    Const(ConstantValue),
+}
+
+/// An operand with its type, which is either written in the source or implied
+/// by the instruction.
+struct TypedOperand<'a> {
+    ty: Ty,
+    operand: OperandSyntax<'a>,
+}
+
+enum InstSyntaxKind<'a> {
+    Alloca(Ty),
+    Phi {
+        ty: Ty,
+        incomings: Vec<(TypedOperand<'a>, Name<'a>)>,
+    },
+    Load {
+        ty: Ty,
+        ptr: TypedOperand<'a>,
+    },
+    Store {
+        val: TypedOperand<'a>,
+        ptr: TypedOperand<'a>,
+    },
+    GetElementPtr {
+        bound_ty: Ty,
+        ptr: TypedOperand<'a>,
+        indices: Vec<TypedOperand<'a>>,
+    },
+    Call {
+        ret_ty: Ty,
+        callee: Name<'a>,
+        args: Vec<TypedOperand<'a>>,
+    },
+    Br(Name<'a>),
+    CondBr {
+        cond: TypedOperand<'a>,
+        then_dest: Name<'a>,
+        else_dest: Name<'a>,
+    },
+    Ret(Option<TypedOperand<'a>>),
+    IntBinary {
+        op: IntBinaryOp,
+        lhs: TypedOperand<'a>,
+        rhs: TypedOperand<'a>,
+    },
+    FloatBinary {
+        op: FloatBinaryOp,
+        lhs: TypedOperand<'a>,
+        rhs: TypedOperand<'a>,
+    },
+    FNeg(TypedOperand<'a>),
+    Cast {
+        op: CastOp,
+        val: TypedOperand<'a>,
+        ty: Ty,
+    },
+}
+
+impl<'a> InstSyntaxKind<'a> {
+    /// Get the value operands, including the incoming values of phi nodes.
+    fn operands(&self) -> Vec<&TypedOperand<'a>> {
+        match self {
+            InstSyntaxKind::Alloca(_) | InstSyntaxKind::Br(_) => Vec::new(),
+            InstSyntaxKind::Phi { incomings, .. } => {
+                incomings.iter().map(|(value, _)| value).collect()
+            }
+            InstSyntaxKind::Load { ptr, .. } => vec![ptr],
+            InstSyntaxKind::Store { val, ptr } => vec![val, ptr],
+            InstSyntaxKind::GetElementPtr { ptr, indices, .. } => {
+                std::iter::once(ptr).chain(indices).collect()
+            }
+            InstSyntaxKind::Call { args, .. } => args.iter().collect(),
+            InstSyntaxKind::CondBr { cond, .. } => vec![cond],
+            InstSyntaxKind::Ret(val) => val.iter().collect(),
+            InstSyntaxKind::IntBinary { lhs, rhs, .. }
+            | InstSyntaxKind::FloatBinary { lhs, rhs, .. } => vec![lhs, rhs],
+            InstSyntaxKind::FNeg(val) | InstSyntaxKind::Cast { val, .. } => vec![val],
+        }
+    }
+
+    /// Get the blocks referred to, including the incoming blocks of phi nodes.
+    fn labels(&self) -> Vec<Name<'a>> {
+        match self {
+            InstSyntaxKind::Phi { incomings, .. } => {
+                incomings.iter().map(|(_, block)| *block).collect()
+            }
+            InstSyntaxKind::Br(dest) => vec![*dest],
+            InstSyntaxKind::CondBr {
+                then_dest,
+                else_dest,
+                ..
+            } => vec![*then_dest, *else_dest],
+            _ => Vec::new(),
+        }
+    }
+}
+
+struct InstSyntax<'a> {
+    result: Option<Name<'a>>,
+    /// The offset of the opcode.
+    offset: usize,
+    kind: InstSyntaxKind<'a>,
+}
+
+struct BlockSyntax<'a> {
+    name: Name<'a>,
+    insts: Vec<InstSyntax<'a>>,
+}
+
+struct GlobalSyntax<'a> {
+    name: Name<'a>,
+    private: bool,
+    value: ConstantValue,
+}
+
+struct FuncSyntax<'a> {
+    name: Name<'a>,
+    ret_ty: Ty,
+    /// Parameters are only named in definitions.
+    params: Vec<(Ty, Option<Name<'a>>)>,
+    variadic: bool,
+    /// The blocks of a definition, or `None` for a declaration.
+    body: Option<Vec<BlockSyntax<'a>>>,
+}
+
+enum Item<'a> {
+    Global(GlobalSyntax<'a>),
+    Func(FuncSyntax<'a>),
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    /// The index of the current token.
+    cursor: usize,
+    ctx: Context,
+    /// References to globals and functions in global initializers, which are
+    /// resolved after all the items are parsed.
+    refs: Vec<Name<'a>>,
+    /// The types of the values that globals and functions point to.
+    ///
+    /// Functions are referred to as plain pointers.
+    symbols: HashMap<&'a str, Ty>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self {
+            tokens,
+            cursor: 0,
+            ctx: Context::default(),
+            refs: Vec::new(),
+            symbols: HashMap::new(),
+        }
+    }
+
+    fn peek(&self) -> TokenKind<'a> {
+        self.tokens[self.cursor].kind
+    }
+
+    /// Peek the token `n` tokens after the current one.
+    fn peek_nth(&self, n: usize) -> TokenKind<'a> {
+        let idx = (self.cursor + n).min(self.tokens.len() - 1);
+        self.tokens[idx].kind
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens[self.cursor].offset
+    }
+
+    fn bump(&mut self) {
+        if self.peek() != TokenKind::Eof {
+            self.cursor += 1;
+        }
+    }
+
+    /// Report that something is expected at the current token.
+    fn error<T>(&self, expected: &str) -> PResult<T> {
+        Err((
+            self.offset(),
+            format!("expected {}, found {}", expected, self.peek()),
+        ))
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        if self.peek() == kind {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> PResult<()> {
+        if self.eat(kind) {
+            Ok(())
+        } else {
+            self.error(&kind.to_string())
+        }
+    }
+
+    fn expect_global(&mut self) -> PResult<Name<'a>> {
+        match self.peek() {
+            TokenKind::Global(name) => {
+                let offset = self.offset();
+                self.bump();
+                Ok(Name { name, offset })
+            }
+            _ => self.error("a global name"),
+        }
+    }
+
+    fn expect_local(&mut self) -> PResult<Name<'a>> {
+        match self.peek() {
+            TokenKind::Local(name) => {
+                let offset = self.offset();
+                self.bump();
+                Ok(Name { name, offset })
+            }
+            _ => self.error("a local name"),
+        }
+    }
+
+    /// Parse a number literal of type `T`, `what` is used in error messages.
+    fn parse_num<T: std::str::FromStr>(&mut self, what: &str) -> PResult<T> {
+        match self.peek() {
+            TokenKind::Num(s) => {
+                let num = s
+                    .parse()
+                    .map_err(|_| (self.offset(), format!("invalid {} `{}`", what, s)))?;
+                self.bump();
+                Ok(num)
+            }
+            _ => self.error(what),
+        }
+    }
+
+    fn parse_ty(&mut self) -> PResult<Ty> {
+        let token = self.peek();
+        let ctx = &mut self.ctx;
+        let ty = match token {
+            TokenKind::Ident("void") => Ty::void(ctx),
+            TokenKind::Ident("i1") => Ty::i1(ctx),
+            TokenKind::Ident("i8") => Ty::i8(ctx),
+            TokenKind::Ident("i32") => Ty::i32(ctx),
+            TokenKind::Ident("f32") => Ty::f32(ctx),
+            TokenKind::Ident("f64") => Ty::f64(ctx),
+            TokenKind::Ident("ptr") => Ty::ptr(ctx),
+            TokenKind::Punct('[') => {
+                self.bump();
+                let len = self.parse_num("array length")?;
+                self.expect(TokenKind::Ident("x"))?;
+                let elem = self.parse_ty()?;
+                self.expect(TokenKind::Punct(']'))?;
+                return Ok(Ty::array(&mut self.ctx, elem, len));
+            }
+            _ => return self.error("a type"),
+        };
+        self.bump();
+        Ok(ty)
+    }
+
+    /// Parse a constant of type `ty`, with the type already parsed.
+    fn parse_const(&mut self, ty: Ty) -> PResult<ConstantValue> {
+        let offset = self.offset();
+        let value = match (self.peek(), ty.deref(&self.ctx).clone()) {
+            (TokenKind::Ident("undef"), _) => ConstantValue::Undef { ty },
+            (TokenKind::Ident("zeroinitializer"), _) => ConstantValue::AggregateZero { ty },
+            (TokenKind::Ident("true"), TyData::Int1) => ConstantValue::i1(&mut self.ctx, true),
+            (TokenKind::Ident("false"), TyData::Int1) => ConstantValue::i1(&mut self.ctx, false),
+            (TokenKind::Num(_), TyData::Int8) => {
+                let value = self.parse_num("i8 constant")?;
+                return Ok(ConstantValue::i8(&mut self.ctx, value));
+            }
+            (TokenKind::Num(_), TyData::Int32) => {
+                let value = self.parse_num("i32 constant")?;
+                return Ok(ConstantValue::i32(&mut self.ctx, value));
+            }
+            // `inf` and `NaN` are lexed as identifiers.
+            (TokenKind::Num(s) | TokenKind::Ident(s), TyData::Float32) => {
+                let value = s
+                    .parse()
+                    .map_err(|_| (offset, format!("invalid f32 constant `{}`", s)))?;
+                ConstantValue::f32(&mut self.ctx, value)
+            }
+            (TokenKind::Global(name), TyData::Ptr) => {
+                self.refs.push(Name { name, offset });
+                // The type of the referred value is resolved after all the
+                // globals are parsed.
+                ConstantValue::global_ref(&mut self.ctx, name.to_string(), ty)
+            }
+            (TokenKind::Punct('['), TyData::Array { elem, len }) => {
+                self.bump();
+                let mut elems = Vec::new();
+                if !self.eat(TokenKind::Punct(']')) {
+                    loop {
+                        let elem_offset = self.offset();
+                        if self.parse_ty()? != elem {
+                            let elem = elem.display(&self.ctx);
+                            let message = format!("expected an element of type `{}`", elem);
+                            return Err((elem_offset, message));
+                        }
+                        elems.push(self.parse_const(elem)?);
+                        if !self.eat(TokenKind::Punct(',')) {
+                            self.expect(TokenKind::Punct(']'))?;
+                            break;
+                        }
+                    }
+                }
+                if elems.len() != len {
+                    return Err((
+                        offset,
+                        format!("expected {} elements, found {}", len, elems.len()),
+                    ));
+                }
+                return Ok(ConstantValue::Array { ty, elems });
+            }
+            _ => {
+                let expected = format!("a value of type `{}`", ty.display(&self.ctx));
+                return self.error(&expected);
+            }
+        };
+        self.bump();
+        Ok(value)
+    }
+
+    /// Parse an operand of type `ty`, with the type already parsed or implied.
+    fn parse_operand(&mut self, ty: Ty) -> PResult<TypedOperand<'a>> {
+        let offset = self.offset();
+        let operand = match self.peek() {
+            TokenKind::Local(name) => {
+                self.bump();
+                OperandSyntax::Local(Name { name, offset })
+            }
+            TokenKind::Global(name) if ty.is_ptr(&self.ctx) => {
+                self.bump();
+                OperandSyntax::Global(Name { name, offset })
+            }
+            _ => OperandSyntax::Const(self.parse_const(ty)?),
+        };
+        Ok(TypedOperand { ty, operand })
+    }
+
+    fn parse_typed_operand(&mut self) -> PResult<TypedOperand<'a>> {
+        let ty = self.parse_ty()?;
+        self.parse_operand(ty)
+    }
+
+    /// Parse the operands of binary operations, the second operand has the
+    /// same type as the first one.
+    fn parse_binary_operands(&mut self) -> PResult<(TypedOperand<'a>, TypedOperand<'a>)> {
+        let lhs = self.parse_typed_operand()?;
+        self.expect(TokenKind::Punct(','))?;
+        let rhs = self.parse_operand(lhs.ty)?;
+        Ok((lhs, rhs))
+    }
+
+    fn parse_items(&mut self) -> PResult<Vec<Item<'a>>> {
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                TokenKind::Global(_) => items.push(Item::Global(self.parse_global()?)),
+                TokenKind::Ident("declare" | "define") => {
+                    items.push(Item::Func(self.parse_func()?))
+                }
+                TokenKind::Eof => return Ok(items),
+                _ => return self.error("a global or a function"),
+            }
+        }
+    }
+
+    fn parse_global(&mut self) -> PResult<GlobalSyntax<'a>> {
+        let name = self.expect_global()?;
+        self.expect(TokenKind::Punct('='))?;
+        // Only private constants and public variables can be created.
+        let private = self.eat(TokenKind::Ident("private"));
+        if private {
+            self.expect(TokenKind::Ident("constant"))?;
+        } else {
+            self.expect(TokenKind::Ident("global"))?;
+        }
+        let ty = self.parse_ty()?;
+        let value = self.parse_const(ty)?;
+        Ok(GlobalSyntax {
+            name,
+            private,
+            value,
+        })
+    }
+
+    fn parse_func(&mut self) -> PResult<FuncSyntax<'a>> {
+        let declaration = self.eat(TokenKind::Ident("declare"));
+        if !declaration {
+            self.expect(TokenKind::Ident("define"))?;
+        }
+        let ret_ty = self.parse_ty()?;
+        let name = self.expect_global()?;
+
+        self.expect(TokenKind::Punct('('))?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        if !self.eat(TokenKind::Punct(')')) {
+            loop {
+                if declaration && self.eat(TokenKind::Ellipsis) {
+                    variadic = true;
+                    self.expect(TokenKind::Punct(')'))?;
+                    break;
+                }
+                let ty = self.parse_ty()?;
+                let name = if declaration {
+                    None
+                } else {
+                    Some(self.expect_local()?)
+                };
+                params.push((ty, name));
+                if !self.eat(TokenKind::Punct(',')) {
+                    self.expect(TokenKind::Punct(')'))?;
+                    break;
+                }
+            }
+        }
+
+        let body = if declaration {
+            None
+        } else {
+            Some(self.parse_body()?)
+        };
+
+        Ok(FuncSyntax {
+            name,
+            ret_ty,
+            params,
+            variadic,
+            body,
+        })
+    }
+
+    fn at_label(&self) -> bool {
+        matches!(
+            (self.peek(), self.peek_nth(1)),
+            (TokenKind::Ident(_), TokenKind::Punct(':'))
+        )
+    }
+
+    fn parse_body(&mut self) -> PResult<Vec<BlockSyntax<'a>>> {
+        self.expect(TokenKind::Punct('{'))?;
+        let mut blocks = Vec::new();
+        while !self.eat(TokenKind::Punct('}')) {
+            let name = match self.peek() {
+                TokenKind::Ident(name) if self.at_label() => Name {
+                    name,
+                    offset: self.offset(),
+                },
+                _ => return self.error("a block label"),
+            };
+            self.bump();
+            self.bump();
+
+            let mut insts = Vec::new();
+            while !self.at_label() && self.peek() != TokenKind::Punct('}') {
+                insts.push(self.parse_inst()?);
+            }
+            blocks.push(BlockSyntax { name, insts });
+        }
+        Ok(blocks)
+    }
+
+    fn parse_inst(&mut self) -> PResult<InstSyntax<'a>> {
+        let result = match (self.peek(), self.peek_nth(1)) {
+            (TokenKind::Local(_), TokenKind::Punct('=')) => {
+                let name = self.expect_local()?;
+                self.bump();
+                Some(name)
+            }
+            _ => None,
+        };
+
+        let offset = self.offset();
+        let opcode = match self.peek() {
+            TokenKind::Ident(opcode) => opcode,
+            _ => return self.error("an instruction"),
+        };
+        self.bump();
+
+        let kind = match opcode {
+            "alloca" => InstSyntaxKind::Alloca(self.parse_ty()?),
+            "phi" => {
+                let ty = self.parse_ty()?;
+                let mut incomings = Vec::new();
+                if self.peek() == TokenKind::Punct('[') {
+                    loop {
+                        self.expect(TokenKind::Punct('['))?;
+                        let value = self.parse_operand(ty)?;
+                        self.expect(TokenKind::Punct(','))?;
+                        let block = self.expect_local()?;
+                        self.expect(TokenKind::Punct(']'))?;
+                        incomings.push((value, block));
+                        if !self.eat(TokenKind::Punct(',')) {
+                            break;
+                        }
+                    }
+                }
+                InstSyntaxKind::Phi { ty, incomings }
+            }
+            "load" => {
+                let ty = self.parse_ty()?;
+                self.expect(TokenKind::Punct(','))?;
+                let ptr = self.parse_typed_operand()?;
+                InstSyntaxKind::Load { ty, ptr }
+            }
+            "store" => {
+                let val = self.parse_typed_operand()?;
+                self.expect(TokenKind::Punct(','))?;
+                let ptr = self.parse_typed_operand()?;
+                InstSyntaxKind::Store { val, ptr }
+            }
+            "getelementptr" => {
+                let bound_ty = self.parse_ty()?;
+                self.expect(TokenKind::Punct(','))?;
+                let ptr = self.parse_typed_operand()?;
+                let mut indices = Vec::new();
+                while self.eat(TokenKind::Punct(',')) {
+                    indices.push(self.parse_typed_operand()?);
+                }
+                InstSyntaxKind::GetElementPtr {
+                    bound_ty,
+                    ptr,
+                    indices,
+                }
+            }
+            "call" => {
+                let ret_ty = self.parse_ty()?;
+                let callee = self.expect_global()?;
+                self.expect(TokenKind::Punct('('))?;
+                let mut args = Vec::new();
+                if !self.eat(TokenKind::Punct(')')) {
+                    loop {
+                        args.push(self.parse_typed_operand()?);
+                        if !self.eat(TokenKind::Punct(',')) {
+                            self.expect(TokenKind::Punct(')'))?;
+                            break;
+                        }
+                    }
+                }
+                InstSyntaxKind::Call {
+                    ret_ty,
+                    callee,
+                    args,
+                }
+            }
+            "br" => {
+                if self.eat(TokenKind::Ident("label")) {
+                    InstSyntaxKind::Br(self.expect_local()?)
+                } else {
+                    let cond = self.parse_typed_operand()?;
+                    self.expect(TokenKind::Punct(','))?;
+                    self.expect(TokenKind::Ident("label"))?;
+                    let then_dest = self.expect_local()?;
+                    self.expect(TokenKind::Punct(','))?;
+                    self.expect(TokenKind::Ident("label"))?;
+                    let else_dest = self.expect_local()?;
+                    InstSyntaxKind::CondBr {
+                        cond,
+                        then_dest,
+                        else_dest,
+                    }
+                }
+            }
+            "ret" => {
+                let ty = self.parse_ty()?;
+                if ty.is_void(&self.ctx) {
+                    InstSyntaxKind::Ret(None)
+                } else {
+                    InstSyntaxKind::Ret(Some(self.parse_operand(ty)?))
+                }
+            }
+            "icmp" => {
+                let cond = match self.peek() {
+                    TokenKind::Ident(s) => int_cmp_cond(s),
+                    _ => None,
+                };
+                let Some(cond) = cond else {
+                    return self.error("a comparison condition");
+                };
+                self.bump();
+                let (lhs, rhs) = self.parse_binary_operands()?;
+                if !lhs.ty.is_int(&self.ctx) && !lhs.ty.is_ptr(&self.ctx) {
+                    return Err(self.invalid_operand_ty(offset, opcode, lhs.ty));
+                }
+                InstSyntaxKind::IntBinary {
+                    op: IntBinaryOp::ICmp { cond },
+                    lhs,
+                    rhs,
+                }
+            }
+            "fcmp" => {
+                let cond = match self.peek() {
+                    TokenKind::Ident(s) => float_cmp_cond(s),
+                    _ => None,
+                };
+                let Some(cond) = cond else {
+                    return self.error("a comparison condition");
+                };
+                self.bump();
+                let (lhs, rhs) = self.parse_binary_operands()?;
+                if !lhs.ty.is_float(&self.ctx) {
+                    return Err(self.invalid_operand_ty(offset, opcode, lhs.ty));
+                }
+                InstSyntaxKind::FloatBinary {
+                    op: FloatBinaryOp::FCmp { cond },
+                    lhs,
+                    rhs,
+                }
+            }
+            "fneg" => {
+                let val = self.parse_typed_operand()?;
+                if !val.ty.is_float(&self.ctx) {
+                    return Err(self.invalid_operand_ty(offset, opcode, val.ty));
+                }
+                InstSyntaxKind::FNeg(val)
+            }
+            _ => {
+                if let Some(op) = int_binary_op(opcode) {
+                    let (lhs, rhs) = self.parse_binary_operands()?;
+                    if !lhs.ty.is_int(&self.ctx) {
+                        return Err(self.invalid_operand_ty(offset, opcode, lhs.ty));
+                    }
+                    InstSyntaxKind::IntBinary { op, lhs, rhs }
+                } else if let Some(op) = float_binary_op(opcode) {
+                    let (lhs, rhs) = self.parse_binary_operands()?;
+                    if !lhs.ty.is_float(&self.ctx) {
+                        return Err(self.invalid_operand_ty(offset, opcode, lhs.ty));
+                    }
+                    InstSyntaxKind::FloatBinary { op, lhs, rhs }
+                } else if let Some(op) = cast_op(opcode) {
+                    let val = self.parse_typed_operand()?;
+                    self.expect(TokenKind::Ident("to"))?;
+                    let ty = self.parse_ty()?;
+                    if !op.is_valid(&self.ctx, val.ty, ty) {
+                        return Err((
+                            offset,
+                            format!(
+                                "invalid cast: {} from `{}` to `{}`",
+                                op,
+                                val.ty.display(&self.ctx),
+                                ty.display(&self.ctx)
+                            ),
+                        ));
+                    }
+                    InstSyntaxKind::Cast { op, val, ty }
+                } else {
+                    return Err((offset, format!("unknown instruction `{}`", opcode)));
+                }
+            }
+        };
+
+        Ok(InstSyntax {
+            result,
+            offset,
+            kind,
+        })
+    }
+
+    fn invalid_operand_ty(&self, offset: usize, opcode: &str, ty: Ty) -> (usize, String) {
+        (
+            offset,
+            format!("invalid operand type `{}` of {}", ty.display(&self.ctx), opcode),
+        )
+    }
+
+    /// Build the parsed items into the context.
+    fn build(&mut self, items: Vec<Item<'a>>) -> PResult<()> {
+        // Collect the symbols first, so that they can be referred to before
+        // they are defined.
+        for item in items.iter() {
+            let (name, ty) = match item {
+                Item::Global(global) => (global.name, global.value.ty()),
+                Item::Func(func) => (func.name, Ty::ptr(&mut self.ctx)),
+            };
+            if self.symbols.insert(name.name, ty).is_some() {
+                return Err((name.offset, format!("redefinition of `@{}`", name.name)));
+            }
+        }
+        for name in self.refs.iter() {
+            self.symbol(*name)?;
+        }
+
+        for item in items {
+            match item {
+                Item::Global(GlobalSyntax {
+                    name,
+                    private,
+                    mut value,
+                }) => {
+                    self.resolve_refs(&mut value);
+                    let name = name.name.to_string();
+                    if private {
+                        Global::new_private_constant(&mut self.ctx, name, value);
+                    } else {
+                        Global::new(&mut self.ctx, name, value);
+                    }
+                }
+                Item::Func(func) => self.build_func(func)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the type of the value that a global or a function points to.
+    fn symbol(&self, name: Name) -> PResult<Ty> {
+        self.symbols
+            .get(name.name)
+            .copied()
+            .ok_or_else(|| (name.offset, format!("undefined global `@{}`", name.name)))
+    }
+
+    /// Resolve the types of the referred values in global references.
+    fn resolve_refs(&self, value: &mut ConstantValue) {
+        match value {
+            ConstantValue::GlobalRef { name, value_ty, .. } => {
+                *value_ty = self.symbols[name.as_str()];
+            }
+            ConstantValue::Array { elems, .. } => {
+                for elem in elems {
+                    self.resolve_refs(elem);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn build_func(&mut self, syntax: FuncSyntax<'a>) -> PResult<()> {
+        let FuncSyntax {
+            name,
+            ret_ty,
+            params,
+            variadic,
+            body,
+        } = syntax;
+        let name = name.name.to_string();
+
+        let Some(blocks) = body else {
+            let func = Func::declare(&mut self.ctx, name, ret_ty, variadic);
+            for (ty, _) in params {
+                func.add_param(&mut self.ctx, ty);
+            }
+            return Ok(());
+        };
+
+        let func = Func::new(&mut self.ctx, name, ret_ty);
+        let mut values = HashMap::new();
+        let mut defined = HashSet::new();
+        for (ty, param) in params {
+            let param = param.unwrap();
+            let value = func.add_param(&mut self.ctx, ty);
+            values.insert(param.name, value);
+            if !defined.insert(param.name) {
+                return Err((param.offset, format!("redefinition of `%{}`", param.name)));
+            }
+        }
+
+        let mut block_map = HashMap::new();
+        let mut pending = Vec::new();
+        for BlockSyntax { name, insts } in blocks {
+            let block = Block::new(&mut self.ctx);
+            func.push_back(&mut self.ctx, block).unwrap();
+            if block_map.insert(name.name, block).is_some() {
+                return Err((name.offset, format!("redefinition of block `{}`", name.name)));
+            }
+            pending.extend(insts.into_iter().map(|inst| (block, Some(inst))));
+        }
+
+        // Check the names before building, so that values used but never
+        // defined are not reported as cyclic uses.
+        for (_, syntax) in pending.iter() {
+            if let Some(result) = syntax.as_ref().and_then(|syntax| syntax.result) {
+                if !defined.insert(result.name) {
+                    return Err((result.offset, format!("redefinition of `%{}`", result.name)));
+                }
+            }
+        }
+        for syntax in pending.iter().filter_map(|(_, syntax)| syntax.as_ref()) {
+            for operand in syntax.kind.operands() {
+                if let OperandSyntax::Local(name) = operand.operand {
+                    if !defined.contains(name.name) {
+                        return Err((name.offset, format!("undefined value `%{}`", name.name)));
+                    }
+                }
+            }
+            for label in syntax.kind.labels() {
+                if !block_map.contains_key(label.name) {
+                    return Err((label.offset, format!("undefined block `%{}`", label.name)));
+                }
+            }
+        }
+
+        // Phi nodes are built first without incomings, because they can refer
+        // to values defined later.
+        let mut built = vec![None; pending.len()];
+        let mut phis = Vec::new();
+        for (i, (_, slot)) in pending.iter_mut().enumerate() {
+            if let Some(InstSyntax {
+                kind: InstSyntaxKind::Phi { .. },
+                ..
+            }) = slot
+            {
+                let InstSyntax {
+                    result,
+                    offset,
+                    kind: InstSyntaxKind::Phi { ty, incomings },
+                } = slot.take().unwrap()
+                else {
+                    unreachable!()
+                };
+                let phi = Inst::phi(&mut self.ctx, ty);
+                self.define(&mut values, phi, result, offset)?;
+                built[i] = Some(phi);
+                phis.push((phi, incomings));
+            }
+        }
+
+        // Other instructions are built once their operands are available. In
+        // valid SSA, definitions dominate their uses, so all the instructions
+        // can be built in this way, no matter how the blocks are laid out.
+        loop {
+            let mut progress = false;
+            for (i, (_, slot)) in pending.iter_mut().enumerate() {
+                let ready = match slot {
+                    Some(syntax) => syntax.kind.operands().iter().all(|operand| {
+                        match operand.operand {
+                            OperandSyntax::Local(name) => values.contains_key(name.name),
+                            _ => true,
+                        }
+                    }),
+                    None => continue,
+                };
+                if ready {
+                    let InstSyntax {
+                        result,
+                        offset,
+                        kind,
+                    } = slot.take().unwrap();
+                    let inst = self.build_inst(kind, &values, &block_map)?;
+                    self.define(&mut values, inst, result, offset)?;
+                    built[i] = Some(inst);
+                    progress = true;
+                }
+            }
+            if !progress {
+                break;
+            }
+        }
+
+        if let Some(syntax) = pending.iter().find_map(|(_, syntax)| syntax.as_ref()) {
+            let name = syntax
+                .kind
+                .operands()
+                .into_iter()
+                .find_map(|operand| match operand.operand {
+                    OperandSyntax::Local(name) if !values.contains_key(name.name) => Some(name),
+                    _ => None,
+                })
+                .unwrap();
+            return Err((name.offset, format!("cyclic use of `%{}`", name.name)));
+        }
+
+        for ((block, _), inst) in pending.into_iter().zip(built) {
+            block.push_back(&mut self.ctx, inst.unwrap()).unwrap();
+        }
+
+        for (phi, incomings) in phis {
+            for (value, block) in incomings {
+                let value = self.operand(&values, value)?;
+                phi.insert_incoming(&mut self.ctx, block_map[block.name], value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Bind the result of the instruction to the name.
+    fn define(
+        &self,
+        values: &mut HashMap<&'a str, Value>,
+        inst: Inst,
+        name: Option<Name<'a>>,
+        offset: usize,
+    ) -> PResult<()> {
+        match (inst.result(&self.ctx), name) {
+            (Some(result), Some(name)) => {
+                values.insert(name.name, result);
+                Ok(())
+            }
+            (None, None) => Ok(()),
+            (Some(_), None) => Err((offset, "expected a name for the result".to_string())),
+            (None, Some(name)) => Err((
+                name.offset,
+                "the instruction does not produce a value".to_string(),
+            )),
+        }
+    }
+
+    /// Resolve the operand into a value, checking the type of named values.
+    fn operand(
+        &mut self,
+        values: &HashMap<&'a str, Value>,
+        operand: TypedOperand<'a>,
+    ) -> PResult<Value> {
+        match operand.operand {
+            OperandSyntax::Local(name) => {
+                let value = values[name.name];
+                let ty = value.ty(&self.ctx);
+                if ty != operand.ty {
+                    return Err((
+                        name.offset,
+                        format!(
+                            "`%{}` is of type `{}`, expected `{}`",
+                            name.name,
+                            ty.display(&self.ctx),
+                            operand.ty.display(&self.ctx)
+                        ),
+                    ));
+                }
+                Ok(value)
+            }
+            OperandSyntax::Global(name) => {
+                let value_ty = self.symbol(name)?;
+                Ok(Value::global_ref(&mut self.ctx, name.name.to_string(), value_ty))
+            }
+            OperandSyntax::Const(mut value) => {
+                self.resolve_refs(&mut value);
+                Ok(Value::constant(&mut self.ctx, value))
+            }
+        }
+    }
+
+    fn build_inst(
+        &mut self,
+        kind: InstSyntaxKind<'a>,
+        values: &HashMap<&'a str, Value>,
+        blocks: &HashMap<&'a str, Block>,
+    ) -> PResult<Inst> {
+        let inst = match kind {
+            InstSyntaxKind::Alloca(ty) => Inst::alloca(&mut self.ctx, ty),
+            InstSyntaxKind::Phi { .. } => unreachable!("phi nodes are built separately"),
+            InstSyntaxKind::Load { ty, ptr } => {
+                let ptr = self.operand(values, ptr)?;
+                Inst::load(&mut self.ctx, ptr, ty)
+            }
+            InstSyntaxKind::Store { val, ptr } => {
+                let val = self.operand(values, val)?;
+                let ptr = self.operand(values, ptr)?;
+                Inst::store(&mut self.ctx, val, ptr)
+            }
+            InstSyntaxKind::GetElementPtr {
+                bound_ty,
+                ptr,
+                indices,
+            } => {
+                let ptr = self.operand(values, ptr)?;
+                let mut idx_values = Vec::new();
+                for idx in indices {
+                    idx_values.push(self.operand(values, idx)?);
+                }
+                Inst::getelementptr(&mut self.ctx, bound_ty, ptr, idx_values)
+            }
+            InstSyntaxKind::Call {
+                ret_ty,
+                callee,
+                args,
+            } => {
+                let value_ty = self.symbol(callee)?;
+                let callee = Value::global_ref(&mut self.ctx, callee.name.to_string(), value_ty);
+                let mut arg_values = Vec::new();
+                for arg in args {
+                    arg_values.push(self.operand(values, arg)?);
+                }
+                Inst::call(&mut self.ctx, callee, arg_values, ret_ty)
+            }
+            InstSyntaxKind::Br(dest) => Inst::br(&mut self.ctx, blocks[dest.name]),
+            InstSyntaxKind::CondBr {
+                cond,
+                then_dest,
+                else_dest,
+            } => {
+                let cond = self.operand(values, cond)?;
+                let then_dest = blocks[then_dest.name];
+                let else_dest = blocks[else_dest.name];
+                Inst::cond_br(&mut self.ctx, cond, then_dest, else_dest)
+            }
+            InstSyntaxKind::Ret(val) => {
+                let val = match val {
+                    Some(val) => Some(self.operand(values, val)?),
+                    None => None,
+                };
+                Inst::ret(&mut self.ctx, val)
+            }
+            InstSyntaxKind::IntBinary { op, lhs, rhs } => {
+                let ty = lhs.ty;
+                let lhs = self.operand(values, lhs)?;
+                let rhs = self.operand(values, rhs)?;
+                match op {
+                    IntBinaryOp::ICmp { cond } => Inst::icmp(&mut self.ctx, cond, lhs, rhs),
+                    op => Inst::int_binary(&mut self.ctx, op, lhs, rhs, ty),
+                }
+            }
+            InstSyntaxKind::FloatBinary { op, lhs, rhs } => {
+                let lhs = self.operand(values, lhs)?;
+                let rhs = self.operand(values, rhs)?;
+                Inst::float_binary(&mut self.ctx, op, lhs, rhs)
+            }
+            InstSyntaxKind::FNeg(val) => {
+                let val = self.operand(values, val)?;
+                Inst::fneg(&mut self.ctx, val)
+            }
+            InstSyntaxKind::Cast { op, val, ty } => {
+                let val = self.operand(values, val)?;
+                Inst::cast(&mut self.ctx, op, val, ty)
+            }
+        };
+        Ok(inst)
+    }
+}
+
+fn int_binary_op(opcode: &str) -> Option<IntBinaryOp> {
+    let op = match opcode {
+        "add" => IntBinaryOp::Add,
+        "sub" => IntBinaryOp::Sub,
+        "mul" => IntBinaryOp::Mul,
+        "sdiv" => IntBinaryOp::SDiv,
+        "udiv" => IntBinaryOp::UDiv,
+        "srem" => IntBinaryOp::SRem,
+        "urem" => IntBinaryOp::URem,
+        "shl" => IntBinaryOp::Shl,
+        "lshr" => IntBinaryOp::LShr,
+        "ashr" => IntBinaryOp::AShr,
+        "and" => IntBinaryOp::And,
+        "or" => IntBinaryOp::Or,
+        "xor" => IntBinaryOp::Xor,
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn int_cmp_cond(s: &str) -> Option<IntCmpCond> {
+    let cond = match s {
+        "eq" => IntCmpCond::Eq,
+        "ne" => IntCmpCond::Ne,
+        "slt" => IntCmpCond::Slt,
+        "sle" => IntCmpCond::Sle,
+        "sgt" => IntCmpCond::Sgt,
+        "sge" => IntCmpCond::Sge,
+        "ult" => IntCmpCond::Ult,
+        "ule" => IntCmpCond::Ule,
+        "ugt" => IntCmpCond::Ugt,
+        "uge" => IntCmpCond::Uge,
+        _ => return None,
+    };
+    Some(cond)
+}
+
+fn float_binary_op(opcode: &str) -> Option<FloatBinaryOp> {
+    let op = match opcode {
+        "fadd" => FloatBinaryOp::Fadd,
+        "fsub" => FloatBinaryOp::Fsub,
+        "fmul" => FloatBinaryOp::Fmul,
+        "fdiv" => FloatBinaryOp::Fdiv,
+        _ => return None,
+    };
+    Some(op)
+}
+
+fn float_cmp_cond(s: &str) -> Option<FloatCmpCond> {
+    let cond = match s {
+        "oeq" => FloatCmpCond::Oeq,
+        "one" => FloatCmpCond::One,
+        "olt" => FloatCmpCond::Olt,
+        "ole" => FloatCmpCond::Ole,
+        "ogt" => FloatCmpCond::Ogt,
+        "oge" => FloatCmpCond::Oge,
+        "une" => FloatCmpCond::Une,
+        _ => return None,
+    };
+    Some(cond)
+}
+
+fn cast_op(opcode: &str) -> Option<CastOp> {
+    let op = match opcode {
+        "zext" => CastOp::Zext,
+        "sext" => CastOp::Sext,
+        "trunc" => CastOp::Trunc,
+        "sitofp" => CastOp::Sitofp,
+        "fptosi" => CastOp::Fptosi,
+        "fpext" => CastOp::Fpext,
+        "bitcast" => CastOp::Bitcast,
+        _ => return None,
+    };
+    Some(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rename values and blocks in the textual IR by the order they first
+    /// appear, so that texts from different contexts can be compared.
+    fn normalize(text: &str) -> String {
+        let mut renamed = HashMap::new();
+        let mut result = String::new();
+        let mut word = String::new();
+        for c in text.chars().chain(std::iter::once('\n')) {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                word.push(c);
+                continue;
+            }
+            let prefix = if word.starts_with("bb_") { "bb_" } else { "v" };
+            let numbered = word.len() > prefix.len()
+                && word.starts_with(prefix)
+                && word[prefix.len()..].bytes().all(|b| b.is_ascii_digit());
+            if numbered {
+                let n = renamed.len();
+                let name = renamed
+                    .entry(word.clone())
+                    .or_insert_with(|| format!("{}{}", prefix, n));
+                result.push_str(name);
+            } else {
+                result.push_str(&word);
+            }
+            word.clear();
+            result.push(c);
+        }
+        result.pop();
+        result
+    }
+
+    fn assert_round_trip(src: &str) {
+        let ctx = parse(src).unwrap();
+        let printed = ctx.to_string();
+        assert_eq!(normalize(&printed), normalize(src));
+
+        let reparsed = parse(&printed).unwrap();
+        assert_eq!(normalize(&reparsed.to_string()), normalize(src));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let src = "\
+@g = global i32 -1
+@a = global [2 x i32] [i32 1, i32 2]
+@z = global [4 x f32] zeroinitializer
+@f = global [2 x f32] [f32 0.5, f32 1e-10]
+@p = global ptr @g
+@__STR_0 = private constant [3 x i8] [i8 104, i8 105, i8 0]
+
+declare i32 @getint()
+declare void @putint(i32)
+declare void @putf(ptr, ...)
+
+define i32 @main(i32 %v1, ptr %v2) {
+bb_0:
+\t%v3 = alloca i32
+\tstore i32 %v1, ptr %v3
+\t%v4 = load i32, ptr %v3
+\t%v5 = getelementptr [2 x i32], ptr @a, i32 0, i32 1
+\t%v6 = icmp slt i32 %v4, 10
+\tbr i1 %v6, label %bb_1, label %bb_2
+bb_1:
+\t%v7 = call i32 @getint()
+\t%v8 = sdiv i32 %v7, %v4
+\t%v9 = zext i1 %v6 to i32
+\tbr label %bb_2
+bb_2:
+\t%v10 = phi i32 [%v4, %bb_0], [%v8, %bb_1]
+\t%v11 = sitofp i32 %v10 to f32
+\t%v12 = fmul f32 %v11, 0.5
+\t%v13 = fneg f32 %v12
+\t%v14 = fcmp olt f32 %v13, inf
+\t%v15 = fcmp une f32 %v13, %v12
+\t%v16 = fpext f32 %v13 to f64
+\tcall void @putf(ptr @__STR_0, i32 %v10, f64 %v16)
+\tcall void @putint(i32 undef)
+\tret i32 %v10
+}
+
+define void @empty() {
+bb_3:
+\tret void
+}
+";
+        assert_round_trip(src);
+    }
+
+    #[test]
+    fn test_parse_forward_references() {
+        // The loop header refers to the value defined in the body, and the
+        // exit block is laid out before the block defining the value it uses.
+        let src = "\
+define i32 @sum(i32 %v1) {
+bb_0:
+\tbr label %bb_1
+bb_1:
+\t%v2 = phi i32 [0, %bb_0], [%v5, %bb_2]
+\t%v3 = phi i32 [0, %bb_0], [%v4, %bb_2]
+\t%v6 = icmp slt i32 %v3, %v1
+\tbr i1 %v6, label %bb_2, label %bb_4
+bb_3:
+\tret i32 %v7
+bb_2:
+\t%v4 = add i32 %v3, 1
+\t%v5 = add i32 %v2, %v3
+\tbr label %bb_1
+bb_4:
+\t%v7 = mul i32 %v2, 2
+\tbr label %bb_3
+}
+
+define i32 @main() {
+bb_5:
+\t%v8 = call i32 @sum(i32 10)
+\tret i32 %v8
+}
+";
+        assert_round_trip(src);
+    }
+
+    #[test]
+    fn test_parse_built_context() {
+        let mut ctx = Context::default();
+        let i32 = Ty::i32(&mut ctx);
+        let func = Func::new(&mut ctx, "max".to_string(), i32);
+        let a = func.add_param(&mut ctx, i32);
+        let b = func.add_param(&mut ctx, i32);
+        let entry = Block::new(&mut ctx);
+        let then_block = Block::new(&mut ctx);
+        let exit = Block::new(&mut ctx);
+        for block in [entry, then_block, exit] {
+            func.push_back(&mut ctx, block).unwrap();
+        }
+
+        let cmp = Inst::icmp(&mut ctx, IntCmpCond::Sgt, a, b);
+        let cond = cmp.result(&ctx).unwrap();
+        let br = Inst::cond_br(&mut ctx, cond, then_block, exit);
+        entry.push_back(&mut ctx, cmp).unwrap();
+        entry.push_back(&mut ctx, br).unwrap();
+        let br = Inst::br(&mut ctx, exit);
+        then_block.push_back(&mut ctx, br).unwrap();
+        let phi = Inst::phi(&mut ctx, i32);
+        phi.insert_incoming(&mut ctx, then_block, a);
+        phi.insert_incoming(&mut ctx, entry, b);
+        let max = phi.result(&ctx).unwrap();
+        let ret = Inst::ret(&mut ctx, Some(max));
+        exit.push_back(&mut ctx, phi).unwrap();
+        exit.push_back(&mut ctx, ret).unwrap();
+
+        let printed = ctx.to_string();
+        let parsed = parse(&printed).unwrap();
+        assert_eq!(normalize(&parsed.to_string()), normalize(&printed));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |src: &str| parse(src).err().unwrap().to_string();
+
+        assert_eq!(
+            error("define void @f() {\nbb_0:\n\tret i32 %v1\n}"),
+            "3:10: undefined value `%v1`"
+        );
+        assert_eq!(
+            error("define void @f(f32 %v1) {\nbb_0:\n\t%v2 = add i32 %v1, 1\n\tret void\n}"),
+            "3:16: `%v1` is of type `f32`, expected `i32`"
+        );
+        assert_eq!(
+            error("define void @f(f32 %v1) {\nbb_0:\n\t%v2 = add f32 %v1, 1.0\n}"),
+            "3:8: invalid operand type `f32` of add"
+        );
+        assert_eq!(
+            error("define void @f() {\nbb_0:\n\t%v1 = add i32 %v2, 1\n\t%v2 = add i32 %v1, 1\n}"),
+            "3:16: cyclic use of `%v2`"
+        );
+        assert_eq!(error("@g = global i32 @h"), "1:17: expected a value of type `i32`, found `@h`");
+        assert_eq!(error("@g = global ptr @h"), "1:17: undefined global `@h`");
+        assert_eq!(
+            error("@g = global [2 x i32] [i32 1]"),
+            "1:23: expected 2 elements, found 1"
+        );
+        assert_eq!(
+            error("define void @f() {\nbb_0:\n\tfoo\n}"),
+            "3:2: unknown instruction `foo`"
+        );
+        assert_eq!(
+            error("define void @f() {\nbb_0:\n\tbr label %bb_1\n}"),
+            "3:11: undefined block `%bb_1`"
+        );
+    }
+}
@@ -0,0 +1,18 @@
+//! Transformations of the IR.
+//!
+//! A pass is either a [`FunctionPass`] or a [`ModulePass`], and reports the
+//! analyses it preserves. The [`PassManager`] runs the passes in order, and
+//! invalidates the cached analyses that are not preserved, see
+//! [`analysis`](super::analysis).
+
+mod const_fold;
+mod dce;
+mod manager;
+mod mem2reg;
+mod unreachable;
+
+pub use const_fold::*;
+pub use dce::*;
+pub use manager::*;
+pub use mem2reg::*;
+pub use unreachable::*;
@@ -0,0 +1,146 @@
+//! Constant folding.
+//!
+//! Integer operations on `i32` constants are replaced by their results, and
+//! conditional branches on constant conditions become unconditional. The
+//! blocks that become unreachable are left for
+//! [`remove_unreachable_blocks`](super::remove_unreachable_blocks).
+
+use super::FunctionPass;
+use crate::infra::linked_list::{LinkedListContainer, LinkedListNode};
+use crate::ir::analysis::PreservedAnalyses;
+use crate::ir::{ConstantValue, Context, Func, Inst, InstKind, IntBinaryOp, Value, ValueKind};
+
+/// Fold the constants in `func`, and return the analyses preserved.
+pub fn const_fold(ctx: &mut Context, func: Func) -> PreservedAnalyses {
+    let mut folded = false;
+    let mut cfg_changed = false;
+
+    // In the reverse postorder, the operands are folded before their users,
+    // except for the phi nodes.
+    let blocks = func.cfg(ctx).reverse_postorder().to_vec();
+    for block in blocks {
+        let insts: Vec<Inst> = block.iter(ctx).collect();
+        for inst in insts {
+            match inst.kind(ctx) {
+                InstKind::IntBinary { .. } => {
+                    if let Some(value) = fold_int_binary(ctx, inst) {
+                        inst.result(ctx).unwrap().replace_all_uses_with(ctx, value);
+                        inst.erase(ctx);
+                        folded = true;
+                    }
+                }
+                InstKind::CondBr => {
+                    let ValueKind::Constant {
+                        value: ConstantValue::Int1 { value: cond, .. },
+                    } = *inst.operand(ctx, 0).kind(ctx)
+                    else {
+                        continue;
+                    };
+                    let (dest, other) = if cond {
+                        (inst.successor(ctx, 0), inst.successor(ctx, 1))
+                    } else {
+                        (inst.successor(ctx, 1), inst.successor(ctx, 0))
+                    };
+                    if dest != other {
+                        let phis: Vec<Inst> =
+                            other.iter(ctx).take_while(|inst| inst.is_phi(ctx)).collect();
+                        for phi in phis {
+                            phi.remove_incoming(ctx, block);
+                        }
+                    }
+                    let br = Inst::br(ctx, dest);
+                    inst.insert_after(ctx, br).unwrap();
+                    inst.erase(ctx);
+                    cfg_changed = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if cfg_changed {
+        PreservedAnalyses::none()
+    } else if folded {
+        PreservedAnalyses::cfg_analyses()
+    } else {
+        PreservedAnalyses::all()
+    }
+}
+
+/// Evaluate an integer operation on `i32` constants, or `None` if any operand
+/// is not a constant or the result is undefined.
+fn fold_int_binary(ctx: &mut Context, inst: Inst) -> Option<Value> {
+    let int32 = |value: Value| match value.kind(ctx) {
+        ValueKind::Constant {
+            value: ConstantValue::Int32 { value, .. },
+        } => Some(*value),
+        _ => None,
+    };
+    let lhs = int32(inst.operand(ctx, 0))?;
+    let rhs = int32(inst.operand(ctx, 1))?;
+    let InstKind::IntBinary { op } = inst.kind(ctx) else {
+        unreachable!()
+    };
+    let is_cmp = matches!(op, IntBinaryOp::ICmp { .. });
+    let result = op.eval(lhs, rhs)?;
+    if is_cmp {
+        Some(Value::i1(ctx, result != 0))
+    } else {
+        Some(Value::i32(ctx, result))
+    }
+}
+
+/// The pass of [`const_fold`].
+pub struct ConstFold;
+
+impl FunctionPass for ConstFold {
+    fn name(&self) -> &'static str { "const-fold" }
+
+    fn run(&mut self, ctx: &mut Context, func: Func) -> PreservedAnalyses { const_fold(ctx, func) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{parse, verify};
+
+    #[test]
+    fn test_const_fold() {
+        let src = "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v1 = add i32 2, 3
+\t%v2 = mul i32 %v1, %v0
+\t%v3 = sdiv i32 %v1, 0
+\t%v4 = icmp sgt i32 %v1, 4
+\tbr i1 %v4, label %bb_1, label %bb_2
+bb_1:
+\tbr label %bb_2
+bb_2:
+\t%v5 = phi i32 [%v2, %bb_0], [%v3, %bb_1]
+\tret i32 %v5
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        assert_eq!(const_fold(&mut ctx, func), PreservedAnalyses::none());
+        assert!(verify(&ctx).is_ok());
+        assert_eq!(const_fold(&mut ctx, func), PreservedAnalyses::all());
+        // The division by zero is left as is.
+        assert_eq!(
+            func.display(&ctx).to_string(),
+            "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v5 = mul i32 5, %v0
+\t%v7 = sdiv i32 5, 0
+\tbr label %bb_1
+bb_1:
+\tbr label %bb_2
+bb_2:
+\t%v1 = phi i32 [%v7, %bb_1]
+\tret i32 %v1
+}"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Dead code elimination.
+//!
+//! An instruction is dead if its result is not used and it has no side effect,
+//! i.e., it is not a `store`, a `call` or a terminator. Erasing a dead
+//! instruction may make the instructions defining its operands dead as well.
+
+use std::collections::HashSet;
+
+use super::FunctionPass;
+use crate::infra::linked_list::LinkedListContainer;
+use crate::ir::analysis::PreservedAnalyses;
+use crate::ir::{Context, Func, Inst, InstKind, Usable, ValueKind};
+
+/// Erase the dead instructions in `func`, and return whether the function is
+/// changed.
+pub fn dce(ctx: &mut Context, func: Func) -> bool {
+    let mut worklist: Vec<Inst> = func
+        .iter(ctx)
+        .flat_map(|block| block.iter(ctx))
+        .collect();
+    let mut erased = HashSet::new();
+
+    while let Some(inst) = worklist.pop() {
+        if erased.contains(&inst) || !is_dead(ctx, inst) {
+            continue;
+        }
+        for operand in inst.operand_iter(ctx) {
+            if let ValueKind::InstResult { inst: def, .. } = operand.kind(ctx) {
+                if *def != inst {
+                    worklist.push(*def);
+                }
+            }
+        }
+        inst.erase(ctx);
+        erased.insert(inst);
+    }
+
+    !erased.is_empty()
+}
+
+/// Check if the instruction has no side effect and its result is only used by
+/// itself, e.g., a phi node in a loop.
+fn is_dead(ctx: &Context, inst: Inst) -> bool {
+    match inst.kind(ctx) {
+        InstKind::Store | InstKind::Call | InstKind::Br | InstKind::CondBr | InstKind::Ret => {
+            false
+        }
+        _ => match inst.result(ctx) {
+            Some(result) => result
+                .users(ctx)
+                .into_iter()
+                .all(|user| user.inst() == inst),
+            None => false,
+        },
+    }
+}
+
+/// The pass of [`dce`].
+pub struct Dce;
+
+impl FunctionPass for Dce {
+    fn name(&self) -> &'static str { "dce" }
+
+    fn run(&mut self, ctx: &mut Context, func: Func) -> PreservedAnalyses {
+        if dce(ctx, func) {
+            PreservedAnalyses::cfg_analyses()
+        } else {
+            PreservedAnalyses::all()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{parse, verify};
+
+    #[test]
+    fn test_dce() {
+        let src = "\
+declare i32 @g()
+
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v1 = alloca i32
+\t%v2 = add i32 %v0, 1
+\t%v3 = mul i32 %v2, 2
+\t%v4 = call i32 @g()
+\t%v5 = load i32, ptr %v1
+\tbr label %bb_1
+bb_1:
+\t%v6 = phi i32 [%v0, %bb_0], [%v7, %bb_1]
+\t%v7 = add i32 %v6, 1
+\t%v8 = icmp slt i32 %v0, 10
+\tbr i1 %v8, label %bb_1, label %bb_2
+bb_2:
+\tret i32 %v0
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().find(|func| func.name(&ctx) == "f").unwrap();
+        assert!(dce(&mut ctx, func));
+        assert!(verify(&ctx).is_ok());
+        assert!(!dce(&mut ctx, func));
+
+        // The dead cycle of `%v6` and `%v7` is not detected.
+        assert_eq!(
+            func.display(&ctx).to_string(),
+            "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v8 = call i32 @g()
+\tbr label %bb_1
+bb_1:
+\t%v1 = phi i32 [%v0, %bb_0], [%v11, %bb_1]
+\t%v11 = add i32 %v1, 1
+\t%v13 = icmp slt i32 %v0, 10
+\tbr i1 %v13, label %bb_1, label %bb_2
+bb_2:
+\tret i32 %v0
+}"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! The pass manager and the optimization pipelines.
+
+use std::fmt;
+
+use super::{ConstFold, Dce, Mem2Reg, RemoveUnreachable};
+use crate::ir::analysis::PreservedAnalyses;
+use crate::ir::{verify, Context, Func};
+
+/// A pass run on each function definition separately.
+pub trait FunctionPass {
+    /// The name of the pass, e.g., `mem2reg`.
+    fn name(&self) -> &'static str;
+
+    /// Run the pass on a function definition, and return the analyses that
+    /// are still valid.
+    fn run(&mut self, ctx: &mut Context, func: Func) -> PreservedAnalyses;
+}
+
+/// A pass run on the whole context, e.g., inlining across functions.
+pub trait ModulePass {
+    /// The name of the pass.
+    fn name(&self) -> &'static str;
+
+    /// Run the pass on the context, and return the analyses that are still
+    /// valid in all the functions.
+    fn run(&mut self, ctx: &mut Context) -> PreservedAnalyses;
+}
+
+enum Pass {
+    Function(Box<dyn FunctionPass>),
+    Module(Box<dyn ModulePass>),
+}
+
+impl Pass {
+    fn name(&self) -> &'static str {
+        match self {
+            Pass::Function(pass) => pass.name(),
+            Pass::Module(pass) => pass.name(),
+        }
+    }
+}
+
+/// The optimization level, selected by `-O0`, `-O1` or `-O2`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum OptLevel {
+    /// No optimization, the IR is left as generated.
+    #[default]
+    O0,
+    /// Promote the local variables to SSA values, and clean up.
+    O1,
+    /// Also fold the constants and the branches on them.
+    O2,
+}
+
+impl OptLevel {
+    /// Parse a command line flag like `-O1`.
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "-O0" => Some(OptLevel::O0),
+            "-O1" => Some(OptLevel::O1),
+            "-O2" => Some(OptLevel::O2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OptLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptLevel::O0 => write!(f, "-O0"),
+            OptLevel::O1 => write!(f, "-O1"),
+            OptLevel::O2 => write!(f, "-O2"),
+        }
+    }
+}
+
+/// Runs the passes in the order they are added.
+///
+/// After each pass, the analyses cached in the functions that the pass does not
+/// preserve are invalidated. In debug builds, the IR is also verified after
+/// each pass, and an invalid IR panics with the name of the pass.
+#[derive(Default)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+}
+
+impl PassManager {
+    pub fn new() -> Self { Self::default() }
+
+    /// Create a pass manager with the pipeline of the optimization level.
+    pub fn with_opt_level(level: OptLevel) -> Self {
+        let mut pm = Self::new();
+        match level {
+            OptLevel::O0 => {}
+            OptLevel::O1 => {
+                pm.add_function_pass(RemoveUnreachable)
+                    .add_function_pass(Mem2Reg)
+                    .add_function_pass(Dce);
+            }
+            OptLevel::O2 => {
+                pm.add_function_pass(RemoveUnreachable)
+                    .add_function_pass(Mem2Reg)
+                    .add_function_pass(ConstFold)
+                    .add_function_pass(RemoveUnreachable)
+                    .add_function_pass(Dce);
+            }
+        }
+        pm
+    }
+
+    /// Append a function pass to the pipeline.
+    pub fn add_function_pass(&mut self, pass: impl FunctionPass + 'static) -> &mut Self {
+        self.passes.push(Pass::Function(Box::new(pass)));
+        self
+    }
+
+    /// Append a module pass to the pipeline.
+    pub fn add_module_pass(&mut self, pass: impl ModulePass + 'static) -> &mut Self {
+        self.passes.push(Pass::Module(Box::new(pass)));
+        self
+    }
+
+    /// Get the names of the passes in the pipeline.
+    pub fn pass_names(&self) -> Vec<&'static str> { self.passes.iter().map(Pass::name).collect() }
+
+    /// Run all the passes on the context.
+    pub fn run(&mut self, ctx: &mut Context) {
+        for pass in self.passes.iter_mut() {
+            let funcs: Vec<Func> = ctx
+                .funcs()
+                .filter(|func| !func.is_declaration(ctx))
+                .collect();
+            match pass {
+                Pass::Function(pass) => {
+                    for func in funcs {
+                        let preserved = pass.run(ctx, func);
+                        func.invalidate_analyses(ctx, &preserved);
+                    }
+                }
+                Pass::Module(pass) => {
+                    let preserved = pass.run(ctx);
+                    // The functions may be removed by the pass.
+                    for func in ctx.funcs().collect::<Vec<_>>() {
+                        func.invalidate_analyses(ctx, &preserved);
+                    }
+                }
+            }
+
+            if cfg!(debug_assertions) {
+                if let Err(errors) = verify(ctx) {
+                    let errors: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+                    panic!("invalid IR after `{}`:\n{}", pass.name(), errors.join("\n"));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::frontend::irgen_src;
+    use crate::infra::linked_list::LinkedListContainer;
+    use crate::ir::{Inst, InstKind};
+
+    /// Records the passes run, and preserves nothing.
+    struct Trace(&'static str, Rc<RefCell<Vec<String>>>);
+
+    impl FunctionPass for Trace {
+        fn name(&self) -> &'static str { self.0 }
+
+        fn run(&mut self, ctx: &mut Context, func: Func) -> PreservedAnalyses {
+            let entry = format!("{} @{}", self.0, func.name(ctx));
+            self.1.borrow_mut().push(entry);
+            PreservedAnalyses::none()
+        }
+    }
+
+    impl ModulePass for Trace {
+        fn name(&self) -> &'static str { self.0 }
+
+        fn run(&mut self, _ctx: &mut Context) -> PreservedAnalyses {
+            self.1.borrow_mut().push(self.0.to_string());
+            PreservedAnalyses::all()
+        }
+    }
+
+    #[test]
+    fn test_pass_order() {
+        let mut ctx = irgen_src("int f() { return 1; } int main() { return f(); }");
+        let trace = Rc::new(RefCell::new(Vec::new()));
+        let mut pm = PassManager::new();
+        pm.add_function_pass(Trace("a", trace.clone()))
+            .add_module_pass(Trace("b", trace.clone()))
+            .add_function_pass(Trace("c", trace.clone()));
+        assert_eq!(pm.pass_names(), ["a", "b", "c"]);
+
+        pm.run(&mut ctx);
+        // The declarations of the runtime library are skipped.
+        assert_eq!(*trace.borrow(), ["a @f", "a @main", "b", "c @f", "c @main"]);
+    }
+
+    #[test]
+    fn test_pipelines() {
+        let src = r#"
+            int main() {
+                int a = 1;
+                int b = 2;
+                int c[2];
+                if (a < b) {
+                    c[0] = a + b;
+                } else {
+                    c[0] = a - b;
+                }
+                return c[0];
+            }
+        "#;
+        let insts = |level: OptLevel| {
+            let mut ctx = irgen_src(src);
+            PassManager::with_opt_level(level).run(&mut ctx);
+            let main = ctx.funcs().find(|func| func.name(&ctx) == "main").unwrap();
+            let insts: Vec<Inst> = main
+                .iter(&ctx)
+                .flat_map(|block| block.iter(&ctx))
+                .collect();
+            let count = |kind: fn(&InstKind) -> bool| {
+                insts.iter().filter(|inst| kind(inst.kind(&ctx))).count()
+            };
+            (
+                count(|kind| matches!(kind, InstKind::Alloca { .. })),
+                count(|kind| matches!(kind, InstKind::CondBr)),
+            )
+        };
+
+        assert_eq!(OptLevel::from_flag("-O2"), Some(OptLevel::O2));
+        assert_eq!(OptLevel::from_flag("-O3"), None);
+        assert!(PassManager::with_opt_level(OptLevel::O0).pass_names().is_empty());
+
+        // Only the array is left in memory after mem2reg, and the branch is
+        // folded at `-O2`.
+        let (allocas, _) = insts(OptLevel::O0);
+        assert!(allocas > 1);
+        assert_eq!(insts(OptLevel::O1), (1, 1));
+        assert_eq!(insts(OptLevel::O2), (1, 0));
+    }
+}
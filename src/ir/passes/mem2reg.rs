@@ -0,0 +1,424 @@
+//! Promote memory slots to SSA values.
+//!
+//! The IR generator puts every local variable into an `alloca` slot, accessed
+//! by `load` and `store`. This pass promotes the slots of scalars whose address
+//! never escapes, i.e., only used as the pointer of `load` and `store`, into
+//! SSA values. Arrays are left in memory.
+//!
+//! Phi nodes are placed with the iterated dominance frontiers of the stores,
+//! pruned to the blocks where the slot is live, and the loads are renamed by a
+//! walk over the dominator tree, as in Cytron et al., "Efficiently Computing
+//! Static Single Assignment Form and the Control Dependence Graph".
+
+use std::collections::{HashMap, HashSet};
+
+use super::FunctionPass;
+use crate::infra::linked_list::LinkedListContainer;
+use crate::ir::analysis::{Cfg, DomFrontier, PreservedAnalyses};
+use crate::ir::{Block, ConstantValue, Context, Func, Inst, InstKind, Ty, Usable, Value};
+
+/// Promote the scalar slots of `func` into SSA values, and return whether the
+/// function is changed.
+///
+/// A load from a slot before any store reads `undef`. The loads and stores in
+/// the blocks unreachable from the entry are removed as well.
+pub fn mem2reg(ctx: &mut Context, func: Func) -> bool {
+    let slots: Vec<Inst> = func
+        .iter(ctx)
+        .flat_map(|block| block.iter(ctx))
+        .filter(|&inst| is_promotable(ctx, inst))
+        .collect();
+    if slots.is_empty() {
+        return false;
+    }
+    let slot_index: HashMap<Value, usize> = slots
+        .iter()
+        .enumerate()
+        .map(|(i, slot)| (slot.result(ctx).unwrap(), i))
+        .collect();
+    let slot_tys: Vec<Ty> = slots.iter().map(|&slot| slot_ty(ctx, slot)).collect();
+
+    // The slot accessed by a load or store, if it is promoted.
+    let accessed_slot = |ctx: &Context, inst: Inst| match inst.kind(ctx) {
+        InstKind::Load => slot_index.get(&inst.operand(ctx, 0)).copied(),
+        InstKind::Store => slot_index.get(&inst.operand(ctx, 1)).copied(),
+        _ => None,
+    };
+
+    let cfg = func.cfg(ctx).clone();
+    let dom = func.dom_tree(ctx).clone();
+    let df = DomFrontier::new(&cfg, &dom);
+
+    // The blocks storing to each slot, and the blocks loading from it before
+    // any store in the same block.
+    let mut def_blocks: Vec<HashSet<Block>> = vec![HashSet::new(); slots.len()];
+    let mut use_blocks: Vec<HashSet<Block>> = vec![HashSet::new(); slots.len()];
+    for &block in cfg.reverse_postorder() {
+        for inst in block.iter(ctx) {
+            let Some(slot) = accessed_slot(ctx, inst) else {
+                continue;
+            };
+            if matches!(inst.kind(ctx), InstKind::Store) {
+                def_blocks[slot].insert(block);
+            } else if !def_blocks[slot].contains(&block) {
+                use_blocks[slot].insert(block);
+            }
+        }
+    }
+
+    // Place the phi nodes where the slot is live on entry.
+    let mut phis: HashMap<Inst, usize> = HashMap::new();
+    let mut block_phis: HashMap<Block, Vec<(usize, Inst)>> = HashMap::new();
+    for slot in 0..slots.len() {
+        let live_in = live_in_blocks(&cfg, &def_blocks[slot], &use_blocks[slot]);
+        let mut blocks: Vec<Block> = df
+            .iterated(def_blocks[slot].iter().copied())
+            .into_iter()
+            .filter(|block| live_in.contains(block))
+            .collect();
+        blocks.sort_by_key(|&block| cfg.rpo_index(block));
+        for block in blocks {
+            let phi = Inst::phi(ctx, slot_tys[slot]);
+            phis.insert(phi, slot);
+            block_phis.entry(block).or_default().push((slot, phi));
+        }
+    }
+    for (&block, block_phis) in block_phis.iter() {
+        for &(_, phi) in block_phis.iter().rev() {
+            block.push_front(ctx, phi).unwrap();
+        }
+    }
+
+    let undefs: Vec<Value> = slot_tys
+        .iter()
+        .map(|&ty| Value::constant(ctx, ConstantValue::undef(ty)))
+        .collect();
+
+    // Rename along the dominator tree, with the values of the slots at the
+    // beginning of each block.
+    let mut worklist = vec![(cfg.entry().unwrap(), undefs.clone())];
+    while let Some((block, mut values)) = worklist.pop() {
+        let insts: Vec<Inst> = block.iter(ctx).collect();
+        for inst in insts {
+            if let Some(&slot) = phis.get(&inst) {
+                values[slot] = inst.result(ctx).unwrap();
+                continue;
+            }
+            let Some(slot) = accessed_slot(ctx, inst) else {
+                continue;
+            };
+            if matches!(inst.kind(ctx), InstKind::Store) {
+                values[slot] = inst.operand(ctx, 0);
+            } else {
+                inst.result(ctx)
+                    .unwrap()
+                    .replace_all_uses_with(ctx, values[slot]);
+            }
+            inst.erase(ctx);
+        }
+
+        for &succ in cfg.succs(block) {
+            for &(slot, phi) in block_phis.get(&succ).into_iter().flatten() {
+                phi.insert_incoming(ctx, block, values[slot]);
+            }
+        }
+        for &child in dom.children(block).iter().rev() {
+            worklist.push((child, values.clone()));
+        }
+    }
+
+    // The unreachable blocks are not renamed, they can only read `undef`.
+    for block in cfg.unreachable_blocks() {
+        for &succ in cfg.succs(block) {
+            for &(slot, phi) in block_phis.get(&succ).into_iter().flatten() {
+                phi.insert_incoming(ctx, block, undefs[slot]);
+            }
+        }
+        let insts: Vec<Inst> = block.iter(ctx).collect();
+        for inst in insts {
+            let Some(slot) = accessed_slot(ctx, inst) else {
+                continue;
+            };
+            if let Some(result) = inst.result(ctx) {
+                result.replace_all_uses_with(ctx, undefs[slot]);
+            }
+            inst.erase(ctx);
+        }
+    }
+
+    for slot in slots {
+        slot.erase(ctx);
+    }
+
+    true
+}
+
+/// The pass of [`mem2reg`].
+pub struct Mem2Reg;
+
+impl FunctionPass for Mem2Reg {
+    fn name(&self) -> &'static str { "mem2reg" }
+
+    fn run(&mut self, ctx: &mut Context, func: Func) -> PreservedAnalyses {
+        if mem2reg(ctx, func) {
+            PreservedAnalyses::cfg_analyses()
+        } else {
+            PreservedAnalyses::all()
+        }
+    }
+}
+
+/// Check if the instruction is an `alloca` of a scalar, only used as the
+/// pointer of `load` and `store` of the same type.
+fn is_promotable(ctx: &Context, inst: Inst) -> bool {
+    if !matches!(inst.kind(ctx), InstKind::Alloca { .. }) {
+        return false;
+    }
+    let ty = slot_ty(ctx, inst);
+    if ty.as_array(ctx).is_some() {
+        return false;
+    }
+    let slot = inst.result(ctx).unwrap();
+    slot.users(ctx).into_iter().all(|user| {
+        let user_inst = user.inst();
+        match user_inst.kind(ctx) {
+            InstKind::Load => user_inst.result(ctx).unwrap().ty(ctx) == ty,
+            // The slot must not be stored as a value.
+            InstKind::Store => user.idx() == 1 && user_inst.operand(ctx, 0).ty(ctx) == ty,
+            _ => false,
+        }
+    })
+}
+
+/// Get the type allocated by an `alloca`.
+fn slot_ty(ctx: &Context, inst: Inst) -> Ty {
+    match inst.kind(ctx) {
+        InstKind::Alloca { ty } => *ty,
+        _ => unreachable!(),
+    }
+}
+
+/// Compute the reachable blocks where a slot is live on entry, i.e., there is
+/// a path to a load from the beginning of the block without a store.
+fn live_in_blocks(
+    cfg: &Cfg,
+    def_blocks: &HashSet<Block>,
+    use_blocks: &HashSet<Block>,
+) -> HashSet<Block> {
+    let mut live_in: HashSet<Block> = use_blocks.clone();
+    let mut worklist: Vec<Block> = use_blocks.iter().copied().collect();
+    while let Some(block) = worklist.pop() {
+        for &pred in cfg.preds(block) {
+            // The slot is defined before the end of a storing block.
+            if !cfg.is_reachable(pred) || def_blocks.contains(&pred) {
+                continue;
+            }
+            if live_in.insert(pred) {
+                worklist.push(pred);
+            }
+        }
+    }
+    live_in
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontend::irgen_src;
+    use crate::ir::{parse, verify};
+
+    fn run(src: &str) -> String {
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().find(|func| func.name(&ctx) == "f").unwrap();
+        mem2reg(&mut ctx, func);
+        if let Err(errors) = verify(&ctx) {
+            panic!("{:?}\n{}", errors, func.display(&ctx));
+        }
+        func.display(&ctx).to_string()
+    }
+
+    #[test]
+    fn test_mem2reg_diamond() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\t%v1 = alloca i32
+\t%v2 = alloca [2 x i32]
+\tstore i32 0, ptr %v1
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tstore i32 1, ptr %v1
+\tbr label %bb_2
+bb_2:
+\t%v3 = load i32, ptr %v1
+\tret i32 %v3
+}
+";
+        assert_eq!(
+            run(src),
+            "\
+define i32 @f(i1 %v0) {
+bb_0:
+\t%v2 = alloca [2 x i32]
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tbr label %bb_2
+bb_2:
+\t%v6 = phi i32 [0, %bb_0], [1, %bb_1]
+\tret i32 %v6
+}"
+        );
+    }
+
+    #[test]
+    fn test_mem2reg_loop() {
+        // `%v2` is read before written, and is dead after the loop.
+        let src = "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v1 = alloca i32
+\t%v2 = alloca i32
+\tstore i32 0, ptr %v1
+\tbr label %bb_1
+bb_1:
+\t%v3 = load i32, ptr %v1
+\t%v4 = icmp slt i32 %v3, %v0
+\tbr i1 %v4, label %bb_2, label %bb_3
+bb_2:
+\t%v5 = load i32, ptr %v2
+\t%v6 = add i32 %v5, %v3
+\tstore i32 %v6, ptr %v2
+\t%v7 = add i32 %v3, 1
+\tstore i32 %v7, ptr %v1
+\tbr label %bb_1
+bb_3:
+\tret i32 %v3
+}
+";
+        assert_eq!(
+            run(src),
+            "\
+define i32 @f(i32 %v0) {
+bb_0:
+\tbr label %bb_1
+bb_1:
+\t%v10 = phi i32 [0, %bb_0], [%v9, %bb_2]
+\t%v11 = phi i32 [undef, %bb_0], [%v7, %bb_2]
+\t%v5 = icmp slt i32 %v10, %v0
+\tbr i1 %v5, label %bb_2, label %bb_3
+bb_2:
+\t%v7 = add i32 %v11, %v10
+\t%v9 = add i32 %v10, 1
+\tbr label %bb_1
+bb_3:
+\tret i32 %v10
+}"
+        );
+    }
+
+    #[test]
+    fn test_mem2reg_escaped() {
+        // The first slot is stored as a value and the third is passed to a call,
+        // only the second one is promoted.
+        let src = "\
+declare void @g(ptr)
+
+define i32 @f() {
+bb_0:
+\t%v0 = alloca i32
+\t%v1 = alloca ptr
+\t%v2 = alloca i32
+\tstore ptr %v0, ptr %v1
+\tcall void @g(ptr %v2)
+\t%v3 = load i32, ptr %v2
+\tret i32 %v3
+}
+";
+        assert_eq!(
+            run(src),
+            "\
+define i32 @f() {
+bb_0:
+\t%v1 = alloca i32
+\t%v3 = alloca i32
+\tcall void @g(ptr %v3)
+\t%v5 = load i32, ptr %v3
+\tret i32 %v5
+}"
+        );
+    }
+
+    #[test]
+    fn test_mem2reg_irgen() {
+        let src = r#"
+            int f(int n) {
+                int a[4] = {1, 2, 3, 4};
+                int s = 0;
+                int i = 0;
+                while (i < n) {
+                    if (i == 2) {
+                        i = i + 1;
+                        continue;
+                    }
+                    s = s + a[i % 4];
+                    i = i + 1;
+                }
+                return s;
+            }
+        "#;
+        let mut ctx = irgen_src(src);
+        let func = ctx.funcs().find(|func| func.name(&ctx) == "f").unwrap();
+        assert!(mem2reg(&mut ctx, func));
+        assert!(verify(&ctx).is_ok());
+
+        // Only the array is left in memory.
+        let insts: Vec<Inst> = func
+            .iter(&ctx)
+            .flat_map(|block| block.iter(&ctx))
+            .collect();
+        let allocas = insts
+            .iter()
+            .filter(|inst| matches!(inst.kind(&ctx), InstKind::Alloca { .. }))
+            .count();
+        assert_eq!(allocas, 1);
+        assert!(insts.iter().any(|inst| inst.is_phi(&ctx)));
+        assert!(!mem2reg(&mut ctx, func));
+    }
+
+    #[test]
+    fn test_mem2reg_unreachable() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\t%v1 = alloca i32
+\tstore i32 1, ptr %v1
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tstore i32 2, ptr %v1
+\tbr label %bb_2
+bb_3:
+\t%v2 = load i32, ptr %v1
+\tstore i32 %v2, ptr %v1
+\tbr label %bb_2
+bb_2:
+\t%v3 = load i32, ptr %v1
+\tret i32 %v3
+}
+";
+        assert_eq!(
+            run(src),
+            "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_3
+bb_1:
+\tbr label %bb_3
+bb_2:
+\tbr label %bb_3
+bb_3:
+\t%v6 = phi i32 [1, %bb_0], [2, %bb_1], [undef, %bb_2]
+\tret i32 %v6
+}"
+        );
+    }
+}
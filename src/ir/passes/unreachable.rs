@@ -0,0 +1,116 @@
+//! Removal of the blocks unreachable from the entry.
+//!
+//! The IR generator leaves unreachable blocks after `return`, `break` and
+//! `continue`, and folding conditional branches creates more. They cannot
+//! affect the execution, but they are still predecessors of reachable blocks,
+//! which stops other passes from simplifying the phi nodes there.
+
+use super::FunctionPass;
+use crate::infra::linked_list::LinkedListContainer;
+use crate::ir::analysis::PreservedAnalyses;
+use crate::ir::{Block, ConstantValue, Context, Func, Inst, Value};
+
+/// Erase the blocks unreachable from the entry of `func`, and return whether
+/// the function is changed.
+pub fn remove_unreachable_blocks(ctx: &mut Context, func: Func) -> bool {
+    let dead: Vec<Block> = func.cfg(ctx).unreachable_blocks().collect();
+    if dead.is_empty() {
+        return false;
+    }
+
+    // The results may still be used by other unreachable blocks, or by the phi
+    // nodes in the reachable ones, which are removed below.
+    for &block in dead.iter() {
+        let results: Vec<Value> = block.iter(ctx).filter_map(|inst| inst.result(ctx)).collect();
+        for result in results {
+            let ty = result.ty(ctx);
+            let undef = Value::constant(ctx, ConstantValue::undef(ty));
+            result.replace_all_uses_with(ctx, undef);
+        }
+    }
+
+    // Erase the terminators first, so the blocks branching to each other can be
+    // erased in any order.
+    for &block in dead.iter() {
+        let Some(terminator) = block.terminator(ctx) else {
+            continue;
+        };
+        let mut succs: Vec<Block> = terminator.successor_iter(ctx).collect();
+        succs.sort();
+        succs.dedup();
+        for succ in succs {
+            let phis: Vec<Inst> = succ.iter(ctx).take_while(|inst| inst.is_phi(ctx)).collect();
+            for phi in phis {
+                if phi.incoming_iter(ctx).any(|(incoming, _)| incoming == block) {
+                    phi.remove_incoming(ctx, block);
+                }
+            }
+        }
+        terminator.erase(ctx);
+    }
+
+    for block in dead {
+        block.erase(ctx);
+    }
+
+    true
+}
+
+/// The pass of [`remove_unreachable_blocks`].
+pub struct RemoveUnreachable;
+
+impl FunctionPass for RemoveUnreachable {
+    fn name(&self) -> &'static str { "remove-unreachable" }
+
+    fn run(&mut self, ctx: &mut Context, func: Func) -> PreservedAnalyses {
+        if remove_unreachable_blocks(ctx, func) {
+            PreservedAnalyses::none()
+        } else {
+            PreservedAnalyses::all()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{parse, verify};
+
+    #[test]
+    fn test_remove_unreachable_blocks() {
+        // `bb_2` and `bb_3` branch to each other, and `bb_3` is an incoming
+        // block of the phi in `bb_4`.
+        let src = "\
+define i32 @f(i32 %v0) {
+bb_0:
+\tbr label %bb_4
+bb_2:
+\t%v1 = add i32 %v0, 1
+\tbr label %bb_3
+bb_3:
+\t%v2 = add i32 %v1, 1
+\t%v3 = icmp slt i32 %v2, 10
+\tbr i1 %v3, label %bb_2, label %bb_4
+bb_4:
+\t%v4 = phi i32 [%v0, %bb_0], [%v2, %bb_3]
+\tret i32 %v4
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        assert!(remove_unreachable_blocks(&mut ctx, func));
+        assert!(verify(&ctx).is_ok());
+        assert!(!remove_unreachable_blocks(&mut ctx, func));
+        assert_eq!(
+            func.display(&ctx).to_string(),
+            "\
+define i32 @f(i32 %v0) {
+bb_0:
+\tbr label %bb_3
+bb_3:
+\t%v1 = phi i32 [%v0, %bb_0]
+\tret i32 %v1
+}"
+        );
+    }
+}
@@ -16,6 +16,11 @@ pub enum TyData {
     Int32,
     /// The 'f32' type.
     Float32,
+    /// The `f64` type.
+    ///
+    /// SysY has no double, this is only used to pass floats to variadic
+    /// functions, which take doubles as in C.
+    Float64,
     /// The pointer type.
     Ptr,
     /// The array type.
@@ -44,6 +49,7 @@ impl<'ctx> std::fmt::Display for DisplayTy<'ctx> {
             TyData::Int8 => write!(f, "i8"),
             TyData::Int32 => write!(f, "i32"),
             TyData::Float32 => write!(f, "f32"),
+            TyData::Float64 => write!(f, "f64"),
             TyData::Ptr => write!(f, "ptr"),
             TyData::Array { elem, len } => write!(
                 f,
@@ -74,6 +80,9 @@ impl Ty {
     /// Fetch a type representing 'f32'.
     pub fn f32(ctx: &mut Context) -> Self { ctx.alloc(TyData::Float32) }
 
+    /// Fetch a type representing 'f64'.
+    pub fn f64(ctx: &mut Context) -> Self { ctx.alloc(TyData::Float64) }
+
     /// Fetch a type representing a pointer.
     pub fn ptr(ctx: &mut Context) -> Self { ctx.alloc(TyData::Ptr) }
 
@@ -86,6 +95,34 @@ impl Ty {
         matches!(self.try_deref(ctx).unwrap(), TyData::Void)
     }
 
+    /// Check if the type is `i1`.
+    pub fn is_i1(&self, ctx: &Context) -> bool {
+        matches!(self.try_deref(ctx).unwrap(), TyData::Int1)
+    }
+
+    /// Check if the type is an integer type, i.e., `i1`, `i8` or `i32`.
+    pub fn is_int(&self, ctx: &Context) -> bool {
+        matches!(
+            self.try_deref(ctx).unwrap(),
+            TyData::Int1 | TyData::Int8 | TyData::Int32
+        )
+    }
+
+    /// Check if the type is a pointer type.
+    pub fn is_ptr(&self, ctx: &Context) -> bool {
+        matches!(self.try_deref(ctx).unwrap(), TyData::Ptr)
+    }
+
+    /// Check if the type is a float type.
+    pub fn is_float(&self, ctx: &Context) -> bool {
+        matches!(self.try_deref(ctx).unwrap(), TyData::Float32)
+    }
+
+    /// Check if the type is `f64`.
+    pub fn is_f64(&self, ctx: &Context) -> bool {
+        matches!(self.try_deref(ctx).unwrap(), TyData::Float64)
+    }
+
     /// Get the bit width of the type.
     pub fn bitwidth(&self, ctx: &Context) -> usize {
         match self.try_deref(ctx).unwrap() {
@@ -94,6 +131,7 @@ impl Ty {
             TyData::Int8 => 8,
             TyData::Int32 => 32,
             TyData::Float32 => 32,
+            TyData::Float64 => 64,
             TyData::Ptr => ctx.target.ptr_size as usize * 8,
             TyData::Array { elem, len } => elem.bitwidth(ctx) * len,
         }
@@ -108,7 +146,7 @@ impl Ty {
     }
 
     /// Get the displayable type.
-    pub fn display(self, ctx: &Context) -> DisplayTy { DisplayTy { ctx, ty: self } }
+    pub fn display(self, ctx: &Context) -> DisplayTy<'_> { DisplayTy { ctx, ty: self } }
 }
 
 impl ArenaPtr for Ty {
@@ -144,10 +182,13 @@ mod tests {
         let i1 = Ty::i1(&mut ctx);
         let i8 = Ty::i8(&mut ctx);
         let i32 = Ty::i32(&mut ctx);
+        let f64 = Ty::f64(&mut ctx);
         let ptr = Ty::ptr(&mut ctx);
         let arr = Ty::array(&mut ctx, i32, 10);
 
         assert_eq!(void.bitwidth(&ctx), 0);
+        assert_eq!(f64.bitwidth(&ctx), 64);
+        assert!(f64.is_f64(&ctx) && !f64.is_float(&ctx));
         assert_eq!(i1.bitwidth(&ctx), 1);
         assert_eq!(i8.bitwidth(&ctx), 8);
         assert_eq!(i32.bitwidth(&ctx), 32);
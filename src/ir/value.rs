@@ -5,7 +5,7 @@ use super::context::Context;
 use super::def_use::{Usable, User};
 use super::func::Func;
 use super::inst::Inst;
-use super::ty::Ty;
+use super::ty::{Ty, TyData};
 use crate::infra::storage::{Arena, ArenaPtr, GenericPtr, Idx};
 
 pub enum ConstantValue {
@@ -69,6 +69,39 @@ impl ConstantValue {
         ConstantValue::Float32 { ty: f32, value }
     }
 
+    pub fn undef(ty: Ty) -> ConstantValue { ConstantValue::Undef { ty } }
+
+    /// Create the zero value of type `ty`, i.e., `zeroinitializer` for arrays.
+    pub fn zero(ctx: &Context, ty: Ty) -> ConstantValue {
+        match ty.try_deref(ctx).unwrap() {
+            TyData::Int1 => ConstantValue::Int1 { ty, value: false },
+            TyData::Int8 => ConstantValue::Int8 { ty, value: 0 },
+            TyData::Int32 => ConstantValue::Int32 { ty, value: 0 },
+            TyData::Float32 => ConstantValue::Float32 { ty, value: 0.0 },
+            _ => ConstantValue::AggregateZero { ty },
+        }
+    }
+
+    /// Check if the constant is all zero bits, e.g., `0`, `0.0` or
+    /// `zeroinitializer`.
+    pub fn is_zero(&self) -> bool {
+        match self {
+            ConstantValue::AggregateZero { .. } => true,
+            ConstantValue::Int1 { value, .. } => !value,
+            ConstantValue::Int8 { value, .. } => *value == 0,
+            ConstantValue::Int32 { value, .. } => *value == 0,
+            ConstantValue::Float32 { value, .. } => value.to_bits() == 0,
+            ConstantValue::Array { elems, .. } => elems.iter().all(ConstantValue::is_zero),
+            ConstantValue::Undef { .. } | ConstantValue::GlobalRef { .. } => false,
+        }
+    }
+
+    /// Create an array constant with elements of type `elem`.
+    pub fn array(ctx: &mut Context, elem: Ty, elems: Vec<ConstantValue>) -> ConstantValue {
+        let ty = Ty::array(ctx, elem, elems.len());
+        ConstantValue::Array { ty, elems }
+    }
+
     pub fn global_ref(ctx: &mut Context, name: String, value_ty: Ty) -> ConstantValue {
         let ty = Ty::ptr(ctx);
         ConstantValue::GlobalRef { ty, name, value_ty }
@@ -87,7 +120,8 @@ impl ConstantValue {
             ConstantValue::Int1 { value, .. } => s.push_str(&value.to_string()),
             ConstantValue::Int8 { value, .. } => s.push_str(&value.to_string()),
             ConstantValue::Int32 { value, .. } => s.push_str(&value.to_string()),
-            ConstantValue::Float32 { value, .. } => s.push_str(&value.to_string()),
+            // Always with a decimal point or an exponent, e.g., `1.0` or `1e-10`
+            ConstantValue::Float32 { value, .. } => s.push_str(&format!("{:?}", value)),
             ConstantValue::Array { elems, .. } => {
                 s.push('[');
                 for (i, elem) in elems.iter().enumerate() {
@@ -180,7 +214,7 @@ impl Value {
         Self::new(ctx, ValueKind::InstResult { inst, ty })
     }
 
-    pub fn display(self, ctx: &Context, with_type: bool) -> DisplayValue {
+    pub fn display(self, ctx: &Context, with_type: bool) -> DisplayValue<'_> {
         DisplayValue {
             ctx,
             value: self,
@@ -188,6 +222,9 @@ impl Value {
         }
     }
 
+    /// Get the kind of the value.
+    pub fn kind(self, ctx: &Context) -> &ValueKind { &self.try_deref(ctx).unwrap().kind }
+
     pub fn is_param(&self, ctx: &Context) -> bool {
         matches!(self.try_deref(ctx).unwrap().kind, ValueKind::Param { .. })
     }
@@ -216,6 +253,22 @@ impl Value {
         let value = ConstantValue::global_ref(ctx, name, value_ty);
         Self::new(ctx, ValueKind::Constant { value })
     }
+
+    /// Create a value of an arbitrary constant, e.g., `undef`.
+    pub fn constant(ctx: &mut Context, value: ConstantValue) -> Self {
+        Self::new(ctx, ValueKind::Constant { value })
+    }
+
+    /// Replace all the uses of this value with `new`.
+    pub fn replace_all_uses_with(self, ctx: &mut Context, new: Value) {
+        if self == new {
+            return;
+        }
+        let users: Vec<_> = self.users(ctx).into_iter().collect();
+        for user in users {
+            user.inst().set_operand(ctx, user.idx(), new);
+        }
+    }
 }
 
 impl ArenaPtr for Value {
@@ -0,0 +1,883 @@
+//! Verifier of the IR.
+//!
+//! [`verify`] checks that a [`Context`] is well-formed, and reports all the
+//! violations found instead of stopping at the first one. It is cheap enough
+//! to be run after every pass, see [`debug_verify`].
+//!
+//! The following properties are checked for every function definition:
+//!
+//! - Every block ends with a terminator, and terminators only appear at the
+//!   end of blocks. Phi nodes only appear at the beginning of blocks.
+//! - The entry block has no predecessors, and the incoming blocks of every phi
+//!   node are exactly the predecessors of its block.
+//! - The operands have the types required by the instructions.
+//! - Every use of an instruction result is dominated by its definition.
+//! - The users recorded in values and blocks are in sync with the operands of
+//!   instructions.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::{
+    Block, ConstantValue, Context, FloatBinaryOp, Func, Inst, InstKind, IntBinaryOp, Ty, Usable,
+    User, Value, ValueKind,
+};
+use crate::infra::linked_list::{LinkedListContainer, LinkedListNode};
+use crate::infra::storage::ArenaPtr;
+
+/// A violation found by [`verify`], with where it is found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyError {
+    /// The name of the function.
+    pub func: String,
+    /// The name of the block, if the error is in a block.
+    pub block: Option<String>,
+    /// The instruction in the textual format, if the error is in an
+    /// instruction.
+    pub inst: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "in @{}", self.func)?;
+        if let Some(block) = &self.block {
+            write!(f, ", {}", block)?;
+        }
+        if let Some(inst) = &self.inst {
+            write!(f, ", `{}`", inst)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Verify the context, returning all the violations found.
+pub fn verify(ctx: &Context) -> Result<(), Vec<VerifyError>> {
+    let mut verifier = Verifier {
+        ctx,
+        symbols: ctx
+            .globals()
+            .map(|global| global.name(ctx))
+            .chain(ctx.funcs().map(|func| func.name(ctx)))
+            .collect(),
+        funcs: ctx.funcs().map(|func| (func.name(ctx), func)).collect(),
+        errors: Vec::new(),
+    };
+
+    for func in ctx.funcs() {
+        if !func.is_declaration(ctx) {
+            verifier.verify_func(func);
+        }
+    }
+
+    if verifier.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(verifier.errors)
+    }
+}
+
+/// Verify the context in debug builds, and panic with all the violations
+/// found. This does nothing in release builds.
+pub fn debug_verify(ctx: &Context) {
+    if cfg!(debug_assertions) {
+        if let Err(errors) = verify(ctx) {
+            let errors: Vec<_> = errors.iter().map(|error| error.to_string()).collect();
+            panic!("invalid IR:\n{}", errors.join("\n"));
+        }
+    }
+}
+
+struct Verifier<'ctx> {
+    ctx: &'ctx Context,
+    /// The names of all the globals and functions.
+    symbols: HashSet<&'ctx str>,
+    /// The functions by name, to check the calls against.
+    funcs: HashMap<&'ctx str, Func>,
+    errors: Vec<VerifyError>,
+}
+
+/// The control flow information of a function.
+struct FuncInfo {
+    func: Func,
+    /// The predecessors of each block.
+    preds: HashMap<Block, HashSet<Block>>,
+    /// The dominators of each block.
+    ///
+    /// Unreachable blocks are dominated by all the blocks.
+    doms: HashMap<Block, HashSet<Block>>,
+    /// The block of each instruction and its position in the block.
+    positions: HashMap<Inst, (Block, usize)>,
+}
+
+impl FuncInfo {
+    fn new(ctx: &Context, func: Func) -> Self {
+        let blocks: Vec<Block> = func.iter(ctx).collect();
+
+        let mut preds: HashMap<Block, HashSet<Block>> =
+            blocks.iter().map(|&block| (block, HashSet::new())).collect();
+        let mut succs: HashMap<Block, Vec<Block>> = HashMap::new();
+        let mut positions = HashMap::new();
+        for &block in blocks.iter() {
+            for (pos, inst) in block.iter(ctx).enumerate() {
+                positions.insert(inst, (block, pos));
+            }
+            // Only the terminator at the end of the block counts.
+            if let Some(tail) = block.tail(ctx).filter(|tail| tail.is_terminator(ctx)) {
+                for succ in tail.successor_iter(ctx) {
+                    if let Some(succ_preds) = preds.get_mut(&succ) {
+                        succ_preds.insert(block);
+                        succs.entry(block).or_default().push(succ);
+                    }
+                }
+            }
+        }
+
+        // Compute the dominators of reachable blocks with the iterative
+        // data-flow algorithm.
+        let all: HashSet<Block> = blocks.iter().copied().collect();
+        let mut doms: HashMap<Block, HashSet<Block>> =
+            blocks.iter().map(|&block| (block, all.clone())).collect();
+        let mut reachable = Vec::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<Block> = blocks.first().copied().into_iter().collect();
+        while let Some(block) = stack.pop() {
+            if visited.insert(block) {
+                reachable.push(block);
+                stack.extend(succs.get(&block).into_iter().flatten());
+            }
+        }
+        if let Some(&entry) = reachable.first() {
+            doms.insert(entry, HashSet::from([entry]));
+        }
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &block in reachable.iter().skip(1) {
+                let mut new_doms = preds[&block]
+                    .iter()
+                    .filter(|pred| visited.contains(*pred))
+                    .map(|pred| &doms[pred])
+                    .fold(None, |acc: Option<HashSet<Block>>, pred_doms| match acc {
+                        None => Some(pred_doms.clone()),
+                        Some(acc) => Some(&acc & pred_doms),
+                    })
+                    .unwrap_or_default();
+                new_doms.insert(block);
+                if new_doms != doms[&block] {
+                    doms.insert(block, new_doms);
+                    changed = true;
+                }
+            }
+        }
+
+        Self {
+            func,
+            preds,
+            doms,
+            positions,
+        }
+    }
+
+    /// Check if block `a` dominates block `b`.
+    fn dominates(&self, a: Block, b: Block) -> bool { self.doms[&b].contains(&a) }
+}
+
+impl Verifier<'_> {
+    fn error(&mut self, func: Func, block: Option<Block>, inst: Option<Inst>, message: String) {
+        self.errors.push(VerifyError {
+            func: func.name(self.ctx).to_string(),
+            block: block.map(|block| block.name(self.ctx)),
+            inst: inst.map(|inst| inst.display(self.ctx).to_string()),
+            message,
+        });
+    }
+
+    fn verify_func(&mut self, func: Func) {
+        let ctx = self.ctx;
+        let blocks: Vec<Block> = func.iter(ctx).collect();
+        let Some(&entry) = blocks.first() else {
+            self.error(func, None, None, "function has no blocks".to_string());
+            return;
+        };
+
+        for (i, &param) in func.params(ctx).iter().enumerate() {
+            match param.kind(ctx) {
+                ValueKind::Param { func: f, index, .. } if *f == func && *index as usize == i => {}
+                _ => self.error(func, None, None, format!("invalid parameter #{}", i)),
+            }
+            self.verify_users(func, None, None, param);
+        }
+
+        let info = FuncInfo::new(ctx, func);
+        if !info.preds[&entry].is_empty() {
+            self.error(func, Some(entry), None, "entry block has predecessors".to_string());
+        }
+
+        for &block in blocks.iter() {
+            self.verify_block(&info, block);
+        }
+    }
+
+    fn verify_block(&mut self, info: &FuncInfo, block: Block) {
+        let ctx = self.ctx;
+        let func = info.func;
+
+        for user in block.users(ctx) {
+            if user.inst().try_deref(ctx).is_none() {
+                self.error(func, Some(block), None, "block has a deallocated user".to_string());
+            } else if user.inst().try_successor(ctx, user.idx()) != Some(block) {
+                let message = format!(
+                    "block is recorded to be used by `{}`, but it is not",
+                    user.inst().display(ctx)
+                );
+                self.error(func, Some(block), None, message);
+            }
+        }
+
+        let insts: Vec<Inst> = block.iter(ctx).collect();
+        match insts.last() {
+            None => self.error(func, Some(block), None, "block is empty".to_string()),
+            Some(&tail) if !tail.is_terminator(ctx) => {
+                let message = "block does not end with a terminator".to_string();
+                self.error(func, Some(block), Some(tail), message);
+            }
+            _ => {}
+        }
+
+        let mut after_phis = false;
+        for (pos, &inst) in insts.iter().enumerate() {
+            if inst.container(ctx) != Some(block) {
+                let message = "instruction is not linked to the block".to_string();
+                self.error(func, Some(block), Some(inst), message);
+            }
+            if inst.is_terminator(ctx) && pos + 1 != insts.len() {
+                let message = "terminator in the middle of the block".to_string();
+                self.error(func, Some(block), Some(inst), message);
+            }
+            if inst.is_phi(ctx) {
+                if after_phis {
+                    let message = "phi node after non-phi instructions".to_string();
+                    self.error(func, Some(block), Some(inst), message);
+                }
+            } else {
+                after_phis = true;
+            }
+            self.verify_inst(info, block, inst);
+        }
+    }
+
+    fn verify_inst(&mut self, info: &FuncInfo, block: Block, inst: Inst) {
+        let ctx = self.ctx;
+        let func = info.func;
+        let error = |this: &mut Self, message: String| {
+            this.error(func, Some(block), Some(inst), message);
+        };
+
+        if let Some(result) = inst.result(ctx) {
+            match result.kind(ctx) {
+                ValueKind::InstResult { inst: def, .. } if *def == inst => {}
+                _ => error(self, "result is not defined by the instruction".to_string()),
+            }
+            self.verify_users(func, Some(block), Some(inst), result);
+        }
+
+        for (idx, value) in inst.indexed_operand_iter(ctx) {
+            if value.try_deref(ctx).is_none() {
+                error(self, format!("operand #{} is deallocated", idx));
+                continue;
+            }
+            if !value.users(ctx).into_iter().any(|user| user == User::new(inst, idx)) {
+                error(self, format!("operand #{} does not record this use", idx));
+            }
+            match value.kind(ctx) {
+                ValueKind::InstResult { inst: def, .. } => {
+                    if !info.positions.contains_key(def) {
+                        let message = format!("operand #{} is not defined in the function", idx);
+                        error(self, message);
+                    } else if !inst.is_phi(ctx) && !self.dominates(info, *def, inst) {
+                        let message = format!(
+                            "operand `{}` is not dominated by its definition",
+                            value.display(ctx, false)
+                        );
+                        error(self, message);
+                    }
+                }
+                ValueKind::Param { func: f, .. } if *f != func => {
+                    let message = format!("operand #{} is a parameter of another function", idx);
+                    error(self, message);
+                }
+                ValueKind::Constant {
+                    value: ConstantValue::GlobalRef { name, .. },
+                } if !self.symbols.contains(name.as_str()) => {
+                    error(self, format!("undefined global `@{}`", name));
+                }
+                _ => {}
+            }
+        }
+
+        for (idx, succ) in inst.indexed_successor_iter(ctx) {
+            if succ.try_deref(ctx).is_none() {
+                error(self, format!("successor #{} is deallocated", idx));
+                continue;
+            }
+            if !succ.users(ctx).into_iter().any(|user| user == User::new(inst, idx)) {
+                error(self, format!("successor #{} does not record this use", idx));
+            }
+            if succ.container(ctx) != Some(func) {
+                error(self, format!("successor #{} is not in the function", idx));
+            }
+        }
+
+        if let Err(message) = self.verify_types(info, block, inst) {
+            error(self, message);
+        }
+    }
+
+    /// Check that the users of the value are in sync with the operands.
+    fn verify_users(&mut self, func: Func, block: Option<Block>, inst: Option<Inst>, value: Value) {
+        let ctx = self.ctx;
+        for user in value.users(ctx) {
+            let message = if user.inst().try_deref(ctx).is_none() {
+                "value has a deallocated user".to_string()
+            } else if user.inst().try_operand(ctx, user.idx()) != Some(value) {
+                format!(
+                    "value is recorded to be used by `{}`, but it is not",
+                    user.inst().display(ctx)
+                )
+            } else if user.inst().container(ctx).and_then(|b| b.container(ctx)) != Some(func) {
+                format!(
+                    "value is used by `{}` outside of the function",
+                    user.inst().display(ctx)
+                )
+            } else {
+                continue;
+            };
+            self.error(func, block, inst, message);
+        }
+    }
+
+    /// Check if the definition `def` dominates the instruction `user`.
+    fn dominates(&self, info: &FuncInfo, def: Inst, user: Inst) -> bool {
+        let (def_block, def_pos) = info.positions[&def];
+        let (user_block, user_pos) = info.positions[&user];
+        if def_block == user_block {
+            def_pos < user_pos
+        } else {
+            info.dominates(def_block, user_block)
+        }
+    }
+
+    /// Check the types of the operands and the result, and the incoming blocks
+    /// of phi nodes.
+    fn verify_types(&self, info: &FuncInfo, block: Block, inst: Inst) -> Result<(), String> {
+        let ctx = self.ctx;
+        let display = |ty: Ty| ty.display(ctx);
+        let operands: Vec<Value> = inst.operand_iter(ctx).collect();
+        let result_ty = inst.result(ctx).map(|result| result.ty(ctx));
+
+        let expect_operands = |n: usize| {
+            if operands.len() == n {
+                Ok(())
+            } else {
+                Err(format!("expected {} operands, found {}", n, operands.len()))
+            }
+        };
+        let expect_successors = |n: usize| {
+            let found = inst.successor_iter(ctx).count();
+            if found == n {
+                Ok(())
+            } else {
+                Err(format!("expected {} successors, found {}", n, found))
+            }
+        };
+
+        match inst.kind(ctx) {
+            InstKind::Alloca { .. } => expect_operands(0)?,
+            InstKind::Phi => {
+                let ty = result_ty.unwrap();
+                let mut incoming_blocks = HashSet::new();
+                for (incoming, value) in inst.incoming_iter(ctx) {
+                    if value.ty(ctx) != ty {
+                        return Err(format!(
+                            "incoming value from {} is of type `{}`, expected `{}`",
+                            incoming.name(ctx),
+                            display(value.ty(ctx)),
+                            display(ty)
+                        ));
+                    }
+                    if let ValueKind::InstResult { inst: def, .. } = value.kind(ctx) {
+                        // The definition should dominate the end of the
+                        // incoming block.
+                        let dominated = match info.positions.get(def) {
+                            Some(&(def_block, _)) => {
+                                !info.preds.contains_key(&incoming)
+                                    || info.dominates(def_block, incoming)
+                            }
+                            None => true,
+                        };
+                        if !dominated {
+                            return Err(format!(
+                                "incoming value `{}` from {} is not dominated by its definition",
+                                value.display(ctx, false),
+                                incoming.name(ctx)
+                            ));
+                        }
+                    }
+                    incoming_blocks.insert(incoming);
+                }
+                let preds = &info.preds[&block];
+                if let Some(pred) = preds.iter().find(|pred| !incoming_blocks.contains(pred)) {
+                    return Err(format!("missing incoming value from {}", pred.name(ctx)));
+                }
+                if let Some(incoming) = incoming_blocks.iter().find(|b| !preds.contains(b)) {
+                    return Err(format!("{} is not a predecessor", incoming.name(ctx)));
+                }
+            }
+            InstKind::Load => {
+                expect_operands(1)?;
+                if !operands[0].ty(ctx).is_ptr(ctx) {
+                    return Err(format!("load from `{}`", display(operands[0].ty(ctx))));
+                }
+            }
+            InstKind::Store => {
+                expect_operands(2)?;
+                if !operands[1].ty(ctx).is_ptr(ctx) {
+                    return Err(format!("store to `{}`", display(operands[1].ty(ctx))));
+                }
+            }
+            InstKind::GetElementPtr { .. } => {
+                if operands.is_empty() {
+                    return Err("expected a pointer operand".to_string());
+                }
+                if !operands[0].ty(ctx).is_ptr(ctx) {
+                    return Err(format!(
+                        "getelementptr on `{}`",
+                        display(operands[0].ty(ctx))
+                    ));
+                }
+                if let Some(idx) = operands[1..].iter().find(|idx| !idx.ty(ctx).is_int(ctx)) {
+                    return Err(format!("index of type `{}`", display(idx.ty(ctx))));
+                }
+            }
+            InstKind::Call => {
+                let callee = match operands.first().map(|callee| callee.kind(ctx)) {
+                    Some(ValueKind::Constant {
+                        value: ConstantValue::GlobalRef { name, .. },
+                    }) => match self.funcs.get(name.as_str()) {
+                        Some(&callee) => callee,
+                        None => return Err(format!("callee `@{}` is not a function", name)),
+                    },
+                    _ => return Err("expected a function as the callee".to_string()),
+                };
+                let params = callee.params(ctx);
+                let args = &operands[1..];
+                let arity_ok = if callee.is_variadic(ctx) {
+                    args.len() >= params.len()
+                } else {
+                    args.len() == params.len()
+                };
+                if !arity_ok {
+                    return Err(format!(
+                        "wrong number of arguments to `@{}`: expected {}{}, found {}",
+                        callee.name(ctx),
+                        if callee.is_variadic(ctx) { "at least " } else { "" },
+                        params.len(),
+                        args.len()
+                    ));
+                }
+                for (i, (arg, param)) in args.iter().zip(params).enumerate() {
+                    if arg.ty(ctx) != param.ty(ctx) {
+                        return Err(format!(
+                            "argument #{} is of type `{}`, expected `{}`",
+                            i,
+                            display(arg.ty(ctx)),
+                            display(param.ty(ctx))
+                        ));
+                    }
+                }
+                // The default argument promotions of C
+                for (i, arg) in args.iter().enumerate().skip(params.len()) {
+                    if arg.ty(ctx).is_float(ctx) {
+                        return Err(format!(
+                            "variadic argument #{} is of type `f32`, expected `f64`",
+                            i
+                        ));
+                    }
+                }
+                let ret_ty = callee.ret_ty(ctx);
+                let ret_ok = match result_ty {
+                    Some(ty) => ty == ret_ty,
+                    None => ret_ty.is_void(ctx),
+                };
+                if !ret_ok {
+                    return Err(format!(
+                        "result type does not match `{}` returned by `@{}`",
+                        display(ret_ty),
+                        callee.name(ctx)
+                    ));
+                }
+            }
+            InstKind::Br => {
+                expect_operands(0)?;
+                expect_successors(1)?;
+            }
+            InstKind::CondBr => {
+                expect_operands(1)?;
+                expect_successors(2)?;
+                if !operands[0].ty(ctx).is_i1(ctx) {
+                    return Err(format!(
+                        "condition is of type `{}`, expected `i1`",
+                        display(operands[0].ty(ctx))
+                    ));
+                }
+            }
+            InstKind::Ret => {
+                let ret_ty = info.func.ret_ty(ctx);
+                match operands.as_slice() {
+                    [] if ret_ty.is_void(ctx) => {}
+                    [val] if val.ty(ctx) == ret_ty => {}
+                    [val] => {
+                        return Err(format!(
+                            "returning `{}` from a function returning `{}`",
+                            display(val.ty(ctx)),
+                            display(ret_ty)
+                        ))
+                    }
+                    _ => {
+                        return Err(format!(
+                            "expected a return value of type `{}`",
+                            display(ret_ty)
+                        ))
+                    }
+                }
+            }
+            InstKind::IntBinary { op } => {
+                expect_operands(2)?;
+                let (lhs, rhs) = (operands[0].ty(ctx), operands[1].ty(ctx));
+                if lhs != rhs {
+                    return Err(format!(
+                        "operand types of {} do not match: `{}` and `{}`",
+                        op,
+                        display(lhs),
+                        display(rhs)
+                    ));
+                }
+                let (valid, expected) = match op {
+                    IntBinaryOp::ICmp { .. } => (lhs.is_int(ctx) || lhs.is_ptr(ctx), None),
+                    _ => (lhs.is_int(ctx), Some(lhs)),
+                };
+                if !valid {
+                    return Err(format!("invalid operand type `{}` of {}", display(lhs), op));
+                }
+                self.expect_result_ty(result_ty.unwrap(), expected)?;
+            }
+            InstKind::FloatBinary { op } => {
+                expect_operands(2)?;
+                let (lhs, rhs) = (operands[0].ty(ctx), operands[1].ty(ctx));
+                if lhs != rhs {
+                    return Err(format!(
+                        "operand types of {} do not match: `{}` and `{}`",
+                        op,
+                        display(lhs),
+                        display(rhs)
+                    ));
+                }
+                if !lhs.is_float(ctx) {
+                    return Err(format!("invalid operand type `{}` of {}", display(lhs), op));
+                }
+                let expected = match op {
+                    FloatBinaryOp::FCmp { .. } => None,
+                    _ => Some(lhs),
+                };
+                self.expect_result_ty(result_ty.unwrap(), expected)?;
+            }
+            InstKind::FNeg => {
+                expect_operands(1)?;
+                let ty = operands[0].ty(ctx);
+                if !ty.is_float(ctx) {
+                    return Err(format!("invalid operand type `{}` of fneg", display(ty)));
+                }
+                self.expect_result_ty(result_ty.unwrap(), Some(ty))?;
+            }
+            InstKind::Cast { op } => {
+                expect_operands(1)?;
+                let (from, to) = (operands[0].ty(ctx), result_ty.unwrap());
+                if !op.is_valid(ctx, from, to) {
+                    return Err(format!(
+                        "invalid cast: {} from `{}` to `{}`",
+                        op,
+                        display(from),
+                        display(to)
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the result type, `None` means `i1`.
+    fn expect_result_ty(&self, result_ty: Ty, expected: Option<Ty>) -> Result<(), String> {
+        let ctx = self.ctx;
+        let valid = match expected {
+            Some(ty) => result_ty == ty,
+            None => result_ty.is_i1(ctx),
+        };
+        if valid {
+            Ok(())
+        } else {
+            let expected = match expected {
+                Some(ty) => ty.display(ctx).to_string(),
+                None => "i1".to_string(),
+            };
+            Err(format!(
+                "result is of type `{}`, expected `{}`",
+                result_ty.display(ctx),
+                expected
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::parse;
+
+    fn errors(src: &str) -> Vec<String> {
+        let ctx = parse(src).unwrap();
+        match verify(&ctx) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors.iter().map(|error| error.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_verify_valid() {
+        let src = "\
+@a = global [2 x i32] zeroinitializer
+
+declare i32 @getint()
+
+define i32 @main(i32 %v1) {
+bb_0:
+\t%v2 = call i32 @getint()
+\t%v3 = getelementptr [2 x i32], ptr @a, i32 0, i32 1
+\tstore i32 %v2, ptr %v3
+\tbr label %bb_1
+bb_1:
+\t%v4 = phi i32 [0, %bb_0], [%v5, %bb_2]
+\t%v6 = icmp slt i32 %v4, %v1
+\tbr i1 %v6, label %bb_2, label %bb_3
+bb_2:
+\t%v5 = add i32 %v4, 1
+\tbr label %bb_1
+bb_3:
+\tret i32 %v4
+bb_4:
+\t; Unreachable blocks are dominated by all the blocks.
+\t%v7 = add i32 %v5, 1
+\tbr label %bb_4
+}
+";
+        assert_eq!(errors(src), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_verify_terminators() {
+        let src = "\
+define void @f() {
+bb_0:
+\tret void
+\tbr label %bb_1
+bb_1:
+\t%v2 = add i32 1, 2
+}
+";
+        assert_eq!(
+            errors(src),
+            vec![
+                "in @f, %bb_0, `ret void`: terminator in the middle of the block",
+                "in @f, %bb_1, `%v2 = add i32 1, 2`: block does not end with a terminator",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_phi() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\tbr label %bb_2
+bb_2:
+\t%v4 = add i32 1, 2
+\t%v1 = phi i32 [0, %bb_0], [1, %bb_3]
+\tret i32 %v1
+bb_3:
+\tbr label %bb_0
+}
+";
+        assert_eq!(
+            errors(src),
+            vec![
+                "in @f, %bb_0: entry block has predecessors",
+                "in @f, %bb_2, `%v1 = phi i32 [0, %bb_0], [1, %bb_3]`: \
+                 phi node after non-phi instructions",
+                "in @f, %bb_2, `%v1 = phi i32 [0, %bb_0], [1, %bb_3]`: \
+                 missing incoming value from %bb_1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_dominance() {
+        let src = "\
+define i32 @f(i1 %v0) {
+bb_0:
+\tbr i1 %v0, label %bb_1, label %bb_2
+bb_1:
+\t%v4 = add i32 1, 2
+\tbr label %bb_2
+bb_2:
+\t%v6 = add i32 %v4, 1
+\t%v1 = phi i32 [%v4, %bb_0], [%v4, %bb_1]
+\tret i32 %v6
+}
+";
+        assert_eq!(
+            errors(src),
+            vec![
+                "in @f, %bb_2, `%v6 = add i32 %v4, 1`: \
+                 operand `%v4` is not dominated by its definition",
+                "in @f, %bb_2, `%v1 = phi i32 [%v4, %bb_0], [%v4, %bb_1]`: \
+                 phi node after non-phi instructions",
+                "in @f, %bb_2, `%v1 = phi i32 [%v4, %bb_0], [%v4, %bb_1]`: \
+                 incoming value `%v4` from %bb_0 is not dominated by its definition",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_types() {
+        let src = "\
+define i32 @f(i32 %v0, f32 %v1) {
+bb_0:
+\t%v2 = load i32, i32 %v0
+\tbr i32 %v0, label %bb_1, label %bb_1
+bb_1:
+\tret f32 %v1
+}
+";
+        assert_eq!(
+            errors(src),
+            vec![
+                "in @f, %bb_0, `%v2 = load i32, i32 %v0`: load from `i32`",
+                "in @f, %bb_0, `br i32 %v0, label %bb_1, label %bb_1`: \
+                 condition is of type `i32`, expected `i1`",
+                "in @f, %bb_1, `ret f32 %v1`: returning `f32` from a function returning `i32`",
+            ]
+        );
+
+        // The builders of integer binary operations do not check the types.
+        let mut ctx = Context::default();
+        let i32 = Ty::i32(&mut ctx);
+        let f32 = Ty::f32(&mut ctx);
+        let func = Func::new(&mut ctx, "g".to_string(), i32);
+        let block = Block::new(&mut ctx);
+        func.push_back(&mut ctx, block).unwrap();
+        let x = func.add_param(&mut ctx, i32);
+        let y = func.add_param(&mut ctx, f32);
+        let add = Inst::add(&mut ctx, x, y, i32);
+        let sub = Inst::sub(&mut ctx, x, x, f32);
+        let ret = Inst::ret(&mut ctx, Some(x));
+        for inst in [add, sub, ret] {
+            block.push_back(&mut ctx, inst).unwrap();
+        }
+
+        let errors: Vec<_> = verify(&ctx).unwrap_err().into_iter().map(|e| e.message).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "operand types of add do not match: `i32` and `f32`",
+                "result is of type `f32`, expected `i32`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_call() {
+        let src = "\
+@a = global i32 0
+
+declare i32 @getint()
+
+declare void @putint(i32)
+
+declare void @putf(ptr, ...)
+
+define void @f(i32 %v2, f32 %v3, ptr %v4) {
+bb_0:
+\tcall void @putint(i32 %v2)
+\tcall void @putf(ptr %v4, i32 %v2, f32 %v3)
+\tcall void @putint()
+\tcall void @putint(f32 %v3)
+\tcall void @putf()
+\t%v11 = call i32 @putint(i32 %v2)
+\tcall void @getint()
+\tcall void @a()
+\tret void
+}
+";
+        assert_eq!(
+            errors(src),
+            vec![
+                "in @f, %bb_0, `call void @putf(ptr %v4, i32 %v2, f32 %v3)`: \
+                 variadic argument #2 is of type `f32`, expected `f64`",
+                "in @f, %bb_0, `call void @putint()`: \
+                 wrong number of arguments to `@putint`: expected 1, found 0",
+                "in @f, %bb_0, `call void @putint(f32 %v3)`: \
+                 argument #0 is of type `f32`, expected `i32`",
+                "in @f, %bb_0, `call void @putf()`: \
+                 wrong number of arguments to `@putf`: expected at least 1, found 0",
+                "in @f, %bb_0, `%v11 = call i32 @putint(i32 %v2)`: \
+                 result type does not match `void` returned by `@putint`",
+                "in @f, %bb_0, `call void @getint()`: \
+                 result type does not match `i32` returned by `@getint`",
+                "in @f, %bb_0, `call void @a()`: callee `@a` is not a function",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_verify_users() {
+        let src = "\
+define i32 @f(i32 %v0) {
+bb_0:
+\t%v2 = add i32 %v0, 1
+\tret i32 %v2
+}
+";
+        let mut ctx = parse(src).unwrap();
+        let func = ctx.funcs().next().unwrap();
+        let param = func.params(&ctx)[0];
+        let add = func.head(&ctx).unwrap().head(&ctx).unwrap();
+
+        // Drop the use of the parameter, and record a bogus use instead.
+        param.remove_user(&mut ctx, User::new(add, 0));
+        param.insert_user(&mut ctx, User::new(add, 1));
+
+        let errors: Vec<_> = verify(&ctx).unwrap_err().into_iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "in @f: value is recorded to be used by `%v2 = add i32 %v0, 1`, but it is not",
+                "in @f, %bb_0, `%v2 = add i32 %v0, 1`: operand #0 does not record this use",
+            ]
+        );
+    }
+}
@@ -1,14 +1,43 @@
-use nkucc::frontend::{irgen, preprocess, SysYParser};
+use nkucc::frontend::{irgen, preprocess_with_map, SysYParser};
+use nkucc::ir::passes::{OptLevel, PassManager};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Hello, NKUCC!");
 
-    let src = std::fs::read_to_string("tests/sysy/basic.sy")?;
-    let src = preprocess(&src);
+    // nkucc [-O0|-O1|-O2] [path]
+    let mut path = String::from("tests/sysy/basic.sy");
+    let mut opt_level = OptLevel::default();
+    for arg in std::env::args().skip(1) {
+        match OptLevel::from_flag(&arg) {
+            Some(level) => opt_level = level,
+            None => path = arg,
+        }
+    }
+    let path = path.as_str();
 
-    let mut ast = SysYParser::new().parse(&src).unwrap();
+    let src = std::fs::read_to_string(path)?;
+    let (preprocessed, source_map) = preprocess_with_map(&src);
 
-    ast.type_check();
+    let mut ast = match SysYParser::new().parse(&preprocessed) {
+        Ok(ast) => ast,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            std::process::exit(1);
+        }
+    };
+
+    match ast.type_check() {
+        Ok(mut warnings) => {
+            warnings.map_spans(|span| source_map.map_span(span));
+            eprint!("{}", warnings.render(path, &src));
+        }
+        Err(mut diagnostics) => {
+            // Report the errors with the original source
+            diagnostics.map_spans(|span| source_map.map_span(span));
+            eprint!("{}", diagnostics.render(path, &src));
+            std::process::exit(1);
+        }
+    }
 
     println!(
         "{} {} {}",
@@ -19,16 +48,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("{}", ast);
 
-    // let ir = irgen(&ast, 8);
+    let mut ir = irgen(&ast, 8);
+    PassManager::with_opt_level(opt_level).run(&mut ir);
 
-    // println!(
-    //     "\n{} {} {}",
-    //     "=".repeat(12),
-    //     "Intermediate Representation",
-    //     "=".repeat(12)
-    // );
+    println!(
+        "\n{} {} ({}) {}",
+        "=".repeat(12),
+        "Intermediate Representation",
+        opt_level,
+        "=".repeat(12)
+    );
 
-    // println!("{}", ir);
+    println!("{}", ir);
 
     Ok(())
 }